@@ -0,0 +1,50 @@
+
+// --- Beacon Node API ---
+//
+// REST helpers for the consensus client (BEACON_API_URL). Responses wrap
+// their payload in a top-level 'data' field, which these helpers unwrap.
+
+use serde_json::Value;
+
+use crate::rpc::RpcResult;
+
+pub const SLOTS_PER_EPOCH: u64 = 32;
+
+/// GET `{base}{path}` and return the 'data' field
+pub async fn beacon_get(client: &reqwest::Client, base: &str, path: &str) -> RpcResult<Value> {
+    let resp = client.get(format!("{}{}", base.trim_end_matches('/'), path))
+        .send()
+        .await?
+        .error_for_status()?;
+    unwrap_data(resp.json().await?)
+}
+
+/// POST `body` to `{base}{path}` and return the 'data' field
+pub async fn beacon_post(client: &reqwest::Client, base: &str, path: &str, body: &Value) -> RpcResult<Value> {
+    let resp = client.post(format!("{}{}", base.trim_end_matches('/'), path))
+        .json(body)
+        .send()
+        .await?
+        .error_for_status()?;
+    unwrap_data(resp.json().await?)
+}
+
+fn unwrap_data(mut body: Value) -> RpcResult<Value> {
+    body.get_mut("data")
+        .map(Value::take)
+        .ok_or_else(|| "Invalid response: 'data' field missing".into())
+}
+
+/// Reads a field the Beacon API encodes as a decimal string (e.g. "32000000000")
+pub fn parse_decimal(value: &Value) -> Option<i64> {
+    value.as_str()?.parse().ok()
+}
+
+/// Fetches the current head slot
+pub async fn fetch_head_slot(client: &reqwest::Client, base: &str) -> RpcResult<u64> {
+    let header = beacon_get(client, base, "/eth/v1/beacon/headers/head").await?;
+    header.pointer("/header/message/slot")
+        .and_then(parse_decimal)
+        .map(|slot| slot as u64)
+        .ok_or_else(|| "Invalid response: head slot missing".into())
+}
@@ -0,0 +1,3022 @@
+
+// --- Secondary Checks ---
+//
+// The block number comparison runs every poll cycle. Everything else (peer
+// count, fee history, txpool, ...) is a secondary check that runs every
+// `interval_multiplier` cycles to keep the RPC load down.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::beacon::{beacon_get, beacon_post, fetch_head_slot, parse_decimal, SLOTS_PER_EPOCH};
+use crate::clientlog;
+use crate::config::{parse_env, Config};
+use crate::discovery;
+use crate::diskio;
+use crate::error::AppError;
+use crate::ethash;
+use crate::notify::AlertState;
+use crate::proof;
+use crate::routing::{route_request, MethodRouting};
+use crate::rpc::{fetch_blob_count, fetch_block, fetch_block_number, fetch_block_traces, fetch_finalized_block, fetch_goroutine_count, fetch_median_rewards, fetch_peer_details, fetch_peers, fetch_protocol_version, fetch_quantity, fetch_sync_status, fetch_uncle, fetch_uncle_count, is_method_not_found, parse_hex_bytes, quantity_field, rpc_batch, rpc_call, PeerDetails, RpcResult, SyncStatus};
+use crate::state::State;
+use crate::ws::{log_filter, parse_log};
+
+/// Scheduling options shared by every secondary check
+#[derive(Clone, Copy, Debug)]
+pub struct CheckConfig {
+    /// Run the check every N poll cycles (1 = every cycle)
+    pub interval_multiplier: u64,
+    /// Disable the check if its first run finds the method unsupported by the
+    /// client; any other failure then raises an alert instead of only a log line
+    pub check_availability: bool,
+}
+
+impl CheckConfig {
+    /// Reads `{PREFIX}_INTERVAL_MULTIPLIER` (default 1) and `{PREFIX}_CHECK_AVAILABILITY` (default false)
+    pub fn from_env(prefix: &str) -> Self {
+        Self::from_keys(prefix, &format!("{}_INTERVAL_MULTIPLIER", prefix))
+    }
+
+    /// Like `from_env`, for expensive checks that shouldn't run every cycle by default
+    pub fn from_env_or(prefix: &str, default_multiplier: u64) -> Self {
+        Self::from_keys_or(prefix, &format!("{}_INTERVAL_MULTIPLIER", prefix), default_multiplier)
+    }
+
+    /// Like `from_env`, with the multiplier read from an explicitly named variable
+    pub fn from_keys(prefix: &str, multiplier_key: &str) -> Self {
+        Self::from_keys_or(prefix, multiplier_key, 1)
+    }
+
+    /// Like `from_keys`, with a default multiplier other than 1
+    pub fn from_keys_or(prefix: &str, multiplier_key: &str, default_multiplier: u64) -> Self {
+        let interval_multiplier = parse_env(multiplier_key, default_multiplier);
+        if interval_multiplier == 0 {
+            eprintln!("Error: {} must be at least 1", multiplier_key);
+            std::process::exit(1);
+        }
+        let check_availability = parse_env(&format!("{}_CHECK_AVAILABILITY", prefix), false);
+        CheckConfig { interval_multiplier, check_availability }
+    }
+
+    /// Returns true if the check should run on the given poll cycle
+    pub fn is_due(&self, cycle: u64) -> bool {
+        cycle.is_multiple_of(self.interval_multiplier)
+    }
+}
+
+/// Context handed to each check when it runs
+pub struct CheckContext<'a> {
+    pub client: &'a reqwest::Client,
+    pub rpc_url: &'a str,
+    pub routing: Option<&'a MethodRouting>,
+}
+
+impl<'a> CheckContext<'a> {
+    /// Endpoint for a JSON-RPC method: its METHOD_ROUTING route, or the local node
+    pub fn url_for(&self, method: &str) -> &'a str {
+        match self.routing {
+            Some(routing) => route_request(method, routing, self.rpc_url),
+            None => self.rpc_url,
+        }
+    }
+}
+
+/// What a check found
+#[derive(Debug, PartialEq)]
+pub enum CheckOutcome {
+    /// Nothing wrong: the line is printed to the terminal only
+    Ok(String),
+    /// Problem detected: the line is printed and sent as an alert
+    Alert(String),
+}
+
+#[async_trait]
+pub trait Check: Send {
+    /// Short name used in log lines
+    fn name(&self) -> &'static str;
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome>;
+}
+
+/// A check together with its schedule and alert cooldown state
+pub struct ScheduledCheck {
+    pub check: Box<dyn Check>,
+    pub config: CheckConfig,
+    pub alert_state: AlertState,
+    /// False once the client turned out not to support the check
+    pub available: bool,
+    has_run: bool,
+}
+
+impl ScheduledCheck {
+    fn new(check: impl Check + 'static, config: CheckConfig) -> Self {
+        ScheduledCheck { check: Box::new(check), config, alert_state: AlertState::default(), available: true, has_run: false }
+    }
+
+    /// Runs the check if it is due and available. Failures are logged, or with
+    /// `check_availability` turned into an alert (or disable the check on its
+    /// first run when the client lacks the method or field it reads).
+    pub async fn run_if_due(&mut self, ctx: &CheckContext<'_>, cycle: u64) -> Option<CheckOutcome> {
+        if !self.available || !self.config.is_due(cycle) {
+            return None;
+        }
+        let result = self.check.run(ctx).await;
+        let first_run = !std::mem::replace(&mut self.has_run, true);
+        match result {
+            Ok(outcome) => Some(outcome),
+            Err(e) => self.handle_error(&e, first_run),
+        }
+    }
+
+    fn handle_error(&mut self, e: &AppError, first_run: bool) -> Option<CheckOutcome> {
+        let name = self.check.name();
+        if !self.config.check_availability {
+            error!("[ERROR] Check '{}' failed: {}", name, e);
+            None
+        } else if first_run && (is_method_not_found(e) || matches!(e, AppError::NotReported(_))) {
+            warn!("[WARN] Check '{}' is not supported by this client, disabling it: {}", name, e);
+            self.available = false;
+            None
+        } else {
+            Some(CheckOutcome::Alert(tr!("check_failed", check = name, error = e)))
+        }
+    }
+}
+
+/// Builds the list of enabled secondary checks from the configuration
+pub fn build_checks(config: &Config) -> Vec<ScheduledCheck> {
+    let mut checks = Vec::new();
+
+    if let Some(min_peers) = config.min_peer_count {
+        checks.push(ScheduledCheck::new(PeerCountCheck { min_peers }, config.peer_count_check));
+    }
+
+    if config.peer_direction_enabled {
+        let check = PeerDirectionCheck { min_outbound: config.min_outbound_peers };
+        checks.push(ScheduledCheck::new(check, config.peer_direction_check));
+    }
+
+    if config.peer_ratio_enabled {
+        let check = PeerRatioCheck { min_ratio_pct: config.min_peer_ratio_pct };
+        checks.push(ScheduledCheck::new(check, config.peer_ratio_check));
+    }
+
+    if config.rpc_connections_enabled {
+        let check = RpcConnectionsCheck { warn_pct: config.max_rpc_connections_warn_pct, max_connections: config.rpc_max_connections };
+        checks.push(ScheduledCheck::new(check, config.rpc_connections_check));
+    }
+
+    if config.engine_connections_enabled {
+        let check = EngineConnectionsCheck { expected: config.expected_engine_api_connections };
+        checks.push(ScheduledCheck::new(check, config.engine_connections_check));
+    }
+
+    if let Some(max_subscriptions) = config.max_ws_subscriptions_warn {
+        checks.push(ScheduledCheck::new(WsSubscriptionsCheck { max_subscriptions }, config.ws_subscriptions_check));
+    }
+
+    if let Some(max_goroutines) = config.max_goroutine_count_warn {
+        checks.push(ScheduledCheck::new(GoroutineCountCheck { max_goroutines }, config.goroutine_count_check));
+    }
+
+    if config.p2p_discoverability_enabled {
+        let check = DiscoverabilityCheck { probe_host: config.p2p_probe_host };
+        checks.push(ScheduledCheck::new(check, config.p2p_discoverability_check));
+    }
+
+    if config.bootnode_check_enabled {
+        let check = BootnodeCheck { bootnodes: config.bootnodes.clone(), alert: config.bootnode_alert };
+        checks.push(ScheduledCheck::new(check, config.bootnode_check));
+    }
+
+    if !config.required_peers.is_empty() {
+        let check = RequiredPeersCheck { required: config.required_peers.clone(), attempts: HashMap::new() };
+        checks.push(ScheduledCheck::new(check, config.required_peers_check));
+    }
+
+    if let Some(call) = &config.contract_call {
+        let check = ContractCallCheck {
+            address: call.address.clone(),
+            data: call.data.clone(),
+            expected: call.expected_result.clone(),
+        };
+        checks.push(ScheduledCheck::new(check, config.contract_call_check));
+    }
+
+    if let Some(address) = &config.state_proof_address {
+        let check = StateProofCheck { address: address.clone() };
+        checks.push(ScheduledCheck::new(check, config.state_proof_check));
+    }
+
+    if let Some(block) = config.archive_check_block {
+        checks.push(ScheduledCheck::new(ArchiveCheck { block }, config.archive_check));
+    }
+
+    if config.chain_type == ChainType::PolygonZkevm {
+        let check = ZkevmBatchCheck { blocks_per_batch: config.zkevm_blocks_per_batch, threshold: config.batch_lag_threshold };
+        checks.push(ScheduledCheck::new(check, config.zkevm_batch_check));
+    }
+
+    if let Some(minimum) = &config.min_client_version {
+        let check = ClientVersionCheck { minimum: minimum.clone() };
+        checks.push(ScheduledCheck::new(check, config.client_version_check));
+    }
+
+    if let Some(minimum) = config.min_evm_version {
+        checks.push(ScheduledCheck::new(EvmVersionCheck { minimum }, config.evm_version_check));
+    }
+
+    if let Some(expected) = config.expected_mining_state {
+        checks.push(ScheduledCheck::new(MiningCheck { expected }, config.mining_check));
+    }
+
+    if let Some(expected) = config.expected_gas_limit {
+        let check = GasLimitCheck { expected, max_deviation_pct: config.gas_limit_deviation_pct };
+        checks.push(ScheduledCheck::new(check, config.gas_limit_check));
+    }
+
+    if config.header_chain_verify {
+        checks.push(ScheduledCheck::new(HeaderChainCheck, config.header_chain_check));
+    }
+
+    if config.future_timestamp_enabled {
+        let check = FutureTimestampCheck { max_future_secs: config.max_future_block_secs };
+        checks.push(ScheduledCheck::new(check, config.future_timestamp_check));
+    }
+
+    if let Some(max_age) = config.max_pending_tx_age_secs {
+        let check = PendingTxAgeCheck { max_age: chrono::Duration::seconds(max_age as i64), first_seen: HashMap::new(), supported: true };
+        checks.push(ScheduledCheck::new(check, config.txpool_check));
+    }
+
+    for (address, max_gap) in &config.pending_nonce_addresses {
+        let check = PendingNonceCheck { address: address.clone(), max_gap: *max_gap };
+        checks.push(ScheduledCheck::new(check, config.pending_nonce_check));
+    }
+
+    if let Some(url) = &config.local_health_endpoint {
+        let check = HealthEndpointCheck { url: url.clone() };
+        checks.push(ScheduledCheck::new(check, config.health_endpoint_check));
+    }
+
+    if let Some(probe_url) = &config.external_probe_url {
+        let check = ExternalProbeCheck {
+            probe_url: probe_url.clone(),
+            target: probe_target(&config.local_rpc).expect("validated in Config::from_env"),
+        };
+        checks.push(ScheduledCheck::new(check, config.external_probe_check));
+    }
+
+    if let Some(secret) = &config.jwt_secret {
+        let check = JwtAuthCheck { engine_url: config.engine_api_url.clone(), secret: secret.clone() };
+        checks.push(ScheduledCheck::new(check, config.jwt_auth_check));
+    }
+
+    if config.tx_success_rate_enabled {
+        let check = TxSuccessRateCheck { blocks: config.tx_success_rate_blocks, min_success_pct: config.min_tx_success_rate_pct };
+        checks.push(ScheduledCheck::new(check, config.tx_success_rate_check));
+    }
+
+    if config.uncle_rate_enabled {
+        let check = UncleRateCheck { max_rate_pct: config.max_uncle_rate_pct };
+        checks.push(ScheduledCheck::new(check, config.uncle_rate_check));
+    }
+
+    if let Some(max_ratio_pct) = config.max_uncle_reward_ratio_pct {
+        checks.push(ScheduledCheck::new(UncleRewardCheck { max_ratio_pct }, config.uncle_reward_check));
+    }
+
+    if config.pow_nonce_enabled {
+        checks.push(ScheduledCheck::new(PowNonceCheck, config.pow_nonce_check));
+    }
+
+    if let Some(max_blobs) = config.max_blobs_per_block {
+        checks.push(ScheduledCheck::new(BlobCountCheck { max_blobs }, config.blob_count_check));
+    }
+
+    if config.finalization_enabled {
+        let check = FinalizationCheck { max_distance: config.max_finalization_distance };
+        checks.push(ScheduledCheck::new(check, config.finalization_check));
+    }
+
+    if let Some(minutes) = config.sync_stuck_threshold_minutes {
+        let check = SyncProgressCheck { stuck_after: chrono::Duration::minutes(minutes as i64), best: None };
+        checks.push(ScheduledCheck::new(check, config.sync_progress_check));
+    }
+
+    if !config.filter_addresses.is_empty() {
+        let check = FilterCheck {
+            filter: log_filter(&config.filter_addresses, &config.filter_topics),
+            filter_id: None,
+        };
+        checks.push(ScheduledCheck::new(check, config.filter_check));
+    }
+
+    if config.reward_anomaly_enabled {
+        let check = RewardAnomalyCheck { max_multiplier: config.max_reward_multiplier };
+        checks.push(ScheduledCheck::new(check, config.reward_anomaly_check));
+    }
+
+    if let Some(pattern) = &config.trace_pattern {
+        let check = TraceCheck { pattern: pattern.clone(), mode: config.trace_match_mode };
+        checks.push(ScheduledCheck::new(check, config.trace_check));
+    }
+
+    if !config.beacon_validator_indices.is_empty() {
+        let check = ValidatorStatusCheck {
+            beacon_url: config.beacon_api_url.clone().expect("validated in Config::from_env"),
+            indices: config.beacon_validator_indices.clone(),
+        };
+        checks.push(ScheduledCheck::new(check, config.validator_status_check));
+    }
+
+    if let Some(expected_version) = &config.expected_fork_version {
+        let check = ForkVersionCheck {
+            beacon_url: config.beacon_api_url.clone().expect("validated in Config::from_env"),
+            expected_version: expected_version.clone(),
+        };
+        checks.push(ScheduledCheck::new(check, config.fork_version_check));
+    }
+
+    if config.beacon_el_status_enabled {
+        let check = BeaconElStatusCheck { beacon_url: config.beacon_api_url.clone().expect("validated in Config::from_env") };
+        checks.push(ScheduledCheck::new(check, config.beacon_el_status_check));
+    }
+
+    if config.slashing_check_enabled {
+        let check = SlashingCheck { beacon_url: config.beacon_api_url.clone().expect("validated in Config::from_env") };
+        checks.push(ScheduledCheck::new(check, config.slashing_check));
+    }
+
+    if config.sync_committee_check_enabled {
+        let check = SyncCommitteeCheck::new(config.beacon_api_url.clone().expect("validated in Config::from_env"), config.min_sync_committee_participation_pct);
+        checks.push(ScheduledCheck::new(check, config.sync_committee_check));
+    }
+
+    if config.finality_hash_check_enabled {
+        let check = FinalityHashCheck { beacon_url: config.beacon_api_url.clone().expect("validated in Config::from_env") };
+        checks.push(ScheduledCheck::new(check, config.finality_hash_check));
+    }
+
+    if let Some(device) = &config.io_saturation_device {
+        let check = IoSaturationCheck { device: device.clone(), utilization: diskio::spawn_sampler(device.clone()), warn_pct: config.io_saturation_warn_pct };
+        checks.push(ScheduledCheck::new(check, config.io_saturation_check));
+    }
+    if let Some((device, warn_mb_per_sec)) = &config.disk_write_throughput_warn {
+        let check = DiskWriteThroughputCheck { device: device.clone(), throughput: diskio::spawn_write_sampler(device.clone()), warn_mb_per_sec: *warn_mb_per_sec };
+        checks.push(ScheduledCheck::new(check, config.disk_write_throughput_check));
+    }
+
+    if let Some(path) = &config.client_log_file {
+        let check = ClientLogCheck { path: path.clone(), matches: clientlog::spawn_tailer(path.clone(), config.client_log_patterns.clone()) };
+        checks.push(ScheduledCheck::new(check, config.client_log_check));
+    }
+
+    if let Some(validator_index) = config.validator_index {
+        let check = AttestationCheck {
+            beacon_url: config.beacon_api_url.clone().expect("validated in Config::from_env"),
+            validator_index,
+            min_effectiveness_pct: config.min_attestation_effectiveness_pct,
+        };
+        checks.push(ScheduledCheck::new(check, config.attestation_check));
+    }
+
+    checks
+}
+
+// --- Checks ---
+
+/// Alerts when 'net_peerCount' drops below a minimum
+pub struct PeerCountCheck {
+    pub min_peers: u64,
+}
+
+#[async_trait]
+impl Check for PeerCountCheck {
+    fn name(&self) -> &'static str {
+        "peer_count"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let peers = fetch_quantity(ctx.client, ctx.url_for("net_peerCount"), "net_peerCount", serde_json::json!([])).await?;
+        Ok(peer_count_outcome(peers, self.min_peers))
+    }
+}
+
+fn peer_count_outcome(peers: u64, min_peers: u64) -> CheckOutcome {
+    if peers < min_peers {
+        CheckOutcome::Alert(tr!("low_peer_count", peers = peers, min = min_peers))
+    } else {
+        CheckOutcome::Ok(format!("[OK] Peers: {}", peers))
+    }
+}
+
+/// Alerts when too few peers were dialed by us ('admin_peers'); an all-inbound
+/// peer set means the node isn't dialing out and risks isolation
+pub struct PeerDirectionCheck {
+    pub min_outbound: u64,
+}
+
+#[async_trait]
+impl Check for PeerDirectionCheck {
+    fn name(&self) -> &'static str {
+        "peer_direction"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let details = fetch_peer_details(ctx.client, ctx.url_for("admin_peers")).await?;
+        crate::metrics::record_peers(details.inbound, details.outbound);
+        Ok(peer_direction_outcome(&details, self.min_outbound))
+    }
+}
+
+fn peer_direction_outcome(details: &PeerDetails, min_outbound: u64) -> CheckOutcome {
+    if details.outbound < min_outbound {
+        CheckOutcome::Alert(tr!("low_outbound_peers", outbound = details.outbound, inbound = details.inbound, min = min_outbound))
+    } else {
+        CheckOutcome::Ok(format!("[OK] Peers: {} inbound, {} outbound", details.inbound, details.outbound))
+    }
+}
+
+/// Alerts when the node is using too small a share of its peer slots:
+/// 'net_peerCount' against 'admin_nodeInfo' protocols.eth.config.maxPeers
+pub struct PeerRatioCheck {
+    pub min_ratio_pct: f64,
+}
+
+#[async_trait]
+impl Check for PeerRatioCheck {
+    fn name(&self) -> &'static str {
+        "peer_ratio"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let info = rpc_call(ctx.client, ctx.url_for("admin_nodeInfo"), "admin_nodeInfo", serde_json::json!([])).await?;
+        let max_peers = info.pointer("/protocols/eth/config/maxPeers")
+            .and_then(Value::as_u64)
+            .ok_or("Invalid response: 'protocols.eth.config.maxPeers' missing")?;
+        let peers = fetch_quantity(ctx.client, ctx.url_for("net_peerCount"), "net_peerCount", serde_json::json!([])).await?;
+        Ok(peer_ratio_outcome(peers, max_peers, self.min_ratio_pct))
+    }
+}
+
+fn peer_ratio_outcome(peers: u64, max_peers: u64, min_ratio_pct: f64) -> CheckOutcome {
+    // maxPeers=0 means the node runs without peers on purpose
+    if max_peers == 0 {
+        return CheckOutcome::Ok(format!("[OK] Peers: {} (maxPeers is 0)", peers));
+    }
+    let ratio_pct = 100.0 * peers as f64 / max_peers as f64;
+    if ratio_pct < min_ratio_pct {
+        CheckOutcome::Alert(tr!("low_peer_ratio", peers = peers, max = max_peers, ratio = format!("{:.0}", ratio_pct), min = min_ratio_pct))
+    } else {
+        CheckOutcome::Ok(format!("[OK] Peers: {} of {} ({:.0}%)", peers, max_peers, ratio_pct))
+    }
+}
+
+/// Reads a count from 'admin_nodeInfo' by its dotted path (e.g.
+/// `rpc.http.connections`), failing with `NotReported` when it's absent
+fn node_info_count(info: &Value, field: &str) -> RpcResult<u64> {
+    info.pointer(&format!("/{}", field.replace('.', "/")))
+        .and_then(Value::as_u64)
+        .ok_or_else(|| AppError::NotReported(field.to_string()))
+}
+
+/// Alerts when the HTTP RPC server is close to its connection limit: the open
+/// connections in 'admin_nodeInfo' rpc.http against its maxConnections, or
+/// RPC_MAX_CONNECTIONS for clients that don't report one. Neither Geth,
+/// Nethermind, Besu, Erigon nor Reth report rpc.http today, so on those the
+/// check disables itself on its first run
+pub struct RpcConnectionsCheck {
+    pub warn_pct: f64,
+    pub max_connections: Option<u64>,
+}
+
+#[async_trait]
+impl Check for RpcConnectionsCheck {
+    fn name(&self) -> &'static str {
+        "rpc_connections"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let info = rpc_call(ctx.client, ctx.url_for("admin_nodeInfo"), "admin_nodeInfo", serde_json::json!([])).await?;
+        let connections = node_info_count(&info, "rpc.http.connections")?;
+        let max_connections = match self.max_connections {
+            Some(max_connections) => max_connections,
+            None => node_info_count(&info, "rpc.http.maxConnections")?,
+        };
+        Ok(rpc_connections_outcome(connections, max_connections, self.warn_pct))
+    }
+}
+
+fn rpc_connections_outcome(connections: u64, max_connections: u64, warn_pct: f64) -> CheckOutcome {
+    let used_pct = 100.0 * connections as f64 / max_connections.max(1) as f64;
+    if used_pct > warn_pct {
+        CheckOutcome::Alert(tr!("rpc_connections_high", connections = connections, max = max_connections, pct = format!("{:.0}", used_pct), warn = warn_pct))
+    } else {
+        CheckOutcome::Ok(format!("[OK] RPC connections: {} of {} ({:.0}%)", connections, max_connections, used_pct))
+    }
+}
+
+/// Alerts when the client doesn't hold the expected number of Engine API
+/// connections, as reported in 'admin_nodeInfo' rpc.engine.connections. 0
+/// means the consensus client isn't connected, often a wrong --authrpc.addr
+/// after an upgrade. No mainstream client reports rpc.engine today, so on
+/// those the check disables itself on its first run
+pub struct EngineConnectionsCheck {
+    pub expected: u64,
+}
+
+#[async_trait]
+impl Check for EngineConnectionsCheck {
+    fn name(&self) -> &'static str {
+        "engine_connections"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let info = rpc_call(ctx.client, ctx.url_for("admin_nodeInfo"), "admin_nodeInfo", serde_json::json!([])).await?;
+        let connections = node_info_count(&info, "rpc.engine.connections")?;
+        Ok(engine_connections_outcome(connections, self.expected))
+    }
+}
+
+fn engine_connections_outcome(connections: u64, expected: u64) -> CheckOutcome {
+    if connections == expected {
+        CheckOutcome::Ok(format!("[OK] Engine API connections: {}", connections))
+    } else if connections == 0 {
+        CheckOutcome::Alert(tr!("engine_disconnected", expected = expected))
+    } else {
+        CheckOutcome::Alert(tr!("engine_connections_unexpected", connections = connections, expected = expected))
+    }
+}
+
+/// Alerts when the node holds more WebSocket subscriptions than expected, as
+/// reported in 'admin_nodeInfo' rpc.ws.subscriptions; a steadily growing count
+/// points at DApps that never unsubscribe. No mainstream client reports
+/// rpc.ws today, so on those the check disables itself on its first run
+pub struct WsSubscriptionsCheck {
+    pub max_subscriptions: u64,
+}
+
+#[async_trait]
+impl Check for WsSubscriptionsCheck {
+    fn name(&self) -> &'static str {
+        "ws_subscriptions"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let info = rpc_call(ctx.client, ctx.url_for("admin_nodeInfo"), "admin_nodeInfo", serde_json::json!([])).await?;
+        let subscriptions = node_info_count(&info, "rpc.ws.subscriptions")?;
+        Ok(ws_subscriptions_outcome(subscriptions, self.max_subscriptions))
+    }
+}
+
+fn ws_subscriptions_outcome(subscriptions: u64, max_subscriptions: u64) -> CheckOutcome {
+    if subscriptions > max_subscriptions {
+        CheckOutcome::Alert(tr!("ws_subscriptions_high", subscriptions = subscriptions, max = max_subscriptions))
+    } else {
+        CheckOutcome::Ok(format!("[OK] WebSocket subscriptions: {}", subscriptions))
+    }
+}
+
+/// Alerts when the client runs more than `max_goroutines` goroutines, a sign
+/// of a goroutine leak heading for an OOM (Geth's 'debug_metrics')
+pub struct GoroutineCountCheck {
+    pub max_goroutines: u64,
+}
+
+#[async_trait]
+impl Check for GoroutineCountCheck {
+    fn name(&self) -> &'static str {
+        "goroutine_count"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let goroutines = fetch_goroutine_count(ctx.client, ctx.url_for("debug_metrics")).await?;
+        Ok(goroutine_count_outcome(goroutines, self.max_goroutines))
+    }
+}
+
+fn goroutine_count_outcome(goroutines: u64, max_goroutines: u64) -> CheckOutcome {
+    if goroutines > max_goroutines {
+        CheckOutcome::Alert(tr!("goroutine_count_high", goroutines = goroutines, max = max_goroutines))
+    } else {
+        CheckOutcome::Ok(format!("[OK] Goroutines: {}", goroutines))
+    }
+}
+
+/// Reads the discovery port from 'admin_nodeInfo' and sends a discv4 PING to it
+/// from this host; alerts when no PONG comes back (UDP blocked by a firewall)
+pub struct DiscoverabilityCheck {
+    /// Address to probe instead of the node's advertised IP (e.g. behind NAT)
+    pub probe_host: Option<std::net::IpAddr>,
+}
+
+#[async_trait]
+impl Check for DiscoverabilityCheck {
+    fn name(&self) -> &'static str {
+        "p2p_discoverability"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let info = rpc_call(ctx.client, ctx.url_for("admin_nodeInfo"), "admin_nodeInfo", serde_json::json!([])).await?;
+        let (target, tcp_port) = discovery_target(&info, self.probe_host)?;
+        let reachable = discovery::probe(target, tcp_port).await?;
+        Ok(if reachable {
+            CheckOutcome::Ok(format!("[OK] Discovery port {} answers", target))
+        } else {
+            CheckOutcome::Alert(tr!("p2p_not_discoverable", address = target))
+        })
+    }
+}
+
+/// UDP discovery address and TCP listener port advertised in 'admin_nodeInfo'
+fn discovery_target(info: &Value, probe_host: Option<std::net::IpAddr>) -> RpcResult<(std::net::SocketAddr, u16)> {
+    let port = |name: &str| info.pointer(&format!("/ports/{}", name))
+        .and_then(Value::as_u64)
+        .and_then(|port| u16::try_from(port).ok());
+    let discovery = port("discovery").ok_or("Invalid response: 'ports.discovery' missing")?;
+    let listener = port("listener").unwrap_or(discovery);
+
+    let ip = match probe_host {
+        Some(ip) => ip,
+        None => info.get("ip").and_then(Value::as_str)
+            .and_then(|ip| ip.parse().ok())
+            .ok_or("Invalid response: 'ip' missing")?,
+    };
+    Ok((std::net::SocketAddr::new(ip, discovery), listener))
+}
+
+/// Reports whether any known bootnode is among the peers ('admin_peers').
+/// Informational unless `alert` is set (BOOTNODE_ALERT).
+pub struct BootnodeCheck {
+    /// enode:// URLs or ENRs
+    pub bootnodes: Vec<String>,
+    pub alert: bool,
+}
+
+#[async_trait]
+impl Check for BootnodeCheck {
+    fn name(&self) -> &'static str {
+        "bootnodes"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let peers = fetch_peers(ctx.client, ctx.url_for("admin_peers")).await?;
+        let connected = connected_bootnodes(&peers, &self.bootnodes);
+        Ok(bootnode_outcome(connected, self.alert))
+    }
+}
+
+/// Number of bootnodes found in the peer list
+fn connected_bootnodes(peers: &[Value], bootnodes: &[String]) -> usize {
+    bootnodes.iter().filter(|bootnode| is_peer(peers, bootnode)).count()
+}
+
+/// Whether a node is in the peer list. enode URLs are matched on the public
+/// key (the address part differs behind NAT), ENRs on the peer's 'enr'.
+fn is_peer(peers: &[Value], node: &str) -> bool {
+    let enode_key = |enode: &str| enode.strip_prefix("enode://").map(|rest| rest.split('@').next().unwrap_or(rest).to_ascii_lowercase());
+    peers.iter().any(|peer| {
+        let field = |name: &str| peer.get(name).and_then(Value::as_str);
+        match enode_key(node) {
+            Some(key) => field("enode").and_then(enode_key).as_ref() == Some(&key),
+            None => field("enr") == Some(node),
+        }
+    })
+}
+
+/// 'admin_addPeer' attempts before a missing required peer raises an alert
+const MAX_ADD_PEER_ATTEMPTS: u32 = 3;
+
+/// Keeps a list of required peers connected (private networks): missing ones
+/// are re-added with 'admin_addPeer', and an alert fires once a peer is still
+/// missing after MAX_ADD_PEER_ATTEMPTS attempts
+pub struct RequiredPeersCheck {
+    /// enode:// URLs
+    pub required: Vec<String>,
+    /// Consecutive attempts per missing peer
+    pub attempts: HashMap<String, u32>,
+}
+
+#[async_trait]
+impl Check for RequiredPeersCheck {
+    fn name(&self) -> &'static str {
+        "required_peers"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let peers = fetch_peers(ctx.client, ctx.url_for("admin_peers")).await?;
+        let missing = update_peer_attempts(&mut self.attempts, &self.required, &peers);
+
+        for enode in &missing {
+            let params = serde_json::json!([enode]);
+            match rpc_call(ctx.client, ctx.url_for("admin_addPeer"), "admin_addPeer", params).await {
+                Ok(_) => info!("[INFO] Requested connection to required peer {}", enode),
+                Err(e) => warn!("[WARN] admin_addPeer failed for {}: {}", enode, e),
+            }
+        }
+        Ok(required_peers_outcome(&self.attempts, self.required.len()))
+    }
+}
+
+/// Resets the attempt count of connected peers and counts one more attempt
+/// for each missing one. Returns the missing peers.
+fn update_peer_attempts(attempts: &mut HashMap<String, u32>, required: &[String], peers: &[Value]) -> Vec<String> {
+    let mut missing = Vec::new();
+    for enode in required {
+        if is_peer(peers, enode) {
+            attempts.remove(enode);
+        } else {
+            *attempts.entry(enode.clone()).or_insert(0) += 1;
+            missing.push(enode.clone());
+        }
+    }
+    missing
+}
+
+fn required_peers_outcome(attempts: &HashMap<String, u32>, required: usize) -> CheckOutcome {
+    // The attempt made this run hasn't had time to connect, so only earlier ones count
+    let mut stuck: Vec<&str> = attempts.iter()
+        .filter(|(_, count)| **count > MAX_ADD_PEER_ATTEMPTS)
+        .map(|(enode, _)| enode.as_str())
+        .collect();
+    if stuck.is_empty() {
+        return CheckOutcome::Ok(format!("[OK] Required peers: {}/{} connected", required - attempts.len(), required));
+    }
+    stuck.sort_unstable();
+    CheckOutcome::Alert(tr!("required_peers_missing", peers = stuck.join(", "), attempts = MAX_ADD_PEER_ATTEMPTS))
+}
+
+fn bootnode_outcome(connected: usize, alert: bool) -> CheckOutcome {
+    match (connected, alert) {
+        (0, true) => CheckOutcome::Alert(tr!("no_bootnode_connections")),
+        (0, false) => CheckOutcome::Ok("[INFO] No bootnode connections".to_string()),
+        _ => CheckOutcome::Ok(format!("[OK] Connected to {} bootnodes", connected)),
+    }
+}
+
+/// Calls a contract function via 'eth_call' and alerts if the result isn't the expected value
+pub struct ContractCallCheck {
+    pub address: String,
+    pub data: String,
+    pub expected: String,
+}
+
+#[async_trait]
+impl Check for ContractCallCheck {
+    fn name(&self) -> &'static str {
+        "contract_call"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let params = serde_json::json!([{ "to": self.address, "data": self.data }, "latest"]);
+        let result = rpc_call(ctx.client, ctx.url_for("eth_call"), "eth_call", params).await?;
+        let actual = result.as_str().ok_or("Invalid response: 'result' field not a string")?;
+        Ok(contract_call_outcome(&self.address, &self.expected, actual))
+    }
+}
+
+fn contract_call_outcome(address: &str, expected: &str, actual: &str) -> CheckOutcome {
+    // Hex comparison: ignore case and the optional 0x prefix
+    let normalize = |hex: &str| hex.trim_start_matches("0x").to_ascii_lowercase();
+    if normalize(expected) == normalize(actual) {
+        CheckOutcome::Ok(format!("[OK] Contract call {} returned expected result", address))
+    } else {
+        CheckOutcome::Alert(tr!("contract_call_mismatch", address = address, expected = expected, actual = actual))
+    }
+}
+
+/// Account whose balance is read by the archive check; any account works, as
+/// the lookup has to walk the state trie of the old block either way
+const ARCHIVE_CHECK_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
+
+/// Error messages of clients that no longer hold a block's state
+const PRUNED_STATE_ERRORS: &[&str] = &["missing trie node", "pruned", "state not available", "no state available"];
+
+/// Alerts when the node can't serve state at an old block
+/// (ARCHIVE_CHECK_BLOCK_NUMBER) anymore, i.e. it lost its archive data
+pub struct ArchiveCheck {
+    pub block: u64,
+}
+
+#[async_trait]
+impl Check for ArchiveCheck {
+    fn name(&self) -> &'static str {
+        "archive"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let params = serde_json::json!([ARCHIVE_CHECK_ADDRESS, format!("0x{:x}", self.block)]);
+        match rpc_call(ctx.client, ctx.url_for("eth_getBalance"), "eth_getBalance", params).await {
+            Ok(_) => Ok(archive_outcome(self.block, None)),
+            Err(AppError::RpcError { message, .. }) if is_pruned_state_error(&message) => Ok(archive_outcome(self.block, Some(&message))),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+fn is_pruned_state_error(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    PRUNED_STATE_ERRORS.iter().any(|pattern| message.contains(pattern))
+}
+
+fn archive_outcome(block: u64, error: Option<&str>) -> CheckOutcome {
+    match error {
+        Some(error) => CheckOutcome::Alert(tr!("archive_data_missing", block = block, error = error)),
+        None => CheckOutcome::Ok(format!("[OK] Historical state at block {} is available", block)),
+    }
+}
+
+/// Chain family of the local node (CHAIN_TYPE), for chain-specific checks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChainType {
+    Ethereum,
+    /// Polygon zkEVM / CDK: L2 blocks are sealed into batches
+    PolygonZkevm,
+}
+
+impl std::str::FromStr for ChainType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ethereum" => Ok(ChainType::Ethereum),
+            "polygon_zkevm" => Ok(ChainType::PolygonZkevm),
+            other => Err(format!("unknown chain type '{}'", other)),
+        }
+    }
+}
+
+/// Polygon zkEVM: alerts when the L2 blocks run too far ahead of the batches
+/// closed by the sequencer ('zkevm_batchNumber'), counted as `blocks_per_batch`
+/// blocks per batch
+pub struct ZkevmBatchCheck {
+    pub blocks_per_batch: u64,
+    pub threshold: u64,
+}
+
+#[async_trait]
+impl Check for ZkevmBatchCheck {
+    fn name(&self) -> &'static str {
+        "zkevm_batch"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let block = fetch_block_number(ctx.client, ctx.url_for("eth_blockNumber")).await?;
+        let batch = fetch_quantity(ctx.client, ctx.url_for("zkevm_batchNumber"), "zkevm_batchNumber", serde_json::json!([])).await?;
+        Ok(zkevm_batch_outcome(block, batch, self.blocks_per_batch, self.threshold))
+    }
+}
+
+fn zkevm_batch_outcome(block: u64, batch: u64, blocks_per_batch: u64, threshold: u64) -> CheckOutcome {
+    let lag = block.saturating_sub(batch.saturating_mul(blocks_per_batch));
+    if lag > threshold {
+        CheckOutcome::Alert(tr!("zkevm_batch_lag", block = block, batch = batch, lag = lag, max = threshold))
+    } else {
+        CheckOutcome::Ok(format!("[OK] zkEVM | Block: {} | Batch: {} | Batch lag: {} blocks", block, batch, lag))
+    }
+}
+
+/// Alerts when the node's 'eth_getProof' for an account doesn't verify against
+/// the latest block's stateRoot, i.e. the node's state and headers disagree
+pub struct StateProofCheck {
+    pub address: String,
+}
+
+#[async_trait]
+impl Check for StateProofCheck {
+    fn name(&self) -> &'static str {
+        "state_proof"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let block = fetch_block(ctx.client, ctx.url_for("eth_getBlockByNumber"), "latest").await?;
+        let number = quantity_field(&block, "number")?;
+        let state_root = block.get("stateRoot").and_then(Value::as_str).ok_or("Invalid response: 'stateRoot' missing")?;
+        // Prove at the block just fetched, not whatever is latest by now
+        let params = serde_json::json!([self.address, [], format!("0x{:x}", number)]);
+        let response = rpc_call(ctx.client, ctx.url_for("eth_getProof"), "eth_getProof", params).await?;
+        let verified = proof::verify_account_proof(&parse_hex_bytes(state_root)?, &response);
+        Ok(state_proof_outcome(&self.address, number, verified))
+    }
+}
+
+fn state_proof_outcome(address: &str, block: u64, verified: Result<(), String>) -> CheckOutcome {
+    match verified {
+        Ok(()) => CheckOutcome::Ok(format!("[OK] State proof for {} verified at block {}", address, block)),
+        Err(reason) => CheckOutcome::Alert(tr!("state_proof_invalid", address = address, block = block, reason = reason)),
+    }
+}
+
+/// A client name and version from 'web3_clientVersion' (e.g.
+/// `Geth/v1.13.0-stable-3f907d6a/linux-amd64/go1.21.1`) or MIN_CLIENT_VERSION
+/// (`Geth/v1.13.0`, or just `1.13.0` for any client)
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClientVersion {
+    pub client: Option<String>,
+    pub version: semver::Version,
+}
+
+impl std::str::FromStr for ClientVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (client, rest) = match s.split_once('/') {
+            Some((client, rest)) => (Some(client.to_string()), rest),
+            None => (None, s),
+        };
+        // The version is the first segment that looks like one: Erigon writes
+        // `erigon/2.55.1/...`, Nethermind `Nethermind/v1.25.4+20b10b35/...`
+        let version = rest.split('/').find_map(version_core)
+            .ok_or_else(|| format!("no version number in '{}'", s))?;
+        Ok(ClientVersion { client, version })
+    }
+}
+
+/// MAJOR.MINOR[.PATCH] at the start of a segment, after an optional 'v'. Suffixes
+/// like Geth's `-stable` are dropped, as semver would order them as pre-releases.
+fn version_core(segment: &str) -> Option<semver::Version> {
+    let digits = segment.strip_prefix(['v', 'V']).unwrap_or(segment);
+    let end = digits.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(digits.len());
+    let numbers = digits[..end].trim_end_matches('.').split('.').map(str::parse::<u64>).collect::<Result<Vec<_>, _>>().ok()?;
+    match numbers[..] {
+        [major, minor] => Some(semver::Version::new(major, minor, 0)),
+        [major, minor, patch, ..] => Some(semver::Version::new(major, minor, patch)),
+        _ => None,
+    }
+}
+
+/// Alerts when 'web3_clientVersion' reports an older version than MIN_CLIENT_VERSION
+pub struct ClientVersionCheck {
+    pub minimum: ClientVersion,
+}
+
+#[async_trait]
+impl Check for ClientVersionCheck {
+    fn name(&self) -> &'static str {
+        "client_version"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let result = rpc_call(ctx.client, ctx.url_for("web3_clientVersion"), "web3_clientVersion", serde_json::json!([])).await?;
+        let raw = result.as_str().ok_or("Invalid response: 'result' field not a string")?;
+        let running: ClientVersion = raw.parse().map_err(|e| format!("Invalid response: {}", e))?;
+        Ok(client_version_outcome(raw, &running, &self.minimum))
+    }
+}
+
+fn client_version_outcome(raw: &str, running: &ClientVersion, minimum: &ClientVersion) -> CheckOutcome {
+    // A minimum for one client says nothing about another
+    if let (Some(expected), Some(actual)) = (&minimum.client, &running.client) && !expected.eq_ignore_ascii_case(actual) {
+        return CheckOutcome::Ok(format!("[OK] Client {} is not {}, no minimum version applies", raw, expected));
+    }
+    if running.version < minimum.version {
+        CheckOutcome::Alert(tr!("outdated_client_version", running = raw, minimum = minimum.version))
+    } else {
+        CheckOutcome::Ok(format!("[OK] Client {} meets minimum version {}", raw, minimum.version))
+    }
+}
+
+/// EVM upgrades with a probe for an opcode they introduced (MIN_EVM_VERSION)
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum EvmVersion {
+    Istanbul,
+    London,
+    Shanghai,
+    Cancun,
+}
+
+impl std::str::FromStr for EvmVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "istanbul" => Ok(EvmVersion::Istanbul),
+            "london" => Ok(EvmVersion::London),
+            "shanghai" => Ok(EvmVersion::Shanghai),
+            "cancun" => Ok(EvmVersion::Cancun),
+            other => Err(format!("unknown EVM version '{}'", other)),
+        }
+    }
+}
+
+impl EvmVersion {
+    /// The opcode probed, and init code that uses it and then stops
+    fn probe(self) -> (&'static str, &'static str) {
+        match self {
+            // CHAINID POP STOP
+            EvmVersion::Istanbul => ("CHAINID", "0x465000"),
+            // BASEFEE POP STOP
+            EvmVersion::London => ("BASEFEE", "0x485000"),
+            // PUSH0 POP STOP
+            EvmVersion::Shanghai => ("PUSH0", "0x5f5000"),
+            // PUSH0 TLOAD POP STOP
+            EvmVersion::Cancun => ("TLOAD", "0x5f5c5000"),
+        }
+    }
+}
+
+/// Runs init code using an opcode of MIN_EVM_VERSION via 'eth_call' and alerts
+/// when the node rejects it, i.e. its EVM predates that upgrade
+pub struct EvmVersionCheck {
+    pub minimum: EvmVersion,
+}
+
+#[async_trait]
+impl Check for EvmVersionCheck {
+    fn name(&self) -> &'static str {
+        "evm_version"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let (_, code) = self.minimum.probe();
+        // No 'to': the data runs as contract creation code
+        let params = serde_json::json!([{ "data": code }, "latest"]);
+        match rpc_call(ctx.client, ctx.url_for("eth_call"), "eth_call", params).await {
+            Ok(_) => Ok(evm_version_outcome(self.minimum, None)),
+            Err(e @ AppError::RpcError { .. }) if !is_method_not_found(&e) => Ok(evm_version_outcome(self.minimum, Some(&e.to_string()))),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// `error` is the node's answer to the probe, None if it executed
+fn evm_version_outcome(version: EvmVersion, error: Option<&str>) -> CheckOutcome {
+    let (opcode, _) = version.probe();
+    match error {
+        None => CheckOutcome::Ok(format!("[OK] EVM supports {} ({:?})", opcode, version)),
+        Some(error) => CheckOutcome::Alert(tr!("outdated_evm_version", opcode = opcode, version = format!("{:?}", version), error = error)),
+    }
+}
+
+/// Alerts when 'eth_mining' doesn't match the expected state (e.g. a read-only
+/// node that started mining, or a PoW/PoSA validator that stopped)
+pub struct MiningCheck {
+    pub expected: bool,
+}
+
+#[async_trait]
+impl Check for MiningCheck {
+    fn name(&self) -> &'static str {
+        "mining"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let result = rpc_call(ctx.client, ctx.url_for("eth_mining"), "eth_mining", serde_json::json!([])).await?;
+        let mining = result.as_bool().ok_or("Invalid response: 'result' field not a boolean")?;
+        Ok(mining_outcome(mining, self.expected))
+    }
+}
+
+fn mining_outcome(mining: bool, expected: bool) -> CheckOutcome {
+    if mining == expected {
+        CheckOutcome::Ok(format!("[OK] Mining: {}", mining))
+    } else {
+        CheckOutcome::Alert(tr!("unexpected_mining_state", actual = mining, expected = expected))
+    }
+}
+
+/// Alerts when the latest block's 'gasLimit' is too far from the expected value
+/// (e.g. a node running with a wrong '--targetgaslimit')
+pub struct GasLimitCheck {
+    pub expected: u64,
+    pub max_deviation_pct: f64,
+}
+
+#[async_trait]
+impl Check for GasLimitCheck {
+    fn name(&self) -> &'static str {
+        "gas_limit"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let block = fetch_block(ctx.client, ctx.url_for("eth_getBlockByNumber"), "latest").await?;
+        let gas_limit = quantity_field(&block, "gasLimit")?;
+        Ok(gas_limit_outcome(gas_limit, self.expected, self.max_deviation_pct))
+    }
+}
+
+fn gas_limit_outcome(gas_limit: u64, expected: u64, max_deviation_pct: f64) -> CheckOutcome {
+    let deviation_pct = gas_limit.abs_diff(expected) as f64 / expected.max(1) as f64 * 100.0;
+    if deviation_pct > max_deviation_pct {
+        CheckOutcome::Alert(tr!("gas_limit_deviation", actual = gas_limit, expected = expected, pct = format!("{:.1}", deviation_pct), max = max_deviation_pct))
+    } else {
+        CheckOutcome::Ok(format!("[OK] Gas limit: {} ({:.1}% from expected)", gas_limit, deviation_pct))
+    }
+}
+
+/// Alerts when the latest block's parentHash isn't the hash of the block before
+/// it, a sign of a corrupted chain database
+pub struct HeaderChainCheck;
+
+#[async_trait]
+impl Check for HeaderChainCheck {
+    fn name(&self) -> &'static str {
+        "header_chain"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let url = ctx.url_for("eth_getBlockByNumber");
+        let latest = fetch_block(ctx.client, url, "latest").await?;
+        let number = quantity_field(&latest, "number")?;
+        if number == 0 {
+            return Ok(CheckOutcome::Ok("[OK] Only the genesis block, no parent to verify".to_string()));
+        }
+        // By number rather than parentHash, so the lookup itself can't follow a broken link
+        let previous = fetch_block(ctx.client, url, &format!("0x{:x}", number - 1)).await?;
+        let hash_field = |block: &Value, field: &str| -> RpcResult<String> {
+            let hash = block.get(field).and_then(Value::as_str).ok_or_else(|| format!("Invalid response: '{}' missing", field))?;
+            Ok(hash.to_ascii_lowercase())
+        };
+        Ok(header_chain_outcome(number, &hash_field(&latest, "parentHash")?, &hash_field(&previous, "hash")?))
+    }
+}
+
+fn header_chain_outcome(block: u64, parent_hash: &str, previous_hash: &str) -> CheckOutcome {
+    if parent_hash == previous_hash {
+        CheckOutcome::Ok(format!("[OK] Block {} links to its parent {}", block, previous_hash))
+    } else {
+        CheckOutcome::Alert(tr!("header_chain_break", block = block, parent_hash = parent_hash, previous = block - 1, previous_hash = previous_hash))
+    }
+}
+
+/// Alerts when the latest block's timestamp is ahead of this host's clock by more
+/// than the ~15 seconds consensus allows, i.e. the node's time sync is broken
+pub struct FutureTimestampCheck {
+    pub max_future_secs: u64,
+}
+
+#[async_trait]
+impl Check for FutureTimestampCheck {
+    fn name(&self) -> &'static str {
+        "future_timestamp"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let block = fetch_block(ctx.client, ctx.url_for("eth_getBlockByNumber"), "latest").await?;
+        let timestamp = quantity_field(&block, "timestamp")?;
+        Ok(future_timestamp_outcome(timestamp, Utc::now().timestamp(), self.max_future_secs))
+    }
+}
+
+fn future_timestamp_outcome(timestamp: u64, now: i64, max_future_secs: u64) -> CheckOutcome {
+    let ahead = timestamp as i64 - now;
+    if ahead > max_future_secs as i64 {
+        CheckOutcome::Alert(tr!("block_timestamp_future", future_seconds = ahead))
+    } else {
+        CheckOutcome::Ok(format!("[OK] Latest block timestamp is {}s old", (-ahead).max(0)))
+    }
+}
+
+/// Alerts when an address has too many pending transactions ahead of its mined
+/// nonce (e.g. a relayer submitting faster than its transactions are included)
+pub struct PendingNonceCheck {
+    pub address: String,
+    pub max_gap: u64,
+}
+
+#[async_trait]
+impl Check for PendingNonceCheck {
+    fn name(&self) -> &'static str {
+        "pending_nonce"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let count = |tag: &str| fetch_quantity(ctx.client, ctx.url_for("eth_getTransactionCount"), "eth_getTransactionCount", serde_json::json!([self.address, tag]));
+        let pending = count("pending").await?;
+        let latest = count("latest").await?;
+        crate::metrics::record_pending_nonces(&self.address, pending.saturating_sub(latest));
+        Ok(pending_nonce_outcome(&self.address, pending, latest, self.max_gap))
+    }
+}
+
+fn pending_nonce_outcome(address: &str, pending: u64, latest: u64, max_gap: u64) -> CheckOutcome {
+    let gap = pending.saturating_sub(latest);
+    if gap > max_gap {
+        CheckOutcome::Alert(tr!("pending_tx_buildup", address = address, pending = pending, latest = latest, gap = gap, max = max_gap))
+    } else {
+        CheckOutcome::Ok(format!("[OK] Address {} pending transactions: {}", address, gap))
+    }
+}
+
+/// Alerts when a transaction has been pending in the mempool for too long (a
+/// stuck or unreliable mempool). 'txpool_content' has no timestamps, so ages
+/// count from the first run that saw the transaction.
+pub struct PendingTxAgeCheck {
+    pub max_age: chrono::Duration,
+    /// When each currently pending transaction hash was first seen
+    pub first_seen: HashMap<String, DateTime<Utc>>,
+    /// False once the client turned out not to have 'txpool_content' (Geth namespace)
+    pub supported: bool,
+}
+
+#[async_trait]
+impl Check for PendingTxAgeCheck {
+    fn name(&self) -> &'static str {
+        "pending_tx_age"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        if !self.supported {
+            return Ok(CheckOutcome::Ok("[OK] Pending transaction age: txpool_content not supported".to_string()));
+        }
+        let content = match rpc_call(ctx.client, ctx.url_for("txpool_content"), "txpool_content", serde_json::json!([])).await {
+            Ok(content) => content,
+            Err(e) if is_method_not_found(&e) => {
+                warn!("[WARN] Check 'pending_tx_age' needs txpool_content, which this client doesn't support: {}", e);
+                self.supported = false;
+                return Ok(CheckOutcome::Ok("[OK] Pending transaction age: txpool_content not supported".to_string()));
+            }
+            Err(e) => return Err(e),
+        };
+        Ok(self.observe(pending_tx_hashes(&content), Utc::now()))
+    }
+}
+
+impl PendingTxAgeCheck {
+    fn observe(&mut self, hashes: Vec<String>, now: DateTime<Utc>) -> CheckOutcome {
+        // Forget mined or dropped transactions
+        let mut first_seen: HashMap<String, DateTime<Utc>> = hashes.into_iter()
+            .map(|hash| {
+                let seen = self.first_seen.get(&hash).copied().unwrap_or(now);
+                (hash, seen)
+            })
+            .collect();
+        std::mem::swap(&mut self.first_seen, &mut first_seen);
+
+        let Some((hash, seen)) = self.first_seen.iter().min_by_key(|(_, seen)| **seen) else {
+            return CheckOutcome::Ok("[OK] No pending transactions".to_string());
+        };
+        let age = now - *seen;
+        if age > self.max_age {
+            CheckOutcome::Alert(tr!("stale_pending_tx", hash = hash, seconds = age.num_seconds(), max = self.max_age.num_seconds()))
+        } else {
+            CheckOutcome::Ok(format!("[OK] Oldest of {} pending transactions: {}s", self.first_seen.len(), age.num_seconds()))
+        }
+    }
+}
+
+/// Hashes of the 'pending' section of 'txpool_content' ({sender: {nonce: tx}})
+fn pending_tx_hashes(content: &Value) -> Vec<String> {
+    let Some(senders) = content.get("pending").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    senders.values()
+        .filter_map(Value::as_object)
+        .flat_map(|by_nonce| by_nonce.values())
+        .filter_map(|tx| tx.get("hash")?.as_str().map(String::from))
+        .collect()
+}
+
+/// Number of recent blocks the uncle rate is averaged over
+const UNCLE_RATE_WINDOW: u64 = 10;
+
+/// Number of recent blocks the uncle reward ratio is computed over
+const UNCLE_REWARD_WINDOW: u64 = 20;
+
+/// Reads the client's own health endpoint (e.g. Nethermind's /api/health),
+/// which knows more than "answers RPC calls", and alerts on a non-200 status
+/// or an `"unhealthy"` status in the body
+pub struct HealthEndpointCheck {
+    pub url: String,
+}
+
+#[async_trait]
+impl Check for HealthEndpointCheck {
+    fn name(&self) -> &'static str {
+        "health_endpoint"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let resp = ctx.client.get(&self.url).send().await?;
+        let status = resp.status().as_u16();
+        // The body is client-specific and may not be JSON at all
+        let body: Option<Value> = resp.json().await.ok();
+        Ok(health_endpoint_outcome(status, body.as_ref()))
+    }
+}
+
+fn health_endpoint_outcome(http_status: u16, body: Option<&Value>) -> CheckOutcome {
+    let status = body.and_then(|body| body.get("status")).and_then(Value::as_str).unwrap_or("unknown");
+    if http_status != 200 || status.eq_ignore_ascii_case("unhealthy") {
+        CheckOutcome::Alert(tr!("health_endpoint_unhealthy", http_status = http_status, status = status))
+    } else {
+        CheckOutcome::Ok(format!("[OK] Health endpoint: {} (HTTP {})", status, http_status))
+    }
+}
+
+/// Asks a probe on another machine (EXTERNAL_PROBE_URL) whether the local RPC
+/// port is reachable from outside. Checks only run while the node answers
+/// locally, so an unreachable report points at a firewall or routing problem.
+pub struct ExternalProbeCheck {
+    pub probe_url: String,
+    /// `host:port` of LOCAL_RPC_URL
+    pub target: String,
+}
+
+#[async_trait]
+impl Check for ExternalProbeCheck {
+    fn name(&self) -> &'static str {
+        "external_probe"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let resp = ctx.client.get(&self.probe_url).query(&[("target", &self.target)]).send().await?;
+        let status = resp.status().as_u16();
+        // Probes may answer with just a status code or with {"reachable": bool}
+        let reachable = resp.json::<Value>().await.ok().and_then(|body| body.get("reachable").and_then(Value::as_bool));
+        Ok(external_probe_outcome(&self.target, status, reachable))
+    }
+}
+
+/// `host:port` the probe should connect to; the scheme's default port when the URL has none
+pub fn probe_target(rpc_url: &str) -> Option<String> {
+    let url = url::Url::parse(rpc_url).ok()?;
+    Some(format!("{}:{}", url.host_str()?, url.port_or_known_default()?))
+}
+
+fn external_probe_outcome(target: &str, http_status: u16, reachable: Option<bool>) -> CheckOutcome {
+    if (200..300).contains(&http_status) && reachable != Some(false) {
+        CheckOutcome::Ok(format!("[OK] {} is reachable from the external probe", target))
+    } else {
+        CheckOutcome::Alert(tr!("rpc_unreachable_externally", target = target, http_status = http_status))
+    }
+}
+
+/// Calls the Engine API with a freshly signed JWT (the secret shared with the
+/// consensus client) and alerts when the node rejects it
+pub struct JwtAuthCheck {
+    pub engine_url: String,
+    pub secret: Vec<u8>,
+}
+
+#[async_trait]
+impl Check for JwtAuthCheck {
+    fn name(&self) -> &'static str {
+        "jwt_auth"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let token = engine_jwt(&self.secret, Utc::now().timestamp())?;
+        // Any JSON-RPC answer, even an error for an unsupported method, means the token was accepted
+        let payload = serde_json::json!({ "jsonrpc": "2.0", "method": "engine_exchangeCapabilities", "params": [[]], "id": 1 });
+        let resp = ctx.client.post(&self.engine_url).bearer_auth(token).json(&payload).send().await?;
+        jwt_auth_outcome(&self.engine_url, resp.status().as_u16())
+    }
+}
+
+/// Reads a JWT secret file: 32 bytes as hex, with or without '0x'
+pub fn parse_jwt_secret(contents: &str) -> Result<Vec<u8>, String> {
+    let secret = parse_hex_bytes(contents.trim()).map_err(|e| format!("not hex: {}", e))?;
+    if secret.len() != 32 {
+        return Err(format!("expected 32 bytes, found {}", secret.len()));
+    }
+    Ok(secret)
+}
+
+/// HS256 token with the 'iat' claim, which the Engine API requires within 60s of its clock
+fn engine_jwt(secret: &[u8], now: i64) -> RpcResult<String> {
+    let claims = serde_json::json!({ "iat": now });
+    Ok(jsonwebtoken::encode(&jsonwebtoken::Header::default(), &claims, &jsonwebtoken::EncodingKey::from_secret(secret))?)
+}
+
+fn jwt_auth_outcome(url: &str, http_status: u16) -> RpcResult<CheckOutcome> {
+    match http_status {
+        200 => Ok(CheckOutcome::Ok(format!("[OK] Engine API accepted the JWT (HTTP {})", http_status))),
+        401 | 403 => Ok(CheckOutcome::Alert(tr!("jwt_auth_failed", url = crate::redact_url(url), http_status = http_status))),
+        other => Err(format!("Invalid response: Engine API answered HTTP {}", other).into()),
+    }
+}
+
+/// Receipts requested per 'eth_getTransactionReceipt' batch; providers cap batch sizes
+const RECEIPT_BATCH_SIZE: usize = 100;
+
+/// Alerts when too many transactions in the last blocks reverted (a chain
+/// issue, or a popular but broken contract). One receipt per transaction, so
+/// this should run with a high multiplier.
+pub struct TxSuccessRateCheck {
+    pub blocks: u64,
+    pub min_success_pct: f64,
+}
+
+#[async_trait]
+impl Check for TxSuccessRateCheck {
+    fn name(&self) -> &'static str {
+        "tx_success_rate"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let head = fetch_block_number(ctx.client, ctx.url_for("eth_blockNumber")).await?;
+        let url = ctx.url_for("eth_getBlockByNumber");
+        let blocks = join_all((head.saturating_sub(self.blocks - 1)..=head).map(|block| async move {
+            fetch_block(ctx.client, url, &format!("{:#x}", block)).await
+        })).await;
+
+        let mut hashes = Vec::new();
+        for block in blocks {
+            let block = block?;
+            let transactions = block.get("transactions").and_then(Value::as_array).ok_or("Invalid response: 'transactions' missing")?;
+            hashes.extend(transactions.iter().filter_map(Value::as_str).map(String::from));
+        }
+
+        let url = ctx.url_for("eth_getTransactionReceipt");
+        let (mut succeeded, mut total) = (0, 0);
+        for chunk in hashes.chunks(RECEIPT_BATCH_SIZE) {
+            let calls: Vec<_> = chunk.iter().map(|hash| ("eth_getTransactionReceipt", serde_json::json!([hash]))).collect();
+            for receipt in rpc_batch(ctx.client, url, &calls).await? {
+                if let Some(success) = receipt_succeeded(&receipt?) {
+                    total += 1;
+                    succeeded += success as u64;
+                }
+            }
+        }
+        Ok(tx_success_outcome(succeeded, total, self.blocks, self.min_success_pct))
+    }
+}
+
+/// A receipt's 'status' (0x1 success, 0x0 reverted); None for pre-Byzantium receipts
+fn receipt_succeeded(receipt: &Value) -> Option<bool> {
+    match receipt.get("status")?.as_str()? {
+        "0x1" => Some(true),
+        "0x0" => Some(false),
+        _ => None,
+    }
+}
+
+fn tx_success_outcome(succeeded: u64, total: u64, blocks: u64, min_success_pct: f64) -> CheckOutcome {
+    if total == 0 {
+        return CheckOutcome::Ok(format!("[OK] No transactions in the last {} blocks", blocks));
+    }
+    let pct = succeeded as f64 / total as f64 * 100.0;
+    if pct < min_success_pct {
+        CheckOutcome::Alert(tr!("low_tx_success_rate", pct = format!("{:.1}", pct), succeeded = succeeded, total = total, blocks = blocks, min = min_success_pct))
+    } else {
+        CheckOutcome::Ok(format!("[OK] Transaction success rate: {:.1}% ({}/{} in {} blocks)", pct, succeeded, total, blocks))
+    }
+}
+
+/// Alerts when the average uncle (ommer) rate over the last blocks is too high (PoW chains)
+pub struct UncleRateCheck {
+    pub max_rate_pct: f64,
+}
+
+#[async_trait]
+impl Check for UncleRateCheck {
+    fn name(&self) -> &'static str {
+        "uncle_rate"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let head = fetch_block_number(ctx.client, ctx.url_for("eth_blockNumber")).await?;
+        let blocks = head.saturating_sub(UNCLE_RATE_WINDOW - 1)..=head;
+        let url = ctx.url_for("eth_getUncleCountByBlockNumber");
+        let counts = join_all(blocks.map(|block| fetch_uncle_count(ctx.client, url, block))).await;
+        let counts = counts.into_iter().collect::<RpcResult<Vec<u64>>>()?;
+        Ok(uncle_rate_outcome(&counts, self.max_rate_pct))
+    }
+}
+
+fn uncle_rate_outcome(counts: &[u64], max_rate_pct: f64) -> CheckOutcome {
+    let uncles: u64 = counts.iter().sum();
+    // PoS chains never have uncles; nothing to judge
+    if uncles == 0 {
+        return CheckOutcome::Ok(format!("[OK] No uncles in the last {} blocks", counts.len()));
+    }
+    let rate_pct = uncles as f64 / counts.len() as f64 * 100.0;
+    if rate_pct > max_rate_pct {
+        CheckOutcome::Alert(tr!("high_uncle_rate", rate = format!("{:.1}", rate_pct), uncles = uncles, blocks = counts.len(), max = max_rate_pct))
+    } else {
+        CheckOutcome::Ok(format!("[OK] Uncle rate: {:.1}% ({} in {} blocks)", rate_pct, uncles, counts.len()))
+    }
+}
+
+/// Alerts when uncle rewards over the last blocks are too large a share of the
+/// block rewards, a proxy for the network-wide orphan rate (PoW chains)
+pub struct UncleRewardCheck {
+    pub max_ratio_pct: f64,
+}
+
+#[async_trait]
+impl Check for UncleRewardCheck {
+    fn name(&self) -> &'static str {
+        "uncle_reward"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let latest = fetch_block(ctx.client, ctx.url_for("eth_getBlockByNumber"), "latest").await?;
+        if ethash::pow_seal(&latest)?.is_none() {
+            return Ok(CheckOutcome::Ok("[OK] Uncle reward ratio: not applicable, the chain is not proof of work".to_string()));
+        }
+        let head = quantity_field(&latest, "number")?;
+        let blocks = head.saturating_sub(UNCLE_REWARD_WINDOW - 1)..=head;
+        let count_url = ctx.url_for("eth_getUncleCountByBlockNumber");
+        let counts = join_all(blocks.clone().map(|block| fetch_uncle_count(ctx.client, count_url, block))).await;
+        let counts = counts.into_iter().collect::<RpcResult<Vec<u64>>>()?;
+
+        let uncle_url = ctx.url_for("eth_getUncleByBlockNumberAndIndex");
+        let uncles = blocks.clone().zip(counts).flat_map(|(block, count)| (0..count).map(move |index| (block, index)));
+        let depths = join_all(uncles.map(|(block, index)| async move {
+            let uncle = fetch_uncle(ctx.client, uncle_url, block, index).await?;
+            Ok(block.saturating_sub(quantity_field(&uncle, "number")?))
+        })).await;
+        let depths = depths.into_iter().collect::<RpcResult<Vec<u64>>>()?;
+        Ok(uncle_reward_outcome(&depths, blocks.count() as u64, self.max_ratio_pct))
+    }
+}
+
+/// An uncle `depth` blocks older than the block including it earns
+/// (8 - depth) / 8 of the block reward, so with a constant block reward the
+/// ratio needs only the depths
+fn uncle_reward_outcome(depths: &[u64], blocks: u64, max_ratio_pct: f64) -> CheckOutcome {
+    let uncle_rewards: f64 = depths.iter().map(|depth| 8u64.saturating_sub(*depth) as f64 / 8.0).sum();
+    let ratio_pct = uncle_rewards / blocks.max(1) as f64 * 100.0;
+    if ratio_pct > max_ratio_pct {
+        CheckOutcome::Alert(tr!("high_uncle_reward_ratio", ratio = format!("{:.1}", ratio_pct), uncles = depths.len(), blocks = blocks, max = max_ratio_pct))
+    } else {
+        CheckOutcome::Ok(format!("[OK] Uncle reward ratio: {:.1}% ({} uncles in {} blocks)", ratio_pct, depths.len(), blocks))
+    }
+}
+
+/// Alerts when the latest block's nonce doesn't meet its difficulty, i.e. the
+/// node accepted a block with an invalid proof of work (PoW chains)
+pub struct PowNonceCheck;
+
+#[async_trait]
+impl Check for PowNonceCheck {
+    fn name(&self) -> &'static str {
+        "pow_nonce"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let block = fetch_block(ctx.client, ctx.url_for("eth_getBlockByNumber"), "latest").await?;
+        let number = quantity_field(&block, "number")?;
+        let valid = match ethash::pow_seal(&block)? {
+            Some((difficulty, nonce)) => Some((difficulty, ethash::seal_is_valid(&block, difficulty, nonce)?)),
+            None => None,
+        };
+        let hash = block.get("hash").and_then(|v| v.as_str()).unwrap_or("unknown");
+        Ok(pow_nonce_outcome(number, hash, valid))
+    }
+}
+
+/// `seal` is the block's difficulty and whether its nonce meets it, None on PoS
+fn pow_nonce_outcome(block: u64, hash: &str, seal: Option<(u128, bool)>) -> CheckOutcome {
+    match seal {
+        // PoS blocks have zero nonce and difficulty; nothing to verify
+        None => CheckOutcome::Ok(format!("[OK] Block {} has no proof of work to verify", block)),
+        Some((difficulty, true)) => CheckOutcome::Ok(format!("[OK] Block {} nonce meets difficulty {}", block, difficulty)),
+        Some((difficulty, false)) => CheckOutcome::Alert(tr!("invalid_block_nonce", block = block, hash = hash, difficulty = difficulty)),
+    }
+}
+
+/// Alerts when the latest block carries more EIP-4844 blobs than expected
+pub struct BlobCountCheck {
+    pub max_blobs: u64,
+}
+
+#[async_trait]
+impl Check for BlobCountCheck {
+    fn name(&self) -> &'static str {
+        "blob_count"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let blobs = fetch_blob_count(ctx.client, ctx.url_for("eth_getBlockByNumber")).await?;
+        if let Some(blobs) = blobs {
+            crate::metrics::record_blob_count(blobs);
+        }
+        Ok(blob_count_outcome(blobs, self.max_blobs))
+    }
+}
+
+fn blob_count_outcome(blobs: Option<u64>, max_blobs: u64) -> CheckOutcome {
+    match blobs {
+        None => CheckOutcome::Ok("[OK] No blob data (pre-EIP-4844 chain)".to_string()),
+        Some(blobs) if blobs > max_blobs => CheckOutcome::Alert(tr!("high_blob_count", blobs = blobs, max = max_blobs)),
+        Some(blobs) => CheckOutcome::Ok(format!("[OK] Blobs in latest block: {}", blobs)),
+    }
+}
+
+/// Alerts when the finalized block falls too far behind the latest block. Finality
+/// normally trails by about 2 epochs (64 blocks); more means finality is failing
+/// even if the node itself keeps up with the head.
+pub struct FinalizationCheck {
+    pub max_distance: u64,
+}
+
+#[async_trait]
+impl Check for FinalizationCheck {
+    fn name(&self) -> &'static str {
+        "finalization_distance"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let latest = fetch_block_number(ctx.client, ctx.url_for("eth_blockNumber")).await?;
+        let finalized = fetch_finalized_block(ctx.client, ctx.url_for("eth_getBlockByNumber")).await?;
+        Ok(finalization_outcome(latest, finalized, self.max_distance))
+    }
+}
+
+fn finalization_outcome(latest: u64, finalized: u64, max_distance: u64) -> CheckOutcome {
+    let distance = latest.saturating_sub(finalized);
+    if distance > max_distance {
+        CheckOutcome::Alert(tr!("finalization_distance", latest = latest, finalized = finalized, distance = distance, max = max_distance))
+    } else {
+        CheckOutcome::Ok(format!("[OK] Finalized: {} ({} blocks behind latest)", finalized, distance))
+    }
+}
+
+/// Reports 'eth_syncing' progress and alerts when the sync percentage hasn't
+/// increased for `stuck_after`, which "is syncing" alone can't tell apart
+/// from a healthy sync
+pub struct SyncProgressCheck {
+    pub stuck_after: chrono::Duration,
+    /// Highest progress seen and when it was first reached
+    pub best: Option<(f64, DateTime<Utc>)>,
+}
+
+#[async_trait]
+impl Check for SyncProgressCheck {
+    fn name(&self) -> &'static str {
+        "sync_progress"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let status = fetch_sync_status(ctx.client, ctx.url_for("eth_syncing")).await?;
+        Ok(self.observe(status, Utc::now()))
+    }
+}
+
+impl SyncProgressCheck {
+    fn observe(&mut self, status: SyncStatus, now: DateTime<Utc>) -> CheckOutcome {
+        let SyncStatus::Syncing { current_block, highest_block, .. } = status else {
+            self.best = None;
+            return CheckOutcome::Ok("[OK] Not syncing".to_string());
+        };
+        let progress_pct = status.progress_pct();
+        let since = match self.best {
+            Some((best, since)) if progress_pct <= best => since,
+            _ => {
+                self.best = Some((progress_pct, now));
+                now
+            }
+        };
+        let stalled = now - since;
+        if stalled >= self.stuck_after {
+            CheckOutcome::Alert(tr!("sync_stuck", progress = format!("{:.2}", progress_pct), current = current_block, highest = highest_block, minutes = stalled.num_minutes()))
+        } else {
+            CheckOutcome::Ok(format!("[SYNCING] Block {} of {} ({:.2}%)", current_block, highest_block, progress_pct))
+        }
+    }
+}
+
+/// Error code clients return for an unknown (e.g. expired) filter ID
+const FILTER_NOT_FOUND: i64 = -32000;
+
+/// Keeps an 'eth_newFilter' log filter on the node and reports the events
+/// returned by 'eth_getFilterChanges' each run. Nodes drop idle filters after
+/// a few minutes, so an expired filter is recreated transparently.
+pub struct FilterCheck {
+    pub filter: Value,
+    pub filter_id: Option<String>,
+}
+
+impl FilterCheck {
+    async fn new_filter(&self, ctx: &CheckContext<'_>) -> RpcResult<String> {
+        let result = rpc_call(ctx.client, ctx.url_for("eth_newFilter"), "eth_newFilter", serde_json::json!([self.filter])).await?;
+        let id = result.as_str().ok_or("Invalid response: filter ID not a string")?;
+        info!("[INFO] Created log filter {}", id);
+        Ok(id.to_string())
+    }
+}
+
+#[async_trait]
+impl Check for FilterCheck {
+    fn name(&self) -> &'static str {
+        "filter_changes"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let id = match self.filter_id.take() {
+            Some(id) => id,
+            // A new filter only sees events from now on; nothing to report yet
+            None => {
+                self.filter_id = Some(self.new_filter(ctx).await?);
+                return Ok(CheckOutcome::Ok("[OK] Log filter installed".to_string()));
+            }
+        };
+
+        let url = ctx.url_for("eth_getFilterChanges");
+        match rpc_call(ctx.client, url, "eth_getFilterChanges", serde_json::json!([id])).await {
+            Ok(changes) => {
+                self.filter_id = Some(id);
+                let logs = changes.as_array().ok_or("Invalid response: filter changes not an array")?;
+                Ok(filter_changes_outcome(logs))
+            }
+            Err(e @ AppError::RpcError { code: FILTER_NOT_FOUND, .. }) => {
+                warn!("[WARN] Log filter {} expired, recreating: {}", id, e);
+                self.filter_id = Some(self.new_filter(ctx).await?);
+                Ok(CheckOutcome::Ok("[OK] Log filter recreated".to_string()))
+            }
+            // Keep the filter for the next run
+            Err(e) => {
+                self.filter_id = Some(id);
+                Err(e)
+            }
+        }
+    }
+}
+
+fn filter_changes_outcome(logs: &[Value]) -> CheckOutcome {
+    if logs.is_empty() {
+        return CheckOutcome::Ok("[OK] No new filter events".to_string());
+    }
+    for (address, topic0) in logs.iter().filter_map(parse_log) {
+        info!("[EVENT] Contract {} emitted event {}", address, topic0);
+    }
+    CheckOutcome::Alert(tr!("filter_events", count = logs.len()))
+}
+
+/// Blocks in the rolling reward average (plus the latest block being judged)
+const REWARD_WINDOW: u64 = 100;
+
+/// Alerts when the latest block's median priority fee reward spikes far above
+/// the rolling average of the previous blocks (possible MEV exploit or misconfiguration)
+pub struct RewardAnomalyCheck {
+    pub max_multiplier: f64,
+}
+
+#[async_trait]
+impl Check for RewardAnomalyCheck {
+    fn name(&self) -> &'static str {
+        "reward_anomaly"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let rewards = fetch_median_rewards(ctx.client, ctx.url_for("eth_feeHistory"), REWARD_WINDOW + 1).await?;
+        if let Some((_, previous)) = rewards.split_last().filter(|(_, previous)| !previous.is_empty()) {
+            crate::metrics::record_reward_average(previous.iter().sum::<u64>() as f64 / previous.len() as f64);
+        }
+        Ok(reward_anomaly_outcome(&rewards, self.max_multiplier))
+    }
+}
+
+fn reward_anomaly_outcome(rewards: &[u64], max_multiplier: f64) -> CheckOutcome {
+    let Some((latest, previous)) = rewards.split_last().filter(|(_, previous)| !previous.is_empty()) else {
+        return CheckOutcome::Ok("[OK] Not enough fee history for a reward average".to_string());
+    };
+    let average = previous.iter().sum::<u64>() as f64 / previous.len() as f64;
+    let gwei = |wei: f64| format!("{:.2}", wei / 1e9);
+
+    if *latest as f64 > average * max_multiplier {
+        CheckOutcome::Alert(tr!("reward_anomaly", latest = gwei(*latest as f64), average = gwei(average), max = max_multiplier))
+    } else {
+        CheckOutcome::Ok(format!("[OK] Block reward: {} gwei (average {} gwei)", gwei(*latest as f64), gwei(average)))
+    }
+}
+
+/// Whether a trace pattern match is the problem or its absence (TRACE_MATCH_MODE)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TraceMatchMode {
+    Present,
+    Absent,
+}
+
+impl std::str::FromStr for TraceMatchMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "present" => Ok(TraceMatchMode::Present),
+            "absent" => Ok(TraceMatchMode::Absent),
+            other => Err(format!("unknown trace match mode '{}'", other)),
+        }
+    }
+}
+
+/// Traces the latest block with 'debug_traceBlockByNumber' and matches a regex
+/// against each transaction's call trace (as JSON). Expensive: schedule sparingly.
+pub struct TraceCheck {
+    pub pattern: Regex,
+    pub mode: TraceMatchMode,
+}
+
+#[async_trait]
+impl Check for TraceCheck {
+    fn name(&self) -> &'static str {
+        "block_trace"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let block = fetch_block(ctx.client, ctx.url_for("eth_getBlockByNumber"), "latest").await?;
+        let number = block.get("number").and_then(Value::as_str).ok_or("Invalid response: 'number' missing")?;
+        let traces = fetch_block_traces(ctx.client, ctx.url_for("debug_traceBlockByNumber"), number).await?;
+        Ok(trace_outcome(&traces, &self.pattern, self.mode, number))
+    }
+}
+
+fn trace_outcome(traces: &[Value], pattern: &Regex, mode: TraceMatchMode, block: &str) -> CheckOutcome {
+    // Geth returns [{ txHash, result }]; older clients only { result }
+    let matched = traces.iter().find(|trace| {
+        let call = trace.get("result").unwrap_or(trace);
+        pattern.is_match(&call.to_string())
+    });
+    let tx = matched.and_then(|trace| trace.get("txHash")).and_then(Value::as_str).unwrap_or("unknown");
+
+    match (matched.is_some(), mode) {
+        (true, TraceMatchMode::Present) => CheckOutcome::Alert(tr!("trace_pattern_found", block = block, tx = tx, pattern = pattern.as_str())),
+        (false, TraceMatchMode::Absent) => CheckOutcome::Alert(tr!("trace_pattern_absent", block = block, pattern = pattern.as_str())),
+        _ => CheckOutcome::Ok(format!("[OK] Block {} traces: {} transactions checked", block, traces.len())),
+    }
+}
+
+/// Alerts when a validator earns less than a share of its ideal attestation rewards
+/// in the last completed epoch (Beacon API attestation rewards)
+pub struct AttestationCheck {
+    pub beacon_url: String,
+    pub validator_index: u64,
+    pub min_effectiveness_pct: f64,
+}
+
+#[async_trait]
+impl Check for AttestationCheck {
+    fn name(&self) -> &'static str {
+        "attestation_effectiveness"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let index = self.validator_index;
+        let validator = beacon_get(ctx.client, &self.beacon_url, &format!("/eth/v1/beacon/states/head/validators/{}", index)).await?;
+        let effective_balance = validator.pointer("/validator/effective_balance")
+            .and_then(parse_decimal)
+            .ok_or("Invalid response: effective_balance missing")?;
+
+        // The current and previous epochs may still be in flight; score the one before
+        let epoch = (fetch_head_slot(ctx.client, &self.beacon_url).await? / SLOTS_PER_EPOCH).saturating_sub(2);
+        let body = serde_json::json!([index.to_string()]);
+        let rewards = beacon_post(ctx.client, &self.beacon_url, &format!("/eth/v1/beacon/rewards/attestations/{}", epoch), &body).await?;
+
+        let pct = attestation_effectiveness_pct(&rewards, index, effective_balance)
+            .ok_or("Invalid response: no attestation rewards for validator")?;
+        Ok(attestation_outcome(index, epoch, pct, self.min_effectiveness_pct))
+    }
+}
+
+/// Earned head+target+source rewards as a percentage of the ideal for this effective balance
+fn attestation_effectiveness_pct(rewards: &Value, index: u64, effective_balance: i64) -> Option<f64> {
+    let sum = |entry: &Value| -> Option<i64> {
+        ["head", "target", "source"].iter().map(|k| entry.get(*k).and_then(parse_decimal)).sum()
+    };
+
+    let ideal = rewards.get("ideal_rewards")?.as_array()?.iter()
+        .find(|entry| entry.get("effective_balance").and_then(parse_decimal) == Some(effective_balance))
+        .and_then(sum)?;
+    let actual = rewards.get("total_rewards")?.as_array()?.iter()
+        .find(|entry| entry.get("validator_index").and_then(parse_decimal) == Some(index as i64))
+        .and_then(sum)?;
+
+    if ideal <= 0 {
+        return None;
+    }
+    Some(actual.max(0) as f64 / ideal as f64 * 100.0)
+}
+
+fn attestation_outcome(index: u64, epoch: u64, pct: f64, min_pct: f64) -> CheckOutcome {
+    if pct < min_pct {
+        CheckOutcome::Alert(tr!("low_attestation_effectiveness", index = index, epoch = epoch, pct = format!("{:.1}", pct), min = min_pct))
+    } else {
+        CheckOutcome::Ok(format!("[OK] Validator {} attestation effectiveness: {:.1}% (epoch {})", index, pct, epoch))
+    }
+}
+
+/// Alerts when the fork active at the Beacon node's head epoch isn't the expected
+/// one, e.g. after a hard fork the node didn't apply (a minority chain)
+pub struct ForkVersionCheck {
+    pub beacon_url: String,
+    pub expected_version: String,
+}
+
+#[async_trait]
+impl Check for ForkVersionCheck {
+    fn name(&self) -> &'static str {
+        "fork_version"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let schedule = beacon_get(ctx.client, &self.beacon_url, "/eth/v1/config/fork_schedule").await?;
+        let epoch = fetch_head_slot(ctx.client, &self.beacon_url).await? / SLOTS_PER_EPOCH;
+        let active = active_fork_version(&schedule, epoch).ok_or("Invalid response: no fork active at the head epoch")?;
+        Ok(fork_version_outcome(&active, &self.expected_version, epoch))
+    }
+}
+
+/// `current_version` of the latest fork in the schedule whose epoch has been reached
+fn active_fork_version(schedule: &Value, epoch: u64) -> Option<String> {
+    schedule.as_array()?.iter()
+        .filter_map(|fork| {
+            let fork_epoch = fork.get("epoch").and_then(parse_decimal)?;
+            let version = fork.get("current_version")?.as_str()?;
+            Some((fork_epoch, version))
+        })
+        .filter(|(fork_epoch, _)| *fork_epoch as u64 <= epoch)
+        .max_by_key(|(fork_epoch, _)| *fork_epoch)
+        .map(|(_, version)| version.to_string())
+}
+
+fn fork_version_outcome(active: &str, expected: &str, epoch: u64) -> CheckOutcome {
+    if active.eq_ignore_ascii_case(expected) {
+        CheckOutcome::Ok(format!("[OK] Fork version {} active at epoch {}", active, epoch))
+    } else {
+        CheckOutcome::Alert(tr!("fork_version_mismatch", active = active, expected = expected, epoch = epoch))
+    }
+}
+
+/// Alerts when the Beacon node reports 'el_offline', i.e. the consensus client
+/// lost its Engine API connection to the execution client
+pub struct BeaconElStatusCheck {
+    pub beacon_url: String,
+}
+
+#[async_trait]
+impl Check for BeaconElStatusCheck {
+    fn name(&self) -> &'static str {
+        "beacon_el_status"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let syncing = beacon_get(ctx.client, &self.beacon_url, "/eth/v1/node/syncing").await?;
+        let el_offline = syncing.get("el_offline").and_then(Value::as_bool).ok_or("Invalid response: 'el_offline' missing")?;
+        let slot = syncing.get("head_slot").and_then(parse_decimal).unwrap_or(0);
+        Ok(beacon_el_status_outcome(el_offline, slot))
+    }
+}
+
+fn beacon_el_status_outcome(el_offline: bool, slot: i64) -> CheckOutcome {
+    if el_offline {
+        CheckOutcome::Alert(tr!("beacon_el_offline", slot = slot))
+    } else {
+        CheckOutcome::Ok(format!("[OK] Beacon node is connected to its execution client (head slot {})", slot))
+    }
+}
+
+/// Alerts when the head block of the Beacon chain includes proposer or
+/// attester slashings, naming the slashed validators
+pub struct SlashingCheck {
+    pub beacon_url: String,
+}
+
+#[async_trait]
+impl Check for SlashingCheck {
+    fn name(&self) -> &'static str {
+        "slashing"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let block = beacon_get(ctx.client, &self.beacon_url, "/eth/v2/beacon/blocks/head").await?;
+        slashing_outcome(&block)
+    }
+}
+
+/// Validators slashed by a block body: the proposers of double-signed
+/// headers, and the validators in both of two conflicting attestations
+fn slashed_validators(body: &Value) -> Vec<i64> {
+    let list = |field: &str| body.get(field).and_then(Value::as_array).cloned().unwrap_or_default();
+    let attesters = |attestation: &Value| -> Vec<i64> {
+        attestation.get("attesting_indices").and_then(Value::as_array)
+            .map(|indices| indices.iter().filter_map(parse_decimal).collect())
+            .unwrap_or_default()
+    };
+
+    let mut slashed: Vec<i64> = list("proposer_slashings").iter()
+        .filter_map(|slashing| slashing.pointer("/signed_header_1/message/proposer_index").and_then(parse_decimal))
+        .collect();
+    for slashing in list("attester_slashings") {
+        let first = slashing.get("attestation_1").map(attesters).unwrap_or_default();
+        let second = slashing.get("attestation_2").map(attesters).unwrap_or_default();
+        slashed.extend(first.into_iter().filter(|index| second.contains(index)));
+    }
+    slashed.sort_unstable();
+    slashed.dedup();
+    slashed
+}
+
+fn slashing_outcome(block: &Value) -> RpcResult<CheckOutcome> {
+    let message = block.get("message").ok_or("Invalid response: 'message' missing")?;
+    let body = message.get("body").ok_or("Invalid response: 'body' missing")?;
+    let slot = message.get("slot").and_then(parse_decimal).ok_or("Invalid response: 'slot' missing")?;
+    // Post-merge blocks carry the execution block number; the slot otherwise
+    let block_number = body.pointer("/execution_payload/block_number").and_then(parse_decimal).unwrap_or(slot);
+
+    let slashings = ["proposer_slashings", "attester_slashings"].iter()
+        .map(|field| body.get(*field).and_then(Value::as_array).map_or(0, Vec::len))
+        .sum::<usize>();
+    if slashings == 0 {
+        return Ok(CheckOutcome::Ok(format!("[OK] No slashings in block {} (slot {})", block_number, slot)));
+    }
+    let validators = slashed_validators(body).iter().map(i64::to_string).collect::<Vec<_>>().join(", ");
+    Ok(CheckOutcome::Alert(tr!("slashing_detected", block = block_number, slot = slot, count = slashings, validators = validators)))
+}
+
+/// Alerts when the execution client's block at the Beacon node's finalized
+/// height isn't the one the consensus layer finalized. The finality checkpoint
+/// names a beacon block; its execution payload holds the EL block hash. The EL
+/// block is looked up by that number rather than by the 'finalized' tag, so a
+/// checkpoint advancing between the two requests can't raise a false alert
+pub struct FinalityHashCheck {
+    pub beacon_url: String,
+}
+
+#[async_trait]
+impl Check for FinalityHashCheck {
+    fn name(&self) -> &'static str {
+        "finality_hash"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let checkpoints = beacon_get(ctx.client, &self.beacon_url, "/eth/v1/beacon/states/finalized/finality_checkpoints").await?;
+        let root = checkpoints.pointer("/finalized/root").and_then(Value::as_str).ok_or("Invalid response: 'finalized.root' missing")?;
+        let block = beacon_get(ctx.client, &self.beacon_url, &format!("/eth/v2/beacon/blocks/{}", root)).await?;
+        let Some(payload) = block.pointer("/message/body/execution_payload") else {
+            return Ok(CheckOutcome::Ok("[INFO] Finalized beacon block predates the merge, nothing to compare".to_string()));
+        };
+        let number = payload.get("block_number").and_then(parse_decimal).ok_or("Invalid response: 'execution_payload.block_number' missing")?;
+        let cl_hash = payload.get("block_hash").and_then(Value::as_str).ok_or("Invalid response: 'execution_payload.block_hash' missing")?;
+
+        let el_block = fetch_block(ctx.client, ctx.url_for("eth_getBlockByNumber"), &format!("0x{:x}", number)).await?;
+        let el_hash = el_block.get("hash").and_then(Value::as_str).ok_or("Invalid response: block 'hash' missing")?;
+        Ok(finality_hash_outcome(number, cl_hash, el_hash))
+    }
+}
+
+fn finality_hash_outcome(number: i64, cl_hash: &str, el_hash: &str) -> CheckOutcome {
+    if cl_hash.eq_ignore_ascii_case(el_hash) {
+        CheckOutcome::Ok(format!("[OK] Finalized block {} agrees with the Beacon node ({})", number, el_hash))
+    } else {
+        CheckOutcome::Alert(tr!("finality_hash_mismatch", block = number, cl_hash = cl_hash, el_hash = el_hash))
+    }
+}
+
+/// Slots of one sync committee period (256 epochs)
+const SLOTS_PER_SYNC_PERIOD: u64 = 256 * SLOTS_PER_EPOCH;
+
+/// Alerts when the sync committee signed fewer than `min_pct` of the head
+/// blocks' slots in the current sync period. The pool endpoint only accepts
+/// submissions, so participation is read from each head block's
+/// `sync_aggregate` bits against the committee size of the head state
+pub struct SyncCommitteeCheck {
+    pub beacon_url: String,
+    pub min_pct: f64,
+    /// Period, committee size, signatures and possible signatures so far
+    period: Option<(u64, u64, u64, u64)>,
+    last_slot: Option<u64>,
+}
+
+impl SyncCommitteeCheck {
+    pub fn new(beacon_url: String, min_pct: f64) -> Self {
+        SyncCommitteeCheck { beacon_url, min_pct, period: None, last_slot: None }
+    }
+}
+
+#[async_trait]
+impl Check for SyncCommitteeCheck {
+    fn name(&self) -> &'static str {
+        "sync_committee"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let block = beacon_get(ctx.client, &self.beacon_url, "/eth/v2/beacon/blocks/head").await?;
+        let slot = block.pointer("/message/slot").and_then(parse_decimal).ok_or("Invalid response: 'slot' missing")? as u64;
+        let bits = block.pointer("/message/body/sync_aggregate/sync_committee_bits").and_then(Value::as_str)
+            .ok_or("Invalid response: 'sync_aggregate' missing (pre-Altair block)")?;
+        let signatures = count_set_bits(bits)?;
+
+        let period = slot / SLOTS_PER_SYNC_PERIOD;
+        if self.period.is_none_or(|(current, ..)| current != period) {
+            let committee = beacon_get(ctx.client, &self.beacon_url, "/eth/v1/beacon/states/head/sync_committees").await?;
+            let size = committee.get("validators").and_then(Value::as_array).map(Vec::len)
+                .filter(|size| *size > 0)
+                .ok_or("Invalid response: sync committee 'validators' missing")? as u64;
+            self.period = Some((period, size, 0, 0));
+        }
+        let (_, size, signed, possible) = self.period.as_mut().expect("set above");
+        // The same head seen twice is only counted once
+        if self.last_slot != Some(slot) {
+            self.last_slot = Some(slot);
+            *signed += signatures;
+            *possible += *size;
+        }
+        Ok(sync_committee_outcome(period, *signed, *possible, self.min_pct))
+    }
+}
+
+/// Participants in a hex-encoded SSZ bitvector
+fn count_set_bits(hex: &str) -> RpcResult<u64> {
+    Ok(parse_hex_bytes(hex)?.iter().map(|byte| byte.count_ones() as u64).sum())
+}
+
+fn sync_committee_outcome(period: u64, signed: u64, possible: u64, min_pct: f64) -> CheckOutcome {
+    let pct = 100.0 * signed as f64 / possible.max(1) as f64;
+    if pct < min_pct {
+        CheckOutcome::Alert(tr!("low_sync_committee_participation", pct = format!("{:.1}", pct), min = min_pct, period = period))
+    } else {
+        CheckOutcome::Ok(format!("[OK] Sync committee participation: {:.1}% in period {}", pct, period))
+    }
+}
+
+/// Alerts when the disk holding DATA_DIR is busy more than `warn_pct` of the
+/// time; utilization comes from a sampler task, over its last window
+pub struct IoSaturationCheck {
+    pub device: String,
+    pub utilization: tokio::sync::watch::Receiver<Option<f64>>,
+    pub warn_pct: f64,
+}
+
+#[async_trait]
+impl Check for IoSaturationCheck {
+    fn name(&self) -> &'static str {
+        "io_saturation"
+    }
+
+    async fn run(&mut self, _ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let utilization = *self.utilization.borrow();
+        Ok(match utilization {
+            Some(pct) => io_saturation_outcome(&self.device, pct, self.warn_pct),
+            None => CheckOutcome::Ok(format!("[INFO] Disk I/O on {} | First sample still in progress", self.device)),
+        })
+    }
+}
+
+fn io_saturation_outcome(device: &str, pct: f64, warn_pct: f64) -> CheckOutcome {
+    let window = diskio::IO_SAMPLE_WINDOW.as_secs();
+    if pct > warn_pct {
+        CheckOutcome::Alert(tr!("io_saturated", device = device, pct = format!("{:.1}", pct), max = warn_pct, window = window))
+    } else {
+        CheckOutcome::Ok(format!("[OK] Disk I/O on {} | Utilization: {:.1}% over {}s", device, pct, window))
+    }
+}
+
+/// Alerts when more than `warn_mb_per_sec` was written to the disk holding
+/// DATA_DIR per second, over the sampler's last window
+pub struct DiskWriteThroughputCheck {
+    pub device: String,
+    pub throughput: tokio::sync::watch::Receiver<Option<f64>>,
+    pub warn_mb_per_sec: f64,
+}
+
+#[async_trait]
+impl Check for DiskWriteThroughputCheck {
+    fn name(&self) -> &'static str {
+        "disk_write_throughput"
+    }
+
+    async fn run(&mut self, _ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let throughput = *self.throughput.borrow();
+        Ok(match throughput {
+            Some(mb_per_sec) => disk_write_throughput_outcome(&self.device, mb_per_sec, self.warn_mb_per_sec),
+            None => CheckOutcome::Ok(format!("[INFO] Disk writes on {} | First sample still in progress", self.device)),
+        })
+    }
+}
+
+fn disk_write_throughput_outcome(device: &str, mb_per_sec: f64, warn_mb_per_sec: f64) -> CheckOutcome {
+    let window = diskio::WRITE_SAMPLE_WINDOW.as_secs();
+    if mb_per_sec > warn_mb_per_sec {
+        CheckOutcome::Alert(tr!("disk_write_throughput_high", device = device, rate = format!("{:.1}", mb_per_sec), max = warn_mb_per_sec, window = window))
+    } else {
+        CheckOutcome::Ok(format!("[OK] Disk writes on {} | {:.1} MB/s over {}s", device, mb_per_sec, window))
+    }
+}
+
+/// Alerts on lines of the client's log file matching CLIENT_LOG_ERROR_PATTERNS,
+/// collected by a tailer task since the last run. All of them go into one
+/// alert, so a burst of errors stays one message per cooldown
+pub struct ClientLogCheck {
+    pub path: String,
+    pub matches: tokio::sync::mpsc::Receiver<String>,
+}
+
+#[async_trait]
+impl Check for ClientLogCheck {
+    fn name(&self) -> &'static str {
+        "client_log"
+    }
+
+    async fn run(&mut self, _ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let mut lines = Vec::new();
+        while let Ok(line) = self.matches.try_recv() {
+            lines.push(line);
+        }
+        Ok(client_log_outcome(&self.path, &lines))
+    }
+}
+
+fn client_log_outcome(path: &str, lines: &[String]) -> CheckOutcome {
+    match lines {
+        [] => CheckOutcome::Ok(format!("[OK] No errors in {}", path)),
+        [line] => CheckOutcome::Alert(tr!("client_log_error", line = line)),
+        [line, rest @ ..] => CheckOutcome::Alert(format!("{} {}", tr!("client_log_error", line = line), tr!("client_log_more_errors", count = rest.len()))),
+    }
+}
+
+/// Validator status that needs no attention
+const ACTIVE_ONGOING: &str = "active_ongoing";
+
+/// Status of one validator as reported by the Beacon API
+#[derive(Debug, PartialEq)]
+struct ValidatorStatus {
+    index: u64,
+    status: String,
+    slashed: bool,
+    balance_gwei: Option<i64>,
+}
+
+/// Alerts when any of the validators is not `active_ongoing` (e.g. slashed or
+/// exited) and records their balances
+pub struct ValidatorStatusCheck {
+    pub beacon_url: String,
+    pub indices: Vec<u64>,
+}
+
+#[async_trait]
+impl Check for ValidatorStatusCheck {
+    fn name(&self) -> &'static str {
+        "validator_status"
+    }
+
+    async fn run(&mut self, ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+        let beacon_url = &self.beacon_url;
+        let responses = join_all(self.indices.iter().map(|index| async move {
+            beacon_get(ctx.client, beacon_url, &format!("/eth/v1/beacon/states/head/validators/{}", index)).await
+        })).await;
+
+        let mut statuses = Vec::new();
+        for (index, response) in self.indices.iter().zip(responses) {
+            let status = validator_status(*index, &response?).ok_or("Invalid response: validator status missing")?;
+            if let Some(balance) = status.balance_gwei {
+                crate::metrics::record_validator_balance(status.index, balance);
+            }
+            statuses.push(status);
+        }
+        Ok(validator_status_outcome(&statuses))
+    }
+}
+
+fn validator_status(index: u64, data: &Value) -> Option<ValidatorStatus> {
+    Some(ValidatorStatus {
+        index,
+        status: data.get("status")?.as_str()?.to_string(),
+        slashed: data.pointer("/validator/slashed").and_then(Value::as_bool).unwrap_or(false),
+        balance_gwei: data.get("balance").and_then(parse_decimal),
+    })
+}
+
+fn validator_status_outcome(statuses: &[ValidatorStatus]) -> CheckOutcome {
+    let unhealthy: Vec<String> = statuses.iter()
+        .filter(|v| v.status != ACTIVE_ONGOING || v.slashed)
+        .map(|v| if v.slashed && !v.status.contains("slashed") { format!("{} (slashed, {})", v.index, v.status) } else { format!("{} ({})", v.index, v.status) })
+        .collect();
+    if unhealthy.is_empty() {
+        CheckOutcome::Ok(format!("[OK] Validators active: {}", statuses.len()))
+    } else {
+        CheckOutcome::Alert(tr!("validator_not_active", validators = unhealthy.join(", ")))
+    }
+}
+
+// --- Startup Checks ---
+
+/// Compares 'eth_protocolVersion' with the value stored by the previous run,
+/// then records the current value. A change usually means a client downgrade.
+pub async fn check_protocol_version(client: &reqwest::Client, url: &str, state: &mut State) -> RpcResult<CheckOutcome> {
+    let current = fetch_protocol_version(client, url).await?;
+    crate::metrics::record_protocol_version(&current);
+    let outcome = protocol_version_outcome(state.protocol_version.as_deref(), &current);
+    state.protocol_version = Some(current);
+    Ok(outcome)
+}
+
+fn protocol_version_outcome(previous: Option<&str>, current: &str) -> CheckOutcome {
+    match previous {
+        Some(previous) if previous != current => {
+            CheckOutcome::Alert(tr!("protocol_version_changed", previous = previous, current = current))
+        }
+        _ => CheckOutcome::Ok(format!("[OK] Protocol version: {}", current)),
+    }
+}
+
+/// Compares the enode and ENR from 'admin_nodeInfo' with the ones stored by
+/// the previous run, then records the current ones. A change means the node's
+/// address changed or it was started with a different node key
+pub async fn check_enr_stability(client: &reqwest::Client, url: &str, state: &mut State) -> RpcResult<CheckOutcome> {
+    let info = rpc_call(client, url, "admin_nodeInfo", serde_json::json!([])).await?;
+    let field = |name: &str| info.get(name).and_then(Value::as_str).map(str::to_string);
+    let enode = field("enode").ok_or("Invalid response: 'enode' missing")?;
+    // Not every client reports an ENR
+    let enr = field("enr");
+
+    let mut changed = Vec::new();
+    if let Some(previous) = &state.enode && *previous != enode {
+        changed.push(tr!("identity_changed", field = "enode", previous = previous, current = &enode));
+    }
+    if let Some(previous) = &state.enr && let Some(current) = &enr && previous != current {
+        changed.push(tr!("identity_changed", field = "ENR", previous = previous, current = current));
+    }
+    state.enode = Some(enode.clone());
+    state.enr = enr.or(state.enr.take());
+    Ok(enr_stability_outcome(&enode, &changed))
+}
+
+fn enr_stability_outcome(enode: &str, changed: &[String]) -> CheckOutcome {
+    if changed.is_empty() {
+        CheckOutcome::Ok(format!("[OK] Node identity unchanged: {}", enode))
+    } else {
+        CheckOutcome::Alert(tr!("enr_changed", changes = changed.join("; ")))
+    }
+}
+
+/// Compares the HTTP RPC listen address the client reports in 'admin_nodeInfo'
+/// (rpc.http.host, client-specific) with EXPECTED_RPC_BIND_ADDRESS
+pub async fn check_rpc_bind_address(client: &reqwest::Client, url: &str, expected: &str) -> RpcResult<CheckOutcome> {
+    let info = rpc_call(client, url, "admin_nodeInfo", serde_json::json!([])).await?;
+    let host = info.pointer("/rpc/http/host")
+        .and_then(Value::as_str)
+        .ok_or("Invalid response: 'rpc.http.host' missing (not reported by this client)")?;
+    Ok(rpc_bind_address_outcome(host, expected))
+}
+
+fn rpc_bind_address_outcome(actual: &str, expected: &str) -> CheckOutcome {
+    // Parsed when possible, so e.g. "::" and "0:0:0:0:0:0:0:0" match
+    let matches = match (actual.parse::<std::net::IpAddr>(), expected.parse::<std::net::IpAddr>()) {
+        (Ok(actual), Ok(expected)) => actual == expected,
+        _ => actual.eq_ignore_ascii_case(expected),
+    };
+    if matches {
+        CheckOutcome::Ok(format!("[OK] RPC server listening on {}", actual))
+    } else {
+        CheckOutcome::Alert(tr!("rpc_bind_address_mismatch", actual = actual, expected = expected))
+    }
+}
+
+/// Compares the stateRoot of CHECKPOINT_BLOCK with CHECKPOINT_STATE_ROOT, so
+/// a node that replayed from a corrupted checkpoint after a crash is caught
+pub async fn check_checkpoint_state_root(client: &reqwest::Client, url: &str, block: u64, expected: &str) -> RpcResult<CheckOutcome> {
+    let header = fetch_block(client, url, &format!("0x{:x}", block)).await?;
+    let state_root = header.get("stateRoot").and_then(Value::as_str).ok_or("Invalid response: 'stateRoot' missing")?;
+    Ok(checkpoint_state_root_outcome(block, state_root, expected))
+}
+
+fn checkpoint_state_root_outcome(block: u64, actual: &str, expected: &str) -> CheckOutcome {
+    if actual.eq_ignore_ascii_case(expected) {
+        CheckOutcome::Ok(format!("[OK] State root of checkpoint block {} matches", block))
+    } else {
+        CheckOutcome::Alert(tr!("checkpoint_state_root_mismatch", block = block, actual = actual, expected = expected))
+    }
+}
+
+/// Blocks sampled when inferring the chain's block time
+const BLOCK_TIME_SAMPLE: u64 = 100;
+
+/// Average seconds between blocks over the last `BLOCK_TIME_SAMPLE` blocks
+pub async fn infer_block_time(client: &reqwest::Client, url: &str) -> RpcResult<u64> {
+    let latest = fetch_block(client, url, "latest").await?;
+    let number = quantity_field(&latest, "number")?;
+    let oldest = fetch_block(client, url, &format!("0x{:x}", number.saturating_sub(BLOCK_TIME_SAMPLE))).await?;
+    let span = number - quantity_field(&oldest, "number")?;
+    if span == 0 {
+        return Err("Not enough blocks to infer the block time".into());
+    }
+    let elapsed = quantity_field(&latest, "timestamp")?.saturating_sub(quantity_field(&oldest, "timestamp")?);
+    Ok((elapsed as f64 / span as f64).round() as u64)
+}
+
+/// Polling faster than half the time it takes to fall `lag_threshold` blocks
+/// behind only adds RPC load. Returns the warning with a suggested minimum.
+pub fn poll_interval_warning(poll_interval_secs: u64, block_time_secs: u64, lag_threshold: u64) -> Option<String> {
+    let suggested = block_time_secs * lag_threshold / 2;
+    (poll_interval_secs < suggested).then(|| format!(
+        "[WARN] Poll interval may be unnecessarily frequent: polling every {}s with {}s blocks and a lag threshold of {}. Suggested minimum POLL_INTERVAL_SECONDS: {}",
+        poll_interval_secs, block_time_secs, lag_threshold, suggested,
+    ))
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct UnsupportedCheck;
+
+    #[async_trait]
+    impl Check for UnsupportedCheck {
+        fn name(&self) -> &'static str {
+            "unsupported"
+        }
+
+        async fn run(&mut self, _ctx: &CheckContext<'_>) -> RpcResult<CheckOutcome> {
+            Err(AppError::RpcError { code: -32601, message: "the method txpool_status does not exist/is not available".to_string() })
+        }
+    }
+
+    #[test]
+    fn test_method_not_found_disables_check_on_first_run() {
+        let config = CheckConfig { interval_multiplier: 1, check_availability: true };
+        let mut scheduled = ScheduledCheck::new(UnsupportedCheck, config);
+        let unsupported = AppError::RpcError { code: -32601, message: "method not found".to_string() };
+        let timeout = AppError::RpcError { code: -32000, message: "request timed out".to_string() };
+
+        // Later failures alert; only an unsupported method on the first run disables
+        assert!(matches!(scheduled.handle_error(&timeout, false), Some(CheckOutcome::Alert(_))));
+        assert!(scheduled.available);
+        assert!(scheduled.handle_error(&unsupported, true).is_none());
+        assert!(!scheduled.available);
+
+        let mut scheduled = ScheduledCheck::new(UnsupportedCheck, config);
+        let not_reported = AppError::NotReported("rpc.http.connections".to_string());
+        assert!(scheduled.handle_error(&not_reported, true).is_none());
+        assert!(!scheduled.available);
+    }
+
+    #[test]
+    fn test_errors_only_logged_without_availability_check() {
+        let config = CheckConfig { interval_multiplier: 1, check_availability: false };
+        let mut scheduled = ScheduledCheck::new(UnsupportedCheck, config);
+        let unsupported = AppError::RpcError { code: -32601, message: "method not found".to_string() };
+        assert!(scheduled.handle_error(&unsupported, true).is_none());
+        assert!(scheduled.available);
+    }
+
+    #[test]
+    fn test_poll_interval_warning() {
+        // Mainnet: 12s blocks, threshold 3 => polling under 18s is wasteful
+        let warning = poll_interval_warning(1, 12, 3).unwrap();
+        assert!(warning.contains("Suggested minimum POLL_INTERVAL_SECONDS: 18"));
+        assert!(poll_interval_warning(18, 12, 3).is_none());
+        assert!(poll_interval_warning(60, 12, 3).is_none());
+    }
+
+    #[test]
+    fn test_sync_progress_stuck_alerts() {
+        let mut check = SyncProgressCheck { stuck_after: chrono::Duration::minutes(30), best: None };
+        let start = Utc::now();
+        let status = |current| SyncStatus::Syncing { current_block: current, highest_block: 1000, blocks_remaining: 1000 - current };
+
+        assert!(matches!(check.observe(status(500), start), CheckOutcome::Ok(line) if line.contains("50.00%")));
+        // No progress for 20 minutes is still fine; 30 minutes is stuck
+        let later = start + chrono::Duration::minutes(20);
+        assert!(matches!(check.observe(status(500), later), CheckOutcome::Ok(_)));
+        let stuck = start + chrono::Duration::minutes(30);
+        assert!(matches!(check.observe(status(500), stuck), CheckOutcome::Alert(_)));
+        // Progress restarts the clock
+        assert!(matches!(check.observe(status(510), stuck), CheckOutcome::Ok(_)));
+        assert!(matches!(check.observe(SyncStatus::Synced, stuck), CheckOutcome::Ok(line) if line.contains("Not syncing")));
+        assert!(check.best.is_none());
+    }
+
+    #[test]
+    fn test_active_fork_version_from_schedule() {
+        let schedule = serde_json::json!([
+            { "previous_version": "0x00000000", "current_version": "0x00000000", "epoch": "0" },
+            { "previous_version": "0x00000000", "current_version": "0x01000000", "epoch": "74240" },
+            { "previous_version": "0x03000000", "current_version": "0x04000000", "epoch": "269568" },
+            { "previous_version": "0x04000000", "current_version": "0x05000000", "epoch": "18446744073709551615" }
+        ]);
+        assert_eq!(active_fork_version(&schedule, 100).as_deref(), Some("0x00000000"));
+        assert_eq!(active_fork_version(&schedule, 74240).as_deref(), Some("0x01000000"));
+        // Scheduled but not reached yet
+        assert_eq!(active_fork_version(&schedule, 300_000).as_deref(), Some("0x04000000"));
+
+        assert!(matches!(fork_version_outcome("0x04000000", "0x04000000", 300_000), CheckOutcome::Ok(_)));
+        let outcome = fork_version_outcome("0x03000000", "0x04000000", 300_000);
+        assert!(matches!(outcome, CheckOutcome::Alert(msg) if msg.contains("Active: 0x03000000")));
+    }
+
+    #[test]
+    fn test_validator_status_alerts_for_slashed_or_exited() {
+        let data = serde_json::json!({ "index": "7", "balance": "32001000000", "status": "active_ongoing", "validator": { "slashed": false } });
+        let active = validator_status(7, &data).unwrap();
+        assert_eq!(active, ValidatorStatus { index: 7, status: "active_ongoing".to_string(), slashed: false, balance_gwei: Some(32_001_000_000) });
+        assert!(matches!(validator_status_outcome(&[active]), CheckOutcome::Ok(_)));
+
+        let status = |index, status: &str, slashed| ValidatorStatus { index, status: status.to_string(), slashed, balance_gwei: None };
+        let statuses = [status(1, "active_ongoing", false), status(2, "exited_unslashed", false), status(3, "active_slashed", true)];
+        let outcome = validator_status_outcome(&statuses);
+        assert!(matches!(outcome, CheckOutcome::Alert(msg) if msg.contains("2 (exited_unslashed), 3 (active_slashed)")));
+    }
+
+    #[test]
+    fn test_pending_tx_age_tracks_first_seen() {
+        let content = serde_json::json!({
+            "pending": {
+                "0xaaa": { "5": { "hash": "0x01", "nonce": "0x5" }, "6": { "hash": "0x02", "nonce": "0x6" } },
+                "0xbbb": { "0": { "hash": "0x03", "nonce": "0x0" } }
+            },
+            "queued": { "0xccc": { "9": { "hash": "0x09" } } }
+        });
+        let mut hashes = pending_tx_hashes(&content);
+        hashes.sort();
+        assert_eq!(hashes, vec!["0x01", "0x02", "0x03"]);
+
+        let mut check = PendingTxAgeCheck { max_age: chrono::Duration::seconds(600), first_seen: HashMap::new(), supported: true };
+        let start = Utc::now();
+        let at = |secs| start + chrono::Duration::seconds(secs);
+        assert!(matches!(check.observe(hashes, at(0)), CheckOutcome::Ok(_)));
+        // 0x01 and 0x02 got mined; 0x03 is still pending, 0x04 is new
+        let still_pending = vec!["0x03".to_string(), "0x04".to_string()];
+        assert!(matches!(check.observe(still_pending.clone(), at(300)), CheckOutcome::Ok(line) if line.contains("300s")));
+        assert_eq!(check.first_seen.len(), 2);
+        let outcome = check.observe(still_pending, at(601));
+        assert!(matches!(outcome, CheckOutcome::Alert(msg) if msg.contains("0x03") && msg.contains("601")));
+        assert!(matches!(check.observe(Vec::new(), at(700)), CheckOutcome::Ok(line) if line.contains("No pending")));
+    }
+
+    #[test]
+    fn test_health_endpoint_outcome() {
+        let healthy = serde_json::json!({ "status": "Healthy", "totalDuration": "00:00:00.01" });
+        assert!(matches!(health_endpoint_outcome(200, Some(&healthy)), CheckOutcome::Ok(line) if line.contains("Healthy")));
+        assert!(matches!(health_endpoint_outcome(200, None), CheckOutcome::Ok(line) if line.contains("unknown")));
+
+        let unhealthy = serde_json::json!({ "status": "Unhealthy" });
+        assert!(matches!(health_endpoint_outcome(200, Some(&unhealthy)), CheckOutcome::Alert(_)));
+        let outcome = health_endpoint_outcome(503, None);
+        assert!(matches!(outcome, CheckOutcome::Alert(msg) if msg.contains("HTTP 503")));
+    }
+
+    #[test]
+    fn test_tx_success_rate() {
+        assert_eq!(receipt_succeeded(&serde_json::json!({ "status": "0x1" })), Some(true));
+        assert_eq!(receipt_succeeded(&serde_json::json!({ "status": "0x0" })), Some(false));
+        assert_eq!(receipt_succeeded(&serde_json::json!({ "root": "0xabc" })), None);
+
+        assert!(matches!(tx_success_outcome(0, 0, 5, 90.0), CheckOutcome::Ok(line) if line.contains("No transactions")));
+        assert!(matches!(tx_success_outcome(95, 100, 5, 90.0), CheckOutcome::Ok(line) if line.contains("95.0%")));
+        let outcome = tx_success_outcome(40, 100, 5, 90.0);
+        assert!(matches!(outcome, CheckOutcome::Alert(msg) if msg.contains("40.0%") && msg.contains("40/100")));
+    }
+
+    #[test]
+    fn test_future_block_timestamp_alerts() {
+        let now = 1_700_000_000;
+        assert!(matches!(future_timestamp_outcome(now as u64 - 12, now, 15), CheckOutcome::Ok(line) if line.contains("12s old")));
+        assert!(matches!(future_timestamp_outcome(now as u64 + 15, now, 15), CheckOutcome::Ok(_)));
+        let outcome = future_timestamp_outcome(now as u64 + 120, now, 15);
+        assert!(matches!(outcome, CheckOutcome::Alert(msg) if msg.contains("120s ahead")));
+    }
+
+    #[test]
+    fn test_pow_nonce_outcome() {
+        assert!(matches!(pow_nonce_outcome(100, "0xab", None), CheckOutcome::Ok(_)));
+        assert!(matches!(pow_nonce_outcome(100, "0xab", Some((17_179_869_184, true))), CheckOutcome::Ok(_)));
+        let outcome = pow_nonce_outcome(100, "0xab", Some((17_179_869_184, false)));
+        assert!(matches!(outcome, CheckOutcome::Alert(msg) if msg.contains("INVALID BLOCK NONCE DETECTED") && msg.contains("0xab")));
+    }
+
+    #[test]
+    fn test_state_proof_outcome() {
+        assert!(matches!(state_proof_outcome("0xabc", 10, Ok(())), CheckOutcome::Ok(line) if line.contains("block 10")));
+        let outcome = state_proof_outcome("0xabc", 10, Err("proof node 0 does not match".to_string()));
+        assert!(matches!(outcome, CheckOutcome::Alert(msg) if msg.contains("0xabc") && msg.contains("does not match")));
+    }
+
+    #[test]
+    fn test_header_chain_outcome() {
+        assert!(matches!(header_chain_outcome(100, "0xaa", "0xaa"), CheckOutcome::Ok(_)));
+        let outcome = header_chain_outcome(100, "0xaa", "0xbb");
+        assert!(matches!(outcome, CheckOutcome::Alert(msg) if msg.contains("CHAIN BREAK") && msg.contains("block 99 hash 0xbb")));
+    }
+
+    #[test]
+    fn test_parse_client_version() {
+        let parse = |raw: &str| raw.parse::<ClientVersion>().unwrap();
+        assert_eq!(parse("Geth/v1.13.0-stable-3f907d6a/linux-amd64/go1.21.1"), ClientVersion { client: Some("Geth".to_string()), version: semver::Version::new(1, 13, 0) });
+        assert_eq!(parse("Nethermind/v1.25.4+20b10b35/linux-x64/dotnet8.0.2").version, semver::Version::new(1, 25, 4));
+        assert_eq!(parse("erigon/2.55.1/linux-amd64/go1.21.5").version, semver::Version::new(2, 55, 1));
+        assert_eq!(parse("besu/v23.10.2/linux-x86_64/openjdk-java-17").version, semver::Version::new(23, 10, 2));
+        assert_eq!(parse("reth/v0.1.0-alpha.13-cb3a92c/x86_64-unknown-linux-gnu").version, semver::Version::new(0, 1, 0));
+        assert_eq!(parse("1.13"), ClientVersion { client: None, version: semver::Version::new(1, 13, 0) });
+        assert!("Geth/stable/linux".parse::<ClientVersion>().is_err());
+    }
+
+    #[test]
+    fn test_client_version_outcome() {
+        let minimum: ClientVersion = "Geth/v1.13.0".parse().unwrap();
+        let check = |raw: &str| client_version_outcome(raw, &raw.parse().unwrap(), &minimum);
+        assert!(matches!(check("Geth/v1.13.0-stable/linux-amd64/go1.21.1"), CheckOutcome::Ok(_)));
+        assert!(matches!(check("Geth/v1.14.2-stable/linux-amd64/go1.22.1"), CheckOutcome::Ok(_)));
+        assert!(matches!(check("Geth/v1.12.9-stable/linux-amd64/go1.20.1"), CheckOutcome::Alert(msg) if msg.contains("1.12.9") && msg.contains("1.13.0")));
+        // Another client isn't held to Geth's version
+        assert!(matches!(check("Nethermind/v1.2.0/linux-x64/dotnet8.0.2"), CheckOutcome::Ok(line) if line.contains("is not Geth")));
+        let any: ClientVersion = "2.0.0".parse().unwrap();
+        assert!(matches!(client_version_outcome("erigon/1.9.0", &"erigon/1.9.0".parse().unwrap(), &any), CheckOutcome::Alert(_)));
+    }
+
+    #[test]
+    fn test_probe_target() {
+        assert_eq!(probe_target("http://10.0.0.5:8545").as_deref(), Some("10.0.0.5:8545"));
+        assert_eq!(probe_target("https://node.example.com/rpc").as_deref(), Some("node.example.com:443"));
+        assert_eq!(probe_target("not a url"), None);
+    }
+
+    #[test]
+    fn test_external_probe_outcome() {
+        assert!(matches!(external_probe_outcome("10.0.0.5:8545", 200, None), CheckOutcome::Ok(_)));
+        assert!(matches!(external_probe_outcome("10.0.0.5:8545", 200, Some(true)), CheckOutcome::Ok(_)));
+        assert!(matches!(external_probe_outcome("10.0.0.5:8545", 200, Some(false)), CheckOutcome::Alert(msg) if msg.contains("10.0.0.5:8545")));
+        assert!(matches!(external_probe_outcome("10.0.0.5:8545", 503, None), CheckOutcome::Alert(msg) if msg.contains("HTTP 503")));
+    }
+
+    #[test]
+    fn test_evm_version_outcome() {
+        assert_eq!("Shanghai".parse::<EvmVersion>(), Ok(EvmVersion::Shanghai));
+        assert!("paris".parse::<EvmVersion>().is_err());
+        assert!(EvmVersion::London < EvmVersion::Cancun);
+
+        assert!(matches!(evm_version_outcome(EvmVersion::Shanghai, None), CheckOutcome::Ok(line) if line.contains("PUSH0")));
+        let outcome = evm_version_outcome(EvmVersion::Shanghai, Some("RPC Error: invalid opcode: opcode 0x5f not defined"));
+        assert!(matches!(outcome, CheckOutcome::Alert(msg) if msg.contains("PUSH0 (Shanghai)") && msg.contains("invalid opcode")));
+    }
+
+    #[test]
+    fn test_engine_jwt() {
+        let secret = parse_jwt_secret(&format!("0x{}\n", "ab".repeat(32))).unwrap();
+        assert!(parse_jwt_secret("abcd").unwrap_err().contains("32 bytes"));
+        assert!(parse_jwt_secret("not hex").is_err());
+
+        let token = engine_jwt(&secret, 1_700_000_000).unwrap();
+        let mut validation = jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::HS256);
+        validation.required_spec_claims.clear();
+        validation.validate_exp = false;
+        let decoded = jsonwebtoken::decode::<Value>(&token, &jsonwebtoken::DecodingKey::from_secret(&secret), &validation).unwrap();
+        assert_eq!(decoded.claims["iat"], 1_700_000_000);
+    }
+
+    #[test]
+    fn test_jwt_auth_outcome() {
+        assert!(matches!(jwt_auth_outcome("http://localhost:8551", 200), Ok(CheckOutcome::Ok(_))));
+        assert!(matches!(jwt_auth_outcome("http://localhost:8551", 401), Ok(CheckOutcome::Alert(msg)) if msg.contains("HTTP 401")));
+        assert!(jwt_auth_outcome("http://localhost:8551", 502).is_err());
+    }
+
+    #[test]
+    fn test_beacon_el_status_outcome() {
+        assert!(matches!(beacon_el_status_outcome(false, 9_000_000), CheckOutcome::Ok(_)));
+        assert!(matches!(beacon_el_status_outcome(true, 9_000_000), CheckOutcome::Alert(msg) if msg.contains("9000000")));
+    }
+
+    #[test]
+    fn test_slashing_outcome() {
+        let block = |proposer_slashings: Value, attester_slashings: Value| serde_json::json!({
+            "message": { "slot": "9000000", "body": {
+                "execution_payload": { "block_number": "20000000" },
+                "proposer_slashings": proposer_slashings,
+                "attester_slashings": attester_slashings,
+            }}
+        });
+        assert!(matches!(slashing_outcome(&block(serde_json::json!([]), serde_json::json!([]))).unwrap(), CheckOutcome::Ok(_)));
+
+        let proposer = serde_json::json!([{ "signed_header_1": { "message": { "proposer_index": "42" } } }]);
+        let attester = serde_json::json!([{
+            "attestation_1": { "attesting_indices": ["7", "8", "9"] },
+            "attestation_2": { "attesting_indices": ["8", "9", "10"] },
+        }]);
+        assert!(matches!(slashing_outcome(&block(proposer, attester)).unwrap(),
+            CheckOutcome::Alert(msg) if msg.contains("BLOCK 20000000") && msg.contains("8, 9, 42")));
+    }
+
+    #[test]
+    fn test_finality_hash_outcome() {
+        let hash = "0xAB12cd34";
+        assert!(matches!(finality_hash_outcome(20_000_000, hash, "0xab12cd34"), CheckOutcome::Ok(_)));
+        assert!(matches!(finality_hash_outcome(20_000_000, hash, "0xffff0000"), CheckOutcome::Alert(msg) if msg.contains("20000000") && msg.contains("0xffff0000")));
+    }
+
+    #[test]
+    fn test_sync_committee_participation() {
+        // 512 members: 60 bytes of 0xff and 4 of 0x0f
+        let bits = format!("0x{}{}", "ff".repeat(60), "0f".repeat(4));
+        assert_eq!(count_set_bits(&bits).unwrap(), 496);
+        assert!(matches!(sync_committee_outcome(1_400, 496 + 420, 1024, 90.0), CheckOutcome::Alert(msg) if msg.contains("89.5") && msg.contains("1400")));
+        assert!(matches!(sync_committee_outcome(1_400, 496, 512, 90.0), CheckOutcome::Ok(_)));
+    }
+
+    #[test]
+    fn test_disk_write_throughput_outcome() {
+        assert!(matches!(disk_write_throughput_outcome("nvme0n1", 412.3, 300.0), CheckOutcome::Alert(msg) if msg.contains("nvme0n1") && msg.contains("412.3")));
+        assert!(matches!(disk_write_throughput_outcome("nvme0n1", 20.0, 300.0), CheckOutcome::Ok(_)));
+    }
+
+    #[test]
+    fn test_io_saturation_outcome() {
+        assert!(matches!(io_saturation_outcome("nvme0n1", 97.5, 90.0), CheckOutcome::Alert(msg) if msg.contains("nvme0n1") && msg.contains("97.5")));
+        assert!(matches!(io_saturation_outcome("nvme0n1", 40.0, 90.0), CheckOutcome::Ok(_)));
+    }
+
+    #[test]
+    fn test_uncle_reward_outcome() {
+        // Depths 1 and 2 earn 7/8 and 6/8 of a block reward: 1.625 / 20 blocks
+        assert!(matches!(uncle_reward_outcome(&[1, 2], 20, 5.0), CheckOutcome::Alert(msg) if msg.contains("8.1%")));
+        assert!(matches!(uncle_reward_outcome(&[6], 20, 5.0), CheckOutcome::Ok(msg) if msg.contains("1.2%")));
+        assert!(matches!(uncle_reward_outcome(&[], 20, 5.0), CheckOutcome::Ok(_)));
+    }
+
+    #[test]
+    fn test_peer_ratio_below_minimum_alerts() {
+        assert!(matches!(peer_ratio_outcome(1, 25, 20.0), CheckOutcome::Alert(msg) if msg.contains("1/25")));
+        assert!(matches!(peer_ratio_outcome(5, 25, 20.0), CheckOutcome::Ok(_)));
+        assert!(matches!(peer_ratio_outcome(0, 0, 20.0), CheckOutcome::Ok(_)));
+    }
+
+    #[test]
+    fn test_enr_stability_outcome() {
+        assert!(matches!(enr_stability_outcome("enode://ab@10.0.0.1:30303", &[]), CheckOutcome::Ok(_)));
+        let changed = vec![tr!("identity_changed", field = "enode", previous = "enode://ab@10.0.0.1:30303", current = "enode://ab@10.0.0.2:30303")];
+        assert!(matches!(enr_stability_outcome("enode://ab@10.0.0.2:30303", &changed), CheckOutcome::Alert(msg) if msg.contains("10.0.0.1") && msg.contains("10.0.0.2")));
+    }
+
+    #[test]
+    fn test_rpc_bind_address_outcome() {
+        assert!(matches!(rpc_bind_address_outcome("0.0.0.0", "0.0.0.0"), CheckOutcome::Ok(_)));
+        assert!(matches!(rpc_bind_address_outcome("::", "0:0:0:0:0:0:0:0"), CheckOutcome::Ok(_)));
+        assert!(matches!(rpc_bind_address_outcome("127.0.0.1", "0.0.0.0"), CheckOutcome::Alert(msg) if msg.contains("127.0.0.1")));
+    }
+
+    #[test]
+    fn test_checkpoint_state_root_outcome() {
+        let root = "0xD7F8974FB5AC78D9AC099B9AD5018BEDC2CE0A72DAD1827A1709DA30580F0544";
+        assert!(matches!(checkpoint_state_root_outcome(0, &root.to_lowercase(), root), CheckOutcome::Ok(_)));
+        assert!(matches!(checkpoint_state_root_outcome(15_537_394, "0x1234", root), CheckOutcome::Alert(msg) if msg.contains("15537394") && msg.contains("0x1234")));
+    }
+
+    #[test]
+    fn test_client_log_outcome() {
+        assert!(matches!(client_log_outcome("geth.log", &[]), CheckOutcome::Ok(_)));
+        let lines = ["ERROR Database corruption".to_string(), "ERROR again".to_string(), "FATAL once more".to_string()];
+        assert!(matches!(client_log_outcome("geth.log", &lines[..1]), CheckOutcome::Alert(msg) if msg.ends_with("ERROR Database corruption")));
+        assert!(matches!(client_log_outcome("geth.log", &lines), CheckOutcome::Alert(msg) if msg.contains("Database corruption") && msg.contains("2")));
+    }
+
+    /// Every top-level field of Geth v1.14's 'admin_nodeInfo' result; none of
+    /// Nethermind, Besu, Erigon or Reth add an 'rpc' section either
+    fn geth_node_info() -> Value {
+        serde_json::json!({
+            "id": "6f8a80d14311c39f35f516fa664deaaaa13e85b2f7493f37f6144d86991ec012",
+            "name": "Geth/v1.14.8-stable-a9523b64/linux-amd64/go1.22.6",
+            "enode": "enode://af22c29c316ad069cf48a09a4ad5cf04a251b411e45098888d114c6dd7f489c@203.0.113.7:30303?discport=30301",
+            "enr": "enr:-KO4QGJvCDvDxCgEGf7BTvLqf1BhRsrtRTAfsz-Qhu4Ff0GzL0zThRXp8DzrPngvCTFm6W2seWxdW0l0EI4jYMwPYfOGAZGaOPyig2V0aMfGhJ-Q",
+            "ip": "203.0.113.7",
+            "ports": { "discovery": 30301, "listener": 30303 },
+            "listenAddr": "[::]:30303",
+            "protocols": {
+                "eth": { "network": 1, "genesis": "0xd4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3", "config": { "chainId": 1 } },
+                "snap": {}
+            }
+        })
+    }
+
+    #[test]
+    fn test_node_info_counts_not_reported_by_geth() {
+        let info = geth_node_info();
+        assert!(matches!(node_info_count(&info, "rpc.http.connections"), Err(AppError::NotReported(field)) if field == "rpc.http.connections"));
+        assert!(matches!(node_info_count(&info, "rpc.ws.subscriptions"), Err(AppError::NotReported(_))));
+        assert!(matches!(node_info_count(&info, "rpc.engine.connections"), Err(AppError::NotReported(_))));
+        assert!(matches!(node_info_count(&info, "ports.listener"), Ok(30303)));
+    }
+
+    #[test]
+    fn test_rpc_connections_outcome() {
+        assert!(matches!(rpc_connections_outcome(85, 100, 80.0), CheckOutcome::Alert(msg) if msg.contains("85/100")));
+        assert!(matches!(rpc_connections_outcome(80, 100, 80.0), CheckOutcome::Ok(_)));
+    }
+
+    #[test]
+    fn test_archive_outcome() {
+        assert!(is_pruned_state_error("missing trie node 4a7d3f… (path ) state 0x4a7d3f is not available"));
+        assert!(is_pruned_state_error("Pruned history unavailable"));
+        assert!(!is_pruned_state_error("header not found"));
+        assert!(matches!(archive_outcome(1_000_000, None), CheckOutcome::Ok(_)));
+        assert!(matches!(archive_outcome(1_000_000, Some("missing trie node")), CheckOutcome::Alert(msg) if msg.contains("1000000")));
+    }
+
+    #[test]
+    fn test_zkevm_batch_outcome() {
+        assert!(matches!(zkevm_batch_outcome(1_250, 1_000, 1, 100), CheckOutcome::Alert(msg) if msg.contains("250")));
+        assert!(matches!(zkevm_batch_outcome(1_050, 1_000, 1, 100), CheckOutcome::Ok(_)));
+        assert!(matches!(zkevm_batch_outcome(2_050, 1_000, 2, 100), CheckOutcome::Ok(_)));
+        assert_eq!("polygon_zkevm".parse::<ChainType>(), Ok(ChainType::PolygonZkevm));
+    }
+
+    #[test]
+    fn test_ws_subscriptions_outcome() {
+        assert!(matches!(ws_subscriptions_outcome(1_200, 1_000), CheckOutcome::Alert(msg) if msg.contains("1200")));
+        assert!(matches!(ws_subscriptions_outcome(1_000, 1_000), CheckOutcome::Ok(_)));
+    }
+
+    #[test]
+    fn test_engine_connections_outcome() {
+        assert!(matches!(engine_connections_outcome(1, 1), CheckOutcome::Ok(_)));
+        assert!(matches!(engine_connections_outcome(0, 1), CheckOutcome::Alert(msg) if msg.contains("DISCONNECTED")));
+        assert!(matches!(engine_connections_outcome(3, 1), CheckOutcome::Alert(msg) if msg.contains('3')));
+    }
+
+    #[test]
+    fn test_goroutine_count_outcome() {
+        assert!(matches!(goroutine_count_outcome(6_120, 5_000), CheckOutcome::Alert(msg) if msg.contains("6120")));
+        assert!(matches!(goroutine_count_outcome(812, 5_000), CheckOutcome::Ok(_)));
+    }
+
+    #[test]
+    fn test_multiplier_one_runs_every_cycle() {
+        let config = CheckConfig { interval_multiplier: 1, check_availability: false };
+        assert!((0..10).all(|cycle| config.is_due(cycle)));
+    }
+
+    #[test]
+    fn test_multiplier_runs_every_nth_cycle() {
+        // multiplier=5 with poll_interval=30s => every 150 seconds
+        let config = CheckConfig { interval_multiplier: 5, check_availability: false };
+        let due: Vec<u64> = (0..12).filter(|c| config.is_due(*c)).collect();
+        assert_eq!(due, vec![0, 5, 10]);
+    }
+
+    #[test]
+    fn test_protocol_version_change_alerts() {
+        assert!(matches!(protocol_version_outcome(Some("0x41"), "0x40"), CheckOutcome::Alert(_)));
+        assert!(matches!(protocol_version_outcome(Some("0x41"), "0x41"), CheckOutcome::Ok(_)));
+        // First run: nothing to compare against
+        assert!(matches!(protocol_version_outcome(None, "0x41"), CheckOutcome::Ok(_)));
+    }
+
+    #[test]
+    fn test_contract_call_result_comparison() {
+        assert!(matches!(contract_call_outcome("0xabc", "0x01", "0x01"), CheckOutcome::Ok(_)));
+        assert!(matches!(contract_call_outcome("0xabc", "0xAB", "0xab"), CheckOutcome::Ok(_)));
+        assert!(matches!(contract_call_outcome("0xabc", "0x01", "0x02"), CheckOutcome::Alert(_)));
+    }
+
+    #[test]
+    fn test_attestation_effectiveness_pct() {
+        let rewards = serde_json::json!({
+            "ideal_rewards": [
+                { "effective_balance": "31000000000", "head": "2000", "target": "5000", "source": "3000" },
+                { "effective_balance": "32000000000", "head": "2500", "target": "5000", "source": "2500" }
+            ],
+            "total_rewards": [
+                { "validator_index": "42", "head": "0", "target": "5000", "source": "2500" }
+            ]
+        });
+        assert_eq!(attestation_effectiveness_pct(&rewards, 42, 32_000_000_000), Some(75.0));
+        assert_eq!(attestation_effectiveness_pct(&rewards, 7, 32_000_000_000), None);
+    }
+
+    #[test]
+    fn test_attestation_penalties_count_as_zero() {
+        let rewards = serde_json::json!({
+            "ideal_rewards": [{ "effective_balance": "32000000000", "head": "1", "target": "1", "source": "1" }],
+            "total_rewards": [{ "validator_index": "1", "head": "0", "target": "-5", "source": "-3" }]
+        });
+        assert_eq!(attestation_effectiveness_pct(&rewards, 1, 32_000_000_000), Some(0.0));
+        assert!(matches!(attestation_outcome(1, 10, 0.0, 80.0), CheckOutcome::Alert(_)));
+        assert!(matches!(attestation_outcome(1, 10, 95.0, 80.0), CheckOutcome::Ok(_)));
+    }
+
+    #[test]
+    fn test_outbound_peers_below_minimum_alerts() {
+        let all_inbound = PeerDetails { inbound: 25, outbound: 0 };
+        assert!(matches!(peer_direction_outcome(&all_inbound, 2), CheckOutcome::Alert(_)));
+        let healthy = PeerDetails { inbound: 20, outbound: 5 };
+        assert!(matches!(peer_direction_outcome(&healthy, 2), CheckOutcome::Ok(_)));
+    }
+
+    #[test]
+    fn test_mining_state_mismatch_alerts() {
+        assert!(matches!(mining_outcome(true, false), CheckOutcome::Alert(_)));
+        assert!(matches!(mining_outcome(false, true), CheckOutcome::Alert(_)));
+        assert!(matches!(mining_outcome(true, true), CheckOutcome::Ok(_)));
+    }
+
+    #[test]
+    fn test_gas_limit_deviation() {
+        assert!(matches!(gas_limit_outcome(30_000_000, 30_000_000, 10.0), CheckOutcome::Ok(_)));
+        assert!(matches!(gas_limit_outcome(32_000_000, 30_000_000, 10.0), CheckOutcome::Ok(_)));
+        assert!(matches!(gas_limit_outcome(36_000_000, 30_000_000, 10.0), CheckOutcome::Alert(_)));
+        assert!(matches!(gas_limit_outcome(8_000_000, 30_000_000, 10.0), CheckOutcome::Alert(_)));
+    }
+
+    #[test]
+    fn test_trace_pattern_match_modes() {
+        let traces = vec![
+            serde_json::json!({ "txHash": "0xaa", "result": { "type": "CALL", "to": "0x1111" } }),
+            serde_json::json!({ "txHash": "0xbb", "result": { "type": "CREATE", "to": "0x2222" } }),
+        ];
+        let create = Regex::new(r#""type":"CREATE""#).unwrap();
+        let selfdestruct = Regex::new("SELFDESTRUCT").unwrap();
+
+        assert!(matches!(trace_outcome(&traces, &create, TraceMatchMode::Present, "0x10"), CheckOutcome::Alert(msg) if msg.contains("0xbb")));
+        assert!(matches!(trace_outcome(&traces, &selfdestruct, TraceMatchMode::Present, "0x10"), CheckOutcome::Ok(_)));
+        assert!(matches!(trace_outcome(&traces, &create, TraceMatchMode::Absent, "0x10"), CheckOutcome::Ok(_)));
+        assert!(matches!(trace_outcome(&traces, &selfdestruct, TraceMatchMode::Absent, "0x10"), CheckOutcome::Alert(_)));
+    }
+
+    #[test]
+    fn test_pending_nonce_gap() {
+        assert!(matches!(pending_nonce_outcome("0xabc", 120, 100, 10), CheckOutcome::Alert(_)));
+        assert!(matches!(pending_nonce_outcome("0xabc", 110, 100, 10), CheckOutcome::Ok(_)));
+        // Pending can briefly lag behind latest on some clients
+        assert!(matches!(pending_nonce_outcome("0xabc", 99, 100, 10), CheckOutcome::Ok(_)));
+    }
+
+    #[test]
+    fn test_uncle_rate() {
+        // 1 uncle in 10 blocks = 10%
+        let counts = [0, 0, 1, 0, 0, 0, 0, 0, 0, 0];
+        assert!(matches!(uncle_rate_outcome(&counts, 5.0), CheckOutcome::Alert(msg) if msg.contains("10.0%")));
+        assert!(matches!(uncle_rate_outcome(&counts, 10.0), CheckOutcome::Ok(_)));
+        assert!(matches!(uncle_rate_outcome(&[0; 10], 5.0), CheckOutcome::Ok(_)));
+    }
+
+    #[test]
+    fn test_blob_count_threshold() {
+        assert!(matches!(blob_count_outcome(Some(7), 6), CheckOutcome::Alert(_)));
+        assert!(matches!(blob_count_outcome(Some(6), 6), CheckOutcome::Ok(_)));
+        assert!(matches!(blob_count_outcome(None, 6), CheckOutcome::Ok(_)));
+    }
+
+    #[test]
+    fn test_finalization_distance() {
+        assert!(matches!(finalization_outcome(1000, 936, 128), CheckOutcome::Ok(_)));
+        assert!(matches!(finalization_outcome(1000, 800, 128), CheckOutcome::Alert(msg) if msg.contains("200")));
+    }
+
+    #[test]
+    fn test_filter_changes_alert_on_events() {
+        assert!(matches!(filter_changes_outcome(&[]), CheckOutcome::Ok(_)));
+        let logs = vec![serde_json::json!({ "address": "0xabc", "topics": ["0xddf2"] })];
+        assert!(matches!(filter_changes_outcome(&logs), CheckOutcome::Alert(msg) if msg.contains('1')));
+    }
+
+    #[test]
+    fn test_reward_spike_alerts() {
+        let mut rewards = vec![1_000_000_000; 100];
+        rewards.push(12_000_000_000);
+        assert!(matches!(reward_anomaly_outcome(&rewards, 10.0), CheckOutcome::Alert(_)));
+        *rewards.last_mut().unwrap() = 9_000_000_000;
+        assert!(matches!(reward_anomaly_outcome(&rewards, 10.0), CheckOutcome::Ok(_)));
+        assert!(matches!(reward_anomaly_outcome(&[5], 10.0), CheckOutcome::Ok(_)));
+    }
+
+    #[test]
+    fn test_discovery_target() {
+        let info = serde_json::json!({ "ip": "203.0.113.7", "ports": { "discovery": 30301, "listener": 30303 } });
+        let (target, tcp) = discovery_target(&info, None).unwrap();
+        assert_eq!(target, "203.0.113.7:30301".parse().unwrap());
+        assert_eq!(tcp, 30303);
+
+        let (target, _) = discovery_target(&info, Some("198.51.100.1".parse().unwrap())).unwrap();
+        assert_eq!(target, "198.51.100.1:30301".parse().unwrap());
+        assert!(discovery_target(&serde_json::json!({ "ip": "203.0.113.7" }), None).is_err());
+    }
+
+    #[test]
+    fn test_required_peer_alerts_after_attempts() {
+        let required = vec!["enode://aa@10.0.0.1:30303".to_string(), "enode://bb@10.0.0.2:30303".to_string()];
+        let peers = vec![serde_json::json!({ "enode": "enode://aa@10.0.0.1:30303" })];
+        let mut attempts = HashMap::new();
+
+        for _ in 0..MAX_ADD_PEER_ATTEMPTS {
+            assert_eq!(update_peer_attempts(&mut attempts, &required, &peers), vec![required[1].clone()]);
+            assert!(matches!(required_peers_outcome(&attempts, 2), CheckOutcome::Ok(_)));
+        }
+        update_peer_attempts(&mut attempts, &required, &peers);
+        assert!(matches!(required_peers_outcome(&attempts, 2), CheckOutcome::Alert(msg) if msg.contains("enode://bb")));
+
+        // Once connected the count resets
+        let peers = vec![serde_json::json!({ "enode": "enode://aa@x:1" }), serde_json::json!({ "enode": "enode://bb@y:2" })];
+        assert!(update_peer_attempts(&mut attempts, &required, &peers).is_empty());
+        assert!(matches!(required_peers_outcome(&attempts, 2), CheckOutcome::Ok(msg) if msg.contains("2/2")));
+    }
+
+    #[test]
+    fn test_connected_bootnodes() {
+        let peers = vec![
+            serde_json::json!({ "enode": "enode://AABB@10.0.0.5:30303", "enr": "enr:-peer1" }),
+            serde_json::json!({ "enode": "enode://ccdd@10.0.0.6:30303", "enr": "enr:-peer2" }),
+        ];
+        let bootnodes = vec!["enode://aabb@18.138.108.67:30303".to_string(), "enr:-peer2".to_string(), "enode://eeff@1.2.3.4:30303".to_string()];
+        assert_eq!(connected_bootnodes(&peers, &bootnodes), 2);
+        assert_eq!(connected_bootnodes(&peers, &bootnodes[2..]), 0);
+        assert!(matches!(bootnode_outcome(0, false), CheckOutcome::Ok(_)));
+        assert!(matches!(bootnode_outcome(0, true), CheckOutcome::Alert(_)));
+    }
+
+    #[test]
+    fn test_peer_count_below_minimum_alerts() {
+        assert!(matches!(peer_count_outcome(1, 3), CheckOutcome::Alert(_)));
+        assert!(matches!(peer_count_outcome(3, 3), CheckOutcome::Ok(_)));
+    }
+}
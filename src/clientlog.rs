@@ -0,0 +1,164 @@
+
+// --- Client Log Tailing ---
+//
+// CLIENT_LOG_FILE points at the execution client's own log. A task follows it
+// like `tail -F`: new lines are matched against CLIENT_LOG_ERROR_PATTERNS and
+// matching ones queued for the client_log check, which turns them into an
+// alert. Errors like a corrupted database or a failing peer handshake often
+// show up there long before the RPC answers change.
+
+use std::io::SeekFrom;
+use std::os::unix::fs::MetadataExt;
+use std::time::Duration;
+
+use regex::RegexSet;
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::mpsc;
+
+/// How often the file is checked for new lines
+const TAIL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Matching lines kept between two runs of the check; later ones are dropped
+const MATCH_CAPACITY: usize = 100;
+
+/// Matching lines are cut to this many characters, to fit into an alert
+const MAX_LINE_CHARS: usize = 500;
+
+/// Default CLIENT_LOG_ERROR_PATTERNS: Geth-style `ERROR`/`CRIT` levels and
+/// the usual fatal markers of the other clients
+pub const DEFAULT_ERROR_PATTERN: &str = r"(?i)\b(error|crit|fatal|panic)\b";
+
+/// Splits `chunk`, appended to the unfinished line in `partial`, into
+/// complete lines; the new unfinished tail is left in `partial`
+fn split_lines(partial: &mut String, chunk: &str) -> Vec<String> {
+    partial.push_str(chunk);
+    let Some(end) = partial.rfind('\n') else {
+        return Vec::new();
+    };
+    let rest = partial.split_off(end + 1);
+    let lines = partial.lines().map(|line| line.trim_end_matches('\r').to_string()).collect();
+    *partial = rest;
+    lines
+}
+
+fn shorten(line: &str) -> String {
+    match line.char_indices().nth(MAX_LINE_CHARS) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+/// The open log file, and the inode and offset it was read up to
+struct Tail {
+    file: File,
+    inode: u64,
+    offset: u64,
+}
+
+impl Tail {
+    /// Opens `path` at its end, or at its start for a file that replaced the
+    /// one being followed (rotation), so none of its lines are missed
+    async fn open(path: &str, from_start: bool) -> std::io::Result<Tail> {
+        let mut file = File::open(path).await?;
+        let metadata = file.metadata().await?;
+        let offset = if from_start { 0 } else { metadata.len() };
+        file.seek(SeekFrom::Start(offset)).await?;
+        Ok(Tail { file, inode: metadata.ino(), offset })
+    }
+
+    /// Whatever was appended since the last read; None when the file was
+    /// rotated or truncated and has to be reopened
+    async fn read_new(&mut self, path: &str) -> std::io::Result<Option<String>> {
+        let metadata = tokio::fs::metadata(path).await?;
+        if metadata.ino() != self.inode || metadata.len() < self.offset {
+            return Ok(None);
+        }
+        let mut bytes = Vec::new();
+        self.file.read_to_end(&mut bytes).await?;
+        self.offset += bytes.len() as u64;
+        Ok(Some(String::from_utf8_lossy(&bytes).into_owned()))
+    }
+}
+
+/// Spawns the task following `path`; the receiver yields the matching lines
+pub fn spawn_tailer(path: String, patterns: RegexSet) -> mpsc::Receiver<String> {
+    let (sender, receiver) = mpsc::channel(MATCH_CAPACITY);
+    tokio::spawn(async move {
+        let mut tail: Option<Tail> = None;
+        // Lines already in the file at startup are old news; a file that
+        // appears later is read from its first line
+        let mut from_start = false;
+        let mut partial = String::new();
+        let mut reported_error = false;
+        loop {
+            if tail.is_none() {
+                match Tail::open(&path, from_start).await {
+                    Ok(opened) => {
+                        tail = Some(opened);
+                        reported_error = false;
+                    }
+                    Err(e) if !reported_error => {
+                        warn!("[WARN] Can't read CLIENT_LOG_FILE '{}', retrying: {}", path, e);
+                        reported_error = true;
+                    }
+                    Err(_) => {}
+                }
+            }
+            if let Some(current) = tail.as_mut() {
+                match current.read_new(&path).await {
+                    Ok(Some(chunk)) => {
+                        for line in split_lines(&mut partial, &chunk) {
+                            if patterns.is_match(&line) && sender.try_send(shorten(&line)).is_err() {
+                                if sender.is_closed() {
+                                    return;
+                                }
+                                debug!("[DEBUG] Client log match queue full, dropping a line");
+                            }
+                        }
+                    }
+                    Ok(None) => {
+                        info!("[INFO] CLIENT_LOG_FILE '{}' was rotated, reopening", path);
+                        tail = None;
+                        partial.clear();
+                    }
+                    Err(_) => tail = None,
+                }
+            }
+            from_start = true;
+            tokio::time::sleep(TAIL_INTERVAL).await;
+        }
+    });
+    receiver
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_lines_keeps_unfinished_line() {
+        let mut partial = String::new();
+        assert_eq!(split_lines(&mut partial, "INFO [01-01] a\nERROR [01-01] b"), vec!["INFO [01-01] a"]);
+        assert_eq!(partial, "ERROR [01-01] b");
+        assert_eq!(split_lines(&mut partial, " failed\r\nWARN c\n"), vec!["ERROR [01-01] b failed", "WARN c"]);
+        assert_eq!(partial, "");
+    }
+
+    #[tokio::test]
+    async fn test_tailer_reports_new_matching_lines() {
+        let path = std::env::temp_dir().join(format!("eth-alive-clientlog-{}.log", std::process::id()));
+        std::fs::write(&path, "ERROR old line, before startup\n").unwrap();
+        let patterns = RegexSet::new([DEFAULT_ERROR_PATTERN]).unwrap();
+        let mut matches = spawn_tailer(path.to_str().unwrap().to_string(), patterns);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        std::io::Write::write_all(&mut file, b"INFO Imported new chain segment\nERROR Database corruption detected\n").unwrap();
+        let line = tokio::time::timeout(Duration::from_secs(5), matches.recv()).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(line.as_deref(), Some("ERROR Database corruption detected"));
+    }
+}
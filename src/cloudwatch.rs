@@ -0,0 +1,77 @@
+
+// --- CloudWatch Metrics ---
+//
+// With CLOUDWATCH_NAMESPACE and AWS_REGION set, every alert sent is put as an
+// `AlertSent` custom metric (dimensions NodeName and AlertKind) and the block
+// lag as `Lag` every poll cycle, for CloudWatch dashboards and alarms.
+// Credentials come from the usual AWS chain (environment, profile, instance
+// role). Each put runs in its own task so the main loop never waits on AWS.
+
+use std::sync::OnceLock;
+
+use aws_config::{BehaviorVersion, Region};
+use aws_sdk_cloudwatch::types::{Dimension, MetricDatum, StandardUnit};
+
+struct CloudWatch {
+    client: aws_sdk_cloudwatch::Client,
+    namespace: String,
+    /// NODE_LABEL, the NodeName of alerts and of the single pair's lag
+    node_name: String,
+}
+
+static CLOUDWATCH: OnceLock<CloudWatch> = OnceLock::new();
+
+/// Loads the AWS configuration; metrics are only put after this
+pub async fn init(namespace: String, region: String, node_name: String) {
+    let config = aws_config::defaults(BehaviorVersion::latest()).region(Region::new(region)).load().await;
+    let client = aws_sdk_cloudwatch::Client::new(&config);
+    let _ = CLOUDWATCH.set(CloudWatch { client, namespace, node_name });
+}
+
+/// Counts a delivered alert of `kind`
+pub fn record_alert(kind: &str) {
+    if let Some(cloudwatch) = CLOUDWATCH.get() {
+        let dimensions = [("NodeName", cloudwatch.node_name.as_str()), ("AlertKind", kind)];
+        put(cloudwatch, datum("AlertSent", 1.0, StandardUnit::Count, &dimensions));
+    }
+}
+
+/// Block lag of the single pair (`node` None) or of a NODES fleet member
+pub fn record_lag(node: Option<&str>, lag: u64) {
+    if let Some(cloudwatch) = CLOUDWATCH.get() {
+        let node_name = node.unwrap_or(&cloudwatch.node_name);
+        put(cloudwatch, datum("Lag", lag as f64, StandardUnit::Count, &[("NodeName", node_name)]));
+    }
+}
+
+fn datum(name: &str, value: f64, unit: StandardUnit, dimensions: &[(&str, &str)]) -> MetricDatum {
+    let mut datum = MetricDatum::builder().metric_name(name).value(value).unit(unit);
+    for (name, value) in dimensions {
+        datum = datum.dimensions(Dimension::builder().name(*name).value(*value).build());
+    }
+    datum.build()
+}
+
+fn put(cloudwatch: &'static CloudWatch, datum: MetricDatum) {
+    tokio::spawn(async move {
+        let result = cloudwatch.client.put_metric_data().namespace(&cloudwatch.namespace).metric_data(datum).send().await;
+        if let Err(e) = result {
+            warn!("[WARN] Failed to put CloudWatch metric to {}: {}", cloudwatch.namespace, aws_sdk_cloudwatch::error::DisplayErrorContext(e));
+        }
+    });
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_datum_dimensions() {
+        let datum = datum("AlertSent", 1.0, StandardUnit::Count, &[("NodeName", "validator-1"), ("AlertKind", "node_lagging")]);
+        assert_eq!(datum.metric_name(), Some("AlertSent"));
+        let dimensions: Vec<_> = datum.dimensions().iter().map(|d| (d.name().unwrap(), d.value().unwrap())).collect();
+        assert_eq!(dimensions, vec![("NodeName", "validator-1"), ("AlertKind", "node_lagging")]);
+    }
+}
@@ -0,0 +1,1098 @@
+
+// --- Configuration ---
+
+use std::env;
+use std::net::{IpAddr, SocketAddr};
+use std::process;
+use std::str::FromStr;
+use std::time::Duration;
+use chrono::{DateTime, Datelike, Utc, Weekday};
+use dotenvy::dotenv;
+use regex::{Regex, RegexSet};
+
+use crate::checks::{parse_jwt_secret, probe_target, ChainType, CheckConfig, ClientVersion, EvmVersion, TraceMatchMode};
+use crate::diskio::device_for_path;
+use crate::groups::{parse_groups, Batching, GroupConfig};
+use crate::headers::{parse_rules, HeaderRule};
+use crate::influx::InfluxConfig;
+use crate::logging::{LogDest, LogFormat, LogLevel};
+use crate::metrics::parse_extra_labels;
+use crate::nodes::{read_nodes_config, NodeConfig};
+use crate::notify::{webhook_client, webhook_enabled, NotificationBackend};
+use crate::plugins::{webhook_plugin, EmailPlugin, NotificationPlugin, SmtpConfig, SmtpTls, TelegramPlugin, WebhookTarget};
+use crate::remote::RemoteStrategy;
+use crate::routing::{parse_routing, MethodRouting};
+use crate::ws::TransportMode;
+
+/// Settings file read when `--config` isn't given
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+/// What to do when the settings file doesn't exist (CONFIG_MISSING_BEHAVIOR)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConfigMissingBehavior {
+    Error,
+    Warn,
+}
+
+impl FromStr for ConfigMissingBehavior {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(ConfigMissingBehavior::Error),
+            "warn" => Ok(ConfigMissingBehavior::Warn),
+            other => Err(format!("unknown config missing behavior '{}'", other)),
+        }
+    }
+}
+
+/// Contract function call verified by the eth_call check
+pub struct ContractCallConfig {
+    pub address: String,
+    pub data: String,
+    pub expected_result: String,
+}
+
+/// Application configuration loaded from the environment
+pub struct Config {
+    pub local_rpc: String,
+    pub local_ws: Option<String>,
+    pub transport_mode: TransportMode,
+    pub force_http10: bool,
+    pub pid_file: Option<String>,
+    pub redis_url: Option<String>,
+    pub pid_file_exit_if_running: bool,
+    pub remote_rpcs: Vec<String>,
+    pub remote_strategy: RemoteStrategy,
+    pub remote_agreement_check: bool,
+    pub remote_disagreement_threshold: u64,
+    pub circuit_failure_threshold: u32,
+    pub circuit_probe_interval_secs: u64,
+    pub local_bind_address: Option<IpAddr>,
+    /// LOCAL_RPC_SNI_HOSTNAME and the address LOCAL_RPC_URL named. The URL is
+    /// rewritten to the hostname (so TLS sends it as SNI and verifies the
+    /// certificate against it) and the local client resolves it to the address
+    pub local_rpc_sni: Option<(String, SocketAddr)>,
+    pub remote_bind_address: Option<IpAddr>,
+    pub lag_threshold: u64,
+    pub weekend_lag_threshold_multiplier: f64,
+    pub lag_exit_threshold: Option<u64>,
+    pub local_ahead_alert_threshold: u64,
+    pub alert_cooldown_minutes: u64,
+    pub poll_interval_seconds: u64,
+    pub stall_threshold_seconds: u64,
+    /// Seconds between blocks; inferred from the remote's recent blocks when unset
+    pub chain_block_time_secs: Option<u64>,
+    pub discord_webhook: String,
+    pub notification_backend: NotificationBackend,
+    /// Email alerts, with SMTP_HOST
+    pub smtp: Option<SmtpConfig>,
+    /// Alert channels: the DISCORD_WEBHOOK_URL webhook, Telegram with TELEGRAM_BOT_TOKEN
+    /// and email with SMTP_HOST
+    pub notification_plugins: Vec<Box<dyn NotificationPlugin + Send + Sync>>,
+    pub webhook_timeout_secs: u64,
+    pub max_concurrent_webhooks: usize,
+    pub min_webhook_interval_ms: u64,
+    pub alert_dedupe: bool,
+    pub dns_timeout_secs: u64,
+    pub webhook_hmac_secret: Option<String>,
+    pub webhook_proxy_url: Option<String>,
+    pub min_peer_count: Option<u64>,
+    pub peer_count_check: CheckConfig,
+    pub peer_direction_enabled: bool,
+    pub min_outbound_peers: u64,
+    pub peer_direction_check: CheckConfig,
+    pub peer_ratio_enabled: bool,
+    pub min_peer_ratio_pct: f64,
+    pub peer_ratio_check: CheckConfig,
+    pub rpc_connections_enabled: bool,
+    pub max_rpc_connections_warn_pct: f64,
+    pub rpc_max_connections: Option<u64>,
+    pub rpc_connections_check: CheckConfig,
+    pub engine_connections_enabled: bool,
+    pub expected_engine_api_connections: u64,
+    pub engine_connections_check: CheckConfig,
+    pub max_ws_subscriptions_warn: Option<u64>,
+    pub ws_subscriptions_check: CheckConfig,
+    pub max_goroutine_count_warn: Option<u64>,
+    pub goroutine_count_check: CheckConfig,
+    pub p2p_discoverability_enabled: bool,
+    pub p2p_probe_host: Option<IpAddr>,
+    pub p2p_discoverability_check: CheckConfig,
+    pub required_peers: Vec<String>,
+    pub required_peers_check: CheckConfig,
+    pub bootnode_check_enabled: bool,
+    pub bootnodes: Vec<String>,
+    pub bootnode_alert: bool,
+    pub bootnode_check: CheckConfig,
+    pub contract_call: Option<ContractCallConfig>,
+    pub contract_call_check: CheckConfig,
+    pub state_proof_address: Option<String>,
+    pub state_proof_check: CheckConfig,
+    pub archive_check_block: Option<u64>,
+    pub archive_check: CheckConfig,
+    pub chain_type: ChainType,
+    pub zkevm_blocks_per_batch: u64,
+    pub batch_lag_threshold: u64,
+    pub zkevm_batch_check: CheckConfig,
+    pub min_client_version: Option<ClientVersion>,
+    pub client_version_check: CheckConfig,
+    pub min_evm_version: Option<EvmVersion>,
+    pub evm_version_check: CheckConfig,
+    pub expected_mining_state: Option<bool>,
+    pub mining_check: CheckConfig,
+    pub expected_gas_limit: Option<u64>,
+    pub gas_limit_deviation_pct: f64,
+    pub gas_limit_check: CheckConfig,
+    pub header_chain_verify: bool,
+    pub header_chain_check: CheckConfig,
+    pub future_timestamp_enabled: bool,
+    pub max_future_block_secs: u64,
+    pub future_timestamp_check: CheckConfig,
+    /// Addresses whose pending nonce gap is checked, each with its maximum gap
+    pub pending_nonce_addresses: Vec<(String, u64)>,
+    pub pending_nonce_check: CheckConfig,
+    pub max_pending_tx_age_secs: Option<u64>,
+    pub txpool_check: CheckConfig,
+    pub local_health_endpoint: Option<String>,
+    pub health_endpoint_check: CheckConfig,
+    pub external_probe_url: Option<String>,
+    pub external_probe_check: CheckConfig,
+    pub jwt_secret: Option<Vec<u8>>,
+    pub engine_api_url: String,
+    pub jwt_auth_check: CheckConfig,
+    pub tx_success_rate_enabled: bool,
+    pub tx_success_rate_blocks: u64,
+    pub min_tx_success_rate_pct: f64,
+    pub tx_success_rate_check: CheckConfig,
+    pub uncle_rate_enabled: bool,
+    pub max_uncle_rate_pct: f64,
+    pub uncle_rate_check: CheckConfig,
+    pub max_uncle_reward_ratio_pct: Option<f64>,
+    pub uncle_reward_check: CheckConfig,
+    pub pow_nonce_enabled: bool,
+    pub pow_nonce_check: CheckConfig,
+    pub method_routing: Option<MethodRouting>,
+    pub max_blobs_per_block: Option<u64>,
+    pub blob_count_check: CheckConfig,
+    pub finalization_enabled: bool,
+    pub max_finalization_distance: u64,
+    pub finalization_check: CheckConfig,
+    pub sync_stuck_threshold_minutes: Option<u64>,
+    pub sync_progress_check: CheckConfig,
+    pub filter_addresses: Vec<String>,
+    pub filter_topics: Vec<String>,
+    pub filter_check: CheckConfig,
+    pub reward_anomaly_enabled: bool,
+    pub max_reward_multiplier: f64,
+    pub reward_anomaly_check: CheckConfig,
+    pub trace_pattern: Option<Regex>,
+    pub trace_match_mode: TraceMatchMode,
+    pub trace_check: CheckConfig,
+    pub beacon_api_url: Option<String>,
+    pub validator_index: Option<u64>,
+    pub beacon_validator_indices: Vec<u64>,
+    pub validator_status_check: CheckConfig,
+    pub expected_fork_version: Option<String>,
+    pub fork_version_check: CheckConfig,
+    pub beacon_el_status_enabled: bool,
+    pub beacon_el_status_check: CheckConfig,
+    pub slashing_check_enabled: bool,
+    pub slashing_check: CheckConfig,
+    pub sync_committee_check_enabled: bool,
+    pub min_sync_committee_participation_pct: f64,
+    pub sync_committee_check: CheckConfig,
+    pub finality_hash_check_enabled: bool,
+    pub finality_hash_check: CheckConfig,
+    pub min_attestation_effectiveness_pct: f64,
+    pub attestation_check: CheckConfig,
+    /// /proc/diskstats device holding DATA_DIR, with IO_SATURATION_CHECK
+    pub io_saturation_device: Option<String>,
+    pub io_saturation_warn_pct: f64,
+    pub io_saturation_check: CheckConfig,
+    /// Device holding DATA_DIR and DISK_WRITE_THROUGHPUT_WARN_MB_PER_SEC
+    pub disk_write_throughput_warn: Option<(String, f64)>,
+    pub disk_write_throughput_check: CheckConfig,
+    pub client_log_file: Option<String>,
+    pub client_log_patterns: RegexSet,
+    pub client_log_check: CheckConfig,
+    pub groups: Vec<GroupConfig>,
+    /// Independent node pairs (NODES); when set, they replace the single
+    /// LOCAL_RPC_URL/REMOTE_RPC_URL pair
+    pub nodes: Vec<NodeConfig>,
+    pub dedicated_clients: bool,
+    pub fleet_max_concurrent_checks: usize,
+    pub fleet_batching: Batching,
+    pub max_response_size_bytes: Option<usize>,
+    pub self_monitor: bool,
+    pub self_max_memory_mb: u64,
+    pub sqlite_path: Option<String>,
+    pub db_history_days: u64,
+    pub node_label: String,
+    pub log_dest: LogDest,
+    pub log_format: LogFormat,
+    pub log_level: LogLevel,
+    pub watch_headers: Vec<String>,
+    pub header_alert_rules: Vec<HeaderRule>,
+    pub trigger_socket_path: Option<String>,
+    pub alert_locale: String,
+    pub ws_logs_addresses: Vec<String>,
+    pub ws_logs_topics: Vec<String>,
+    pub state_file: String,
+    pub snapshot_file: Option<String>,
+    pub export_csv_path: Option<String>,
+    pub metrics_port: u16,
+    pub prometheus_extra_labels: Vec<(String, String)>,
+    pub max_metric_labels: usize,
+    pub victoria_metrics_url: Option<String>,
+    pub influxdb: Option<InfluxConfig>,
+    pub chain_name: String,
+    pub metadata_url: Option<String>,
+    pub metadata_fields: Vec<String>,
+    pub metadata_refresh_hours: u64,
+    pub status_page_url: Option<String>,
+    pub cloudwatch_namespace: Option<String>,
+    pub aws_region: Option<String>,
+    pub status_refresh_minutes: u64,
+    pub on_down_exec: Option<String>,
+    pub on_recovery_exec: Option<String>,
+    pub protocol_version_check: bool,
+    pub check_enr_stability: bool,
+    pub expected_rpc_bind_address: Option<String>,
+    /// CHECKPOINT_BLOCK and its CHECKPOINT_STATE_ROOT
+    pub checkpoint: Option<(u64, String)>,
+}
+
+impl Config {
+    pub fn from_env() -> Self {
+        // BIND_ADDRESS applies to both clients unless overridden per side
+        let bind_address: Option<IpAddr> = parse_env_opt("BIND_ADDRESS");
+
+        // A fleet needs no single pair; the first node stands in for it
+        let nodes = nodes_from_env();
+        let (local_rpc, remote_rpcs) = match nodes.first() {
+            Some(first) => (
+                secret_env("LOCAL_RPC_URL").unwrap_or_else(|| first.local_rpc.clone()),
+                vec![secret_env("REMOTE_RPC_URL").unwrap_or_else(|| first.remote_rpc.clone())],
+            ),
+            None => (get_secret_env("LOCAL_RPC_URL"), remote_rpcs_from_env()),
+        };
+
+        // USE_AGGREGATED_REMOTE: the remotes are the backends behind one load
+        // balancer; follow the highest and alert when they drift apart
+        let aggregated_remote = parse_env("USE_AGGREGATED_REMOTE", false);
+
+        // A `file://` reference is read once, for the URL and its format
+        let discord_webhook = get_secret_env("DISCORD_WEBHOOK_URL");
+
+        let mut config = Config {
+            local_rpc,
+            local_ws: secret_env("LOCAL_WS_URL"),
+            transport_mode: parse_env("TRANSPORT_MODE", TransportMode::Poll),
+            force_http10: parse_env("FORCE_HTTP10", false),
+            pid_file: env::var("PID_FILE").ok(),
+            redis_url: secret_env("REDIS_URL"),
+            pid_file_exit_if_running: parse_env("PID_FILE_EXIT_IF_RUNNING", false),
+            remote_rpcs,
+            remote_strategy: if aggregated_remote { RemoteStrategy::Max } else { parse_env("REMOTE_RPC_STRATEGY", RemoteStrategy::FirstSuccess) },
+            remote_agreement_check: aggregated_remote || parse_env("REMOTE_AGREEMENT_CHECK", false),
+            remote_disagreement_threshold: if aggregated_remote { parse_env("REMOTE_SPREAD_THRESHOLD", 5) } else { parse_env("REMOTE_DISAGREEMENT_THRESHOLD", 3) },
+            circuit_failure_threshold: parse_env("CIRCUIT_FAILURE_THRESHOLD", 3),
+            circuit_probe_interval_secs: parse_env("CIRCUIT_PROBE_INTERVAL_SECS", 60),
+            local_bind_address: parse_env_opt("LOCAL_BIND_ADDRESS").or(bind_address),
+            local_rpc_sni: None,
+            remote_bind_address: parse_env_opt("REMOTE_BIND_ADDRESS").or(bind_address),
+            lag_threshold: parse_env("LAG_THRESHOLD", 3),
+            weekend_lag_threshold_multiplier: parse_env("WEEKEND_LAG_THRESHOLD_MULTIPLIER", 1.0),
+            lag_exit_threshold: parse_env_opt("LAG_EXIT_THRESHOLD"),
+            local_ahead_alert_threshold: parse_env("LOCAL_AHEAD_ALERT_THRESHOLD", 10),
+            alert_cooldown_minutes: parse_env("ALERT_COOLDOWN_MINUTES", 15),
+            poll_interval_seconds: parse_env("POLL_INTERVAL_SECONDS", 60),
+            stall_threshold_seconds: parse_env("STALL_THRESHOLD_SECONDS", 300),
+            chain_block_time_secs: parse_env_opt("CHAIN_BLOCK_TIME_SECS"),
+            notification_backend: NotificationBackend::detect(&discord_webhook, env::var("WEBHOOK_TEMPLATE").ok()),
+            discord_webhook,
+            smtp: smtp_from_env(),
+            notification_plugins: Vec::new(),
+            webhook_timeout_secs: parse_env("WEBHOOK_TIMEOUT_SECS", 5),
+            max_concurrent_webhooks: parse_env("MAX_CONCURRENT_WEBHOOKS", 5),
+            min_webhook_interval_ms: parse_env("MIN_WEBHOOK_INTERVAL_MS", 2000),
+            alert_dedupe: parse_env("ALERT_DEDUPE", false),
+            dns_timeout_secs: parse_env("DNS_TIMEOUT_SECS", 5),
+            webhook_hmac_secret: secret_env("WEBHOOK_HMAC_SECRET"),
+            webhook_proxy_url: secret_env("WEBHOOK_PROXY_URL"),
+            // optional net_peerCount check, only enabled when MIN_PEER_COUNT is set
+            min_peer_count: parse_env_opt("MIN_PEER_COUNT"),
+            peer_count_check: CheckConfig::from_env("PEER_COUNT"),
+            // admin_peers needs the admin namespace, so this one is opt-in
+            peer_direction_enabled: parse_env("PEER_DIRECTION_CHECK", false),
+            min_outbound_peers: parse_env("MIN_OUTBOUND_PEERS", 2),
+            peer_direction_check: CheckConfig::from_env("PEER_DIRECTION"),
+            // maxPeers comes from admin_nodeInfo, so this one is opt-in too
+            peer_ratio_enabled: parse_env("PEER_RATIO_CHECK", false),
+            min_peer_ratio_pct: parse_env("MIN_PEER_RATIO_PCT", 20.0),
+            peer_ratio_check: CheckConfig::from_env("PEER_RATIO"),
+            rpc_connections_enabled: parse_env("RPC_CONNECTIONS_CHECK", false),
+            max_rpc_connections_warn_pct: parse_env("MAX_RPC_CONNECTIONS_WARN_PCT", 80.0),
+            rpc_max_connections: parse_env_opt("RPC_MAX_CONNECTIONS"),
+            rpc_connections_check: CheckConfig::from_env("RPC_CONNECTIONS"),
+            engine_connections_enabled: parse_env("ENGINE_CONNECTIONS_CHECK", false),
+            expected_engine_api_connections: parse_env("EXPECTED_ENGINE_API_CONNECTIONS", 1),
+            engine_connections_check: CheckConfig::from_env("ENGINE_CONNECTIONS"),
+            max_ws_subscriptions_warn: parse_env_opt("MAX_WS_SUBSCRIPTIONS_WARN"),
+            ws_subscriptions_check: CheckConfig::from_env("WS_SUBSCRIPTIONS"),
+            max_goroutine_count_warn: parse_env_opt("MAX_GOROUTINE_COUNT_WARN"),
+            goroutine_count_check: CheckConfig::from_env("GOROUTINE_COUNT"),
+            p2p_discoverability_enabled: parse_env("CHECK_P2P_DISCOVERABILITY", false),
+            p2p_probe_host: parse_env_opt("P2P_PROBE_HOST"),
+            p2p_discoverability_check: CheckConfig::from_env("P2P_DISCOVERABILITY"),
+            required_peers: env::var("REQUIRED_PEERS").map(|raw| parse_list(&raw)).unwrap_or_default(),
+            required_peers_check: CheckConfig::from_env("REQUIRED_PEERS"),
+            bootnode_check_enabled: parse_env("BOOTNODE_CHECK", false),
+            bootnodes: env::var("BOOTNODES").map(|raw| parse_list(&raw)).unwrap_or_default(),
+            bootnode_alert: parse_env("BOOTNODE_ALERT", false),
+            bootnode_check: CheckConfig::from_env("BOOTNODE"),
+            contract_call: contract_call_from_env(),
+            contract_call_check: CheckConfig::from_env("CONTRACT_CALL"),
+            state_proof_address: env::var("STATE_PROOF_CHECK_ADDRESS").ok(),
+            state_proof_check: CheckConfig::from_env("STATE_PROOF"),
+            archive_check_block: parse_env_opt("ARCHIVE_CHECK_BLOCK_NUMBER"),
+            archive_check: CheckConfig::from_env("ARCHIVE"),
+            chain_type: parse_env("CHAIN_TYPE", ChainType::Ethereum),
+            zkevm_blocks_per_batch: parse_env("ZKEVM_BLOCKS_PER_BATCH", 1),
+            batch_lag_threshold: parse_env("BATCH_LAG_THRESHOLD", 100),
+            zkevm_batch_check: CheckConfig::from_env("ZKEVM_BATCH"),
+            min_client_version: parse_env_opt("MIN_CLIENT_VERSION"),
+            client_version_check: CheckConfig::from_env("CLIENT_VERSION"),
+            min_evm_version: parse_env_opt("MIN_EVM_VERSION"),
+            evm_version_check: CheckConfig::from_env("EVM_VERSION"),
+            expected_mining_state: expected_mining_from_env(),
+            mining_check: CheckConfig::from_env("MINING"),
+            expected_gas_limit: parse_env_opt("EXPECTED_GAS_LIMIT"),
+            gas_limit_deviation_pct: parse_env("GAS_LIMIT_DEVIATION_PCT", 10.0),
+            gas_limit_check: CheckConfig::from_env("GAS_LIMIT"),
+            header_chain_verify: parse_env("HEADER_CHAIN_VERIFY", false),
+            header_chain_check: CheckConfig::from_env("HEADER_CHAIN"),
+            future_timestamp_enabled: parse_env("FUTURE_TIMESTAMP_CHECK", false),
+            max_future_block_secs: parse_env("MAX_FUTURE_BLOCK_SECS", 15),
+            future_timestamp_check: CheckConfig::from_env("FUTURE_TIMESTAMP"),
+            pending_nonce_addresses: pending_nonce_addresses_from_env(),
+            pending_nonce_check: CheckConfig::from_env("PENDING_NONCE"),
+            max_pending_tx_age_secs: parse_env_opt("MAX_PENDING_TX_AGE_SECS"),
+            // txpool_content can be megabytes on a busy chain
+            txpool_check: CheckConfig::from_keys_or("TXPOOL", "TXPOOL_CHECK_INTERVAL", 10),
+            local_health_endpoint: env::var("LOCAL_HEALTH_ENDPOINT").ok(),
+            health_endpoint_check: CheckConfig::from_env("HEALTH_ENDPOINT"),
+            external_probe_url: env::var("EXTERNAL_PROBE_URL").ok(),
+            external_probe_check: CheckConfig::from_env("EXTERNAL_PROBE"),
+            jwt_secret: jwt_secret_from_env(),
+            engine_api_url: secret_env("ENGINE_API_URL").unwrap_or_else(|| "http://localhost:8551".to_string()),
+            jwt_auth_check: CheckConfig::from_env("JWT_AUTH"),
+            tx_success_rate_enabled: parse_env("TX_SUCCESS_RATE_CHECK", false),
+            tx_success_rate_blocks: parse_env("TX_SUCCESS_RATE_BLOCKS", 5),
+            min_tx_success_rate_pct: parse_env("MIN_TX_SUCCESS_RATE_PCT", 90.0),
+            tx_success_rate_check: CheckConfig::from_env_or("TX_SUCCESS_RATE", 10),
+            // Only meaningful on PoW chains
+            uncle_rate_enabled: parse_env("UNCLE_RATE_CHECK", false),
+            max_uncle_rate_pct: parse_env("MAX_UNCLE_RATE_PCT", 5.0),
+            uncle_rate_check: CheckConfig::from_env("UNCLE_RATE"),
+            max_uncle_reward_ratio_pct: parse_env_opt("MAX_UNCLE_REWARD_RATIO_PCT"),
+            uncle_reward_check: CheckConfig::from_env("UNCLE_REWARD"),
+            pow_nonce_enabled: parse_env("POW_NONCE_CHECK", false),
+            pow_nonce_check: CheckConfig::from_env("POW_NONCE"),
+            method_routing: method_routing_from_env(),
+            max_blobs_per_block: parse_env_opt("MAX_BLOBS_PER_BLOCK_WARN"),
+            blob_count_check: CheckConfig::from_env("BLOB_COUNT"),
+            // The 'finalized' tag only exists on PoS chains
+            finalization_enabled: parse_env("FINALIZATION_CHECK", false),
+            max_finalization_distance: parse_env("MAX_FINALIZATION_DISTANCE", 128),
+            finalization_check: CheckConfig::from_env("FINALIZATION"),
+            sync_stuck_threshold_minutes: parse_env_opt("SYNC_STUCK_THRESHOLD_MINUTES"),
+            sync_progress_check: CheckConfig::from_env("SYNC_PROGRESS"),
+            filter_addresses: env::var("FILTER_ADDRESS").map(|raw| parse_list(&raw)).unwrap_or_default(),
+            filter_topics: env::var("FILTER_TOPICS").map(|raw| parse_list(&raw)).unwrap_or_default(),
+            filter_check: CheckConfig::from_env("FILTER"),
+            reward_anomaly_enabled: parse_env("REWARD_ANOMALY_CHECK", false),
+            max_reward_multiplier: parse_env("MAX_REWARD_MULTIPLIER", 10.0),
+            reward_anomaly_check: CheckConfig::from_env("REWARD_ANOMALY"),
+            trace_pattern: trace_pattern_from_env(),
+            trace_match_mode: parse_env("TRACE_MATCH_MODE", TraceMatchMode::Present),
+            trace_check: CheckConfig::from_keys("TRACE_BLOCK", "TRACE_BLOCK_CHECK_INTERVAL"),
+            beacon_api_url: secret_env("BEACON_API_URL"),
+            validator_index: parse_env_opt("VALIDATOR_INDEX"),
+            beacon_validator_indices: validator_indices_from_env(),
+            validator_status_check: CheckConfig::from_env("VALIDATOR_STATUS"),
+            expected_fork_version: env::var("EXPECTED_FORK_VERSION").ok(),
+            fork_version_check: CheckConfig::from_env("FORK_VERSION"),
+            beacon_el_status_enabled: parse_env("BEACON_EL_STATUS_CHECK", false),
+            beacon_el_status_check: CheckConfig::from_env("BEACON_EL_STATUS"),
+            slashing_check_enabled: parse_env("SLASHING_CHECK", false),
+            slashing_check: CheckConfig::from_env("SLASHING"),
+            sync_committee_check_enabled: parse_env("SYNC_COMMITTEE_CHECK", false),
+            min_sync_committee_participation_pct: parse_env("MIN_SYNC_COMMITTEE_PARTICIPATION_PCT", 90.0),
+            sync_committee_check: CheckConfig::from_env("SYNC_COMMITTEE"),
+            finality_hash_check_enabled: parse_env("FINALITY_HASH_CHECK", false),
+            finality_hash_check: CheckConfig::from_env("FINALITY_HASH"),
+            min_attestation_effectiveness_pct: parse_env("MIN_ATTESTATION_EFFECTIVENESS_PCT", 80.0),
+            attestation_check: CheckConfig::from_env("ATTESTATION"),
+            io_saturation_device: parse_env("IO_SATURATION_CHECK", false).then(|| io_device_from_env("IO_SATURATION_CHECK")),
+            io_saturation_warn_pct: parse_env("IO_SATURATION_WARN_PCT", 90.0),
+            io_saturation_check: CheckConfig::from_env("IO_SATURATION"),
+            disk_write_throughput_warn: parse_env_opt("DISK_WRITE_THROUGHPUT_WARN_MB_PER_SEC")
+                .map(|warn| (io_device_from_env("DISK_WRITE_THROUGHPUT_WARN_MB_PER_SEC"), warn)),
+            disk_write_throughput_check: CheckConfig::from_env("DISK_WRITE_THROUGHPUT"),
+            client_log_file: env::var("CLIENT_LOG_FILE").ok(),
+            client_log_patterns: client_log_patterns_from_env(),
+            client_log_check: CheckConfig::from_env("CLIENT_LOG"),
+            groups: groups_from_env(),
+            nodes,
+            dedicated_clients: parse_env("DEDICATED_CLIENTS", false),
+            fleet_max_concurrent_checks: parse_env("FLEET_MAX_CONCURRENT_CHECKS", 10),
+            fleet_batching: Batching {
+                size: parse_env("FLEET_BATCH_SIZE", 10),
+                delay: Duration::from_millis(parse_env("FLEET_BATCH_DELAY_MS", 100)),
+            },
+            max_response_size_bytes: parse_env_opt("MAX_RESPONSE_SIZE_BYTES_WARN"),
+            self_monitor: parse_env("SELF_MONITOR", false),
+            self_max_memory_mb: parse_env("SELF_MAX_MEMORY_MB", 256),
+            sqlite_path: env::var("SQLITE_PATH").ok(),
+            db_history_days: parse_env("DB_HISTORY_DAYS", 7),
+            node_label: env::var("NODE_LABEL").unwrap_or_else(|_| "eth-alive".to_string()),
+            log_dest: parse_env("LOG_DEST", LogDest::Stdout),
+            log_format: parse_env("LOG_FORMAT", LogFormat::Plain),
+            log_level: parse_env("LOG_LEVEL", LogLevel::Info),
+            watch_headers: env::var("WATCH_HEADERS").map(|raw| parse_list(&raw)).unwrap_or_default(),
+            header_alert_rules: header_rules_from_env(),
+            trigger_socket_path: env::var("TRIGGER_SOCKET_PATH").ok(),
+            alert_locale: env::var("ALERT_LOCALE").unwrap_or_else(|_| "en".to_string()),
+            ws_logs_addresses: env::var("WS_SUBSCRIBE_LOGS_ADDRESS").map(|raw| parse_list(&raw)).unwrap_or_default(),
+            ws_logs_topics: env::var("WS_SUBSCRIBE_LOGS_TOPICS").map(|raw| parse_list(&raw)).unwrap_or_default(),
+            state_file: env::var("STATE_FILE").unwrap_or_else(|_| "eth-alive-state.json".to_string()),
+            snapshot_file: env::var("SNAPSHOT_FILE").ok(),
+            export_csv_path: env::var("EXPORT_CSV_PATH").ok(),
+            metrics_port: parse_env("METRICS_PORT", 9000),
+            prometheus_extra_labels: prometheus_labels_from_env(),
+            max_metric_labels: parse_env("MAX_METRIC_LABELS", 50),
+            victoria_metrics_url: secret_env("VICTORIA_METRICS_URL"),
+            influxdb: influx_config_from_env(),
+            chain_name: env::var("CHAIN_NAME").unwrap_or_else(|_| "mainnet".to_string()),
+            metadata_url: env::var("METADATA_URL").ok(),
+            metadata_fields: env::var("METADATA_FIELDS").map(|raw| parse_list(&raw)).unwrap_or_default(),
+            metadata_refresh_hours: parse_env("METADATA_REFRESH_HOURS", 6),
+            status_page_url: env::var("STATUS_PAGE_URL").ok(),
+            cloudwatch_namespace: env::var("CLOUDWATCH_NAMESPACE").ok(),
+            aws_region: env::var("AWS_REGION").ok(),
+            status_refresh_minutes: parse_env("STATUS_REFRESH_MINUTES", 5),
+            on_down_exec: env::var("ON_DOWN_EXEC").ok(),
+            on_recovery_exec: env::var("ON_RECOVERY_EXEC").ok(),
+            protocol_version_check: parse_env("PROTOCOL_VERSION_CHECK", false),
+            check_enr_stability: parse_env("CHECK_ENR_STABILITY", false),
+            expected_rpc_bind_address: env::var("EXPECTED_RPC_BIND_ADDRESS").ok(),
+            checkpoint: checkpoint_from_env(),
+        };
+
+        if config.transport_mode == TransportMode::Ws && config.local_ws.is_none() {
+            eprintln!("Error: TRANSPORT_MODE=ws requires LOCAL_WS_URL");
+            process::exit(1);
+        }
+
+        if config.bootnode_check_enabled && config.bootnodes.is_empty() {
+            eprintln!("Error: BOOTNODE_CHECK requires BOOTNODES");
+            process::exit(1);
+        }
+
+        if config.weekend_lag_threshold_multiplier <= 0.0 {
+            eprintln!("Error: WEEKEND_LAG_THRESHOLD_MULTIPLIER must be greater than 0");
+            process::exit(1);
+        }
+
+        if config.cloudwatch_namespace.is_some() && config.aws_region.is_none() {
+            eprintln!("Error: CLOUDWATCH_NAMESPACE requires AWS_REGION");
+            process::exit(1);
+        }
+
+        if config.status_page_url.is_some() && config.status_refresh_minutes == 0 {
+            eprintln!("Error: STATUS_REFRESH_MINUTES must be at least 1");
+            process::exit(1);
+        }
+
+        if config.metadata_url.is_some() && (config.metadata_fields.is_empty() || config.metadata_refresh_hours == 0) {
+            eprintln!("Error: METADATA_URL requires METADATA_FIELDS and a METADATA_REFRESH_HOURS of at least 1");
+            process::exit(1);
+        }
+
+        if config.tx_success_rate_enabled && config.tx_success_rate_blocks == 0 {
+            eprintln!("Error: TX_SUCCESS_RATE_BLOCKS must be at least 1");
+            process::exit(1);
+        }
+
+        if config.fleet_batching.size == 0 {
+            eprintln!("Error: FLEET_BATCH_SIZE must be at least 1");
+            process::exit(1);
+        }
+
+        if config.zkevm_blocks_per_batch == 0 {
+            eprintln!("Error: ZKEVM_BLOCKS_PER_BATCH must be at least 1");
+            process::exit(1);
+        }
+
+        if config.fleet_max_concurrent_checks == 0 {
+            eprintln!("Error: FLEET_MAX_CONCURRENT_CHECKS must be at least 1");
+            process::exit(1);
+        }
+
+        if config.max_concurrent_webhooks == 0 {
+            eprintln!("Error: MAX_CONCURRENT_WEBHOOKS must be at least 1");
+            process::exit(1);
+        }
+
+        if config.validator_index.is_some() && config.beacon_api_url.is_none() {
+            eprintln!("Error: VALIDATOR_INDEX requires BEACON_API_URL");
+            process::exit(1);
+        }
+
+        if !config.beacon_validator_indices.is_empty() && config.beacon_api_url.is_none() {
+            eprintln!("Error: BEACON_VALIDATOR_INDICES requires BEACON_API_URL");
+            process::exit(1);
+        }
+
+        if config.external_probe_url.is_some() && probe_target(&config.local_rpc).is_none() {
+            eprintln!("Error: EXTERNAL_PROBE_URL needs a LOCAL_RPC_URL with a host and port");
+            process::exit(1);
+        }
+
+        if config.expected_fork_version.is_some() && config.beacon_api_url.is_none() {
+            eprintln!("Error: EXPECTED_FORK_VERSION requires BEACON_API_URL");
+            process::exit(1);
+        }
+
+        if config.beacon_el_status_enabled && config.beacon_api_url.is_none() {
+            eprintln!("Error: BEACON_EL_STATUS_CHECK requires BEACON_API_URL");
+            process::exit(1);
+        }
+
+        if config.slashing_check_enabled && config.beacon_api_url.is_none() {
+            eprintln!("Error: SLASHING_CHECK requires BEACON_API_URL");
+            process::exit(1);
+        }
+
+        if config.sync_committee_check_enabled && config.beacon_api_url.is_none() {
+            eprintln!("Error: SYNC_COMMITTEE_CHECK requires BEACON_API_URL");
+            process::exit(1);
+        }
+
+        if config.finality_hash_check_enabled && config.beacon_api_url.is_none() {
+            eprintln!("Error: FINALITY_HASH_CHECK requires BEACON_API_URL");
+            process::exit(1);
+        }
+
+        if let Ok(hostname) = env::var("LOCAL_RPC_SNI_HOSTNAME") {
+            let (url, addr) = sni_override(&config.local_rpc, &hostname).unwrap_or_else(|e| {
+                eprintln!("Error: LOCAL_RPC_SNI_HOSTNAME: {}", e);
+                process::exit(1);
+            });
+            config.local_rpc = url;
+            config.local_rpc_sni = Some((hostname, addr));
+        }
+
+        config.notification_plugins = notification_plugins_from_env(&config);
+        config
+    }
+
+    /// LAG_THRESHOLD, times WEEKEND_LAG_THRESHOLD_MULTIPLIER on Saturdays and
+    /// Sundays (UTC)
+    pub fn lag_threshold_at(&self, now: DateTime<Utc>) -> u64 {
+        lag_threshold_at(self.lag_threshold, self.weekend_lag_threshold_multiplier, now)
+    }
+}
+
+pub fn lag_threshold_at(lag_threshold: u64, weekend_multiplier: f64, now: DateTime<Utc>) -> u64 {
+    match now.weekday() {
+        // Rounded, and at least 1 block
+        Weekday::Sat | Weekday::Sun => ((lag_threshold as f64 * weekend_multiplier).round() as u64).max(1),
+        _ => lag_threshold,
+    }
+}
+
+/// `url` with its IP address replaced by `hostname`, and the address it named
+fn sni_override(url: &str, hostname: &str) -> Result<(String, SocketAddr), String> {
+    let mut parsed = url::Url::parse(url).map_err(|e| format!("invalid LOCAL_RPC_URL: {}", e))?;
+    if parsed.scheme() != "https" {
+        return Err("LOCAL_RPC_URL must be an https:// URL".to_string());
+    }
+    let ip = match parsed.host() {
+        Some(url::Host::Ipv4(ip)) => IpAddr::V4(ip),
+        Some(url::Host::Ipv6(ip)) => IpAddr::V6(ip),
+        _ => return Err("LOCAL_RPC_URL must use an IP address; put the hostname in the URL instead".to_string()),
+    };
+    let port = parsed.port_or_known_default().expect("https has a default port");
+    parsed.set_host(Some(hostname)).map_err(|e| format!("invalid hostname '{}': {}", hostname, e))?;
+    Ok((parsed.to_string(), SocketAddr::new(ip, port)))
+}
+
+/// Applies .env (if present) and the --config file to the environment. Call
+/// it while the process is still single-threaded, ahead of `Config::from_env`
+pub fn load_env() {
+    dotenv().ok();
+    load_config_file(config_path_arg(env::args()));
+}
+
+/// `--config <path>` (or `--config=<path>`) from the command line
+fn config_path_arg(mut args: impl Iterator<Item = String>) -> Option<String> {
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            return Some(args.next().unwrap_or_else(|| {
+                eprintln!("Error: --config needs the path of a TOML file");
+                process::exit(1);
+            }));
+        }
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return Some(path.to_string());
+        }
+    }
+    None
+}
+
+/// Reads the TOML settings file into the environment. Variables already set
+/// (including from .env) win over the file. A missing `--config` file is an
+/// error and a missing default `config.toml` a warning, unless
+/// CONFIG_MISSING_BEHAVIOR says otherwise
+fn load_config_file(explicit_path: Option<String>) {
+    let default_behavior = if explicit_path.is_some() { ConfigMissingBehavior::Error } else { ConfigMissingBehavior::Warn };
+    let path = explicit_path.unwrap_or_else(|| DEFAULT_CONFIG_PATH.to_string());
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let looked_for = env::current_dir().map(|dir| dir.join(&path)).unwrap_or_else(|_| path.clone().into());
+            match parse_env("CONFIG_MISSING_BEHAVIOR", default_behavior) {
+                ConfigMissingBehavior::Error => {
+                    eprintln!("Error: Config file not found: {}", looked_for.display());
+                    process::exit(1);
+                }
+                ConfigMissingBehavior::Warn => {
+                    eprintln!("Warning: Config file not found: {} (using environment variables only)", looked_for.display());
+                    return;
+                }
+            }
+        }
+        Err(e) => {
+            eprintln!("Error: Failed to read config file '{}': {}", path, e);
+            process::exit(1);
+        }
+    };
+    let vars = config_file_vars(&contents, &path).unwrap_or_else(|e| {
+        eprintln!("Error: Invalid config file '{}': {}", path, e);
+        process::exit(1);
+    });
+    for (key, value) in vars {
+        if env::var_os(&key).is_none() {
+            // SAFETY: only called from `load_env`, before the tokio runtime
+            // starts its worker threads, so nothing reads the environment concurrently
+            unsafe { env::set_var(&key, value) };
+        }
+    }
+}
+
+/// The environment variables a settings file stands for: top-level keys are
+/// variable names with scalar values (arrays are joined with commas), and
+/// `[[nodes]]`/`[[network]]` tables make the file the NODES configuration
+fn config_file_vars(contents: &str, path: &str) -> Result<Vec<(String, String)>, String> {
+    let table: toml::Table = toml::from_str(contents).map_err(|e| e.to_string())?;
+    let scalar = |key: &str, value: &toml::Value| match value {
+        toml::Value::String(s) => Ok(s.clone()),
+        toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_) => Ok(value.to_string()),
+        _ => Err(format!("'{}' must be a string, number, boolean, or array of those", key)),
+    };
+    table.iter().map(|(key, value)| match (key.as_str(), value) {
+        ("nodes" | "network", _) => Ok(("NODES".to_string(), path.to_string())),
+        (_, toml::Value::Array(items)) => {
+            let items = items.iter().map(|item| scalar(key, item)).collect::<Result<Vec<_>, _>>()?;
+            Ok((key.clone(), items.join(",")))
+        }
+        _ => Ok((key.clone(), scalar(key, value)?)),
+    }).collect()
+}
+
+/// The disk holding DATA_DIR (default "/"); exits if its I/O statistics
+/// can't be read (e.g. outside Linux)
+fn io_device_from_env(setting: &str) -> String {
+    let data_dir = env::var("DATA_DIR").unwrap_or_else(|_| "/".to_string());
+    device_for_path(&data_dir).unwrap_or_else(|| {
+        eprintln!("Error: {} found no disk in /proc/diskstats holding DATA_DIR '{}'", setting, data_dir);
+        process::exit(1);
+    })
+}
+
+/// The webhook plugin in the format detected from DISCORD_WEBHOOK_URL, plus
+/// Telegram when TELEGRAM_BOT_TOKEN and TELEGRAM_CHAT_ID are set
+pub fn notification_plugins_from_env(config: &Config) -> Vec<Box<dyn NotificationPlugin + Send + Sync>> {
+    let client = webhook_client(config.webhook_timeout_secs);
+    let mut plugins = Vec::new();
+    if webhook_enabled(&config.discord_webhook) {
+        let target = WebhookTarget {
+            client: client.clone(),
+            url: config.discord_webhook.clone(),
+            proxy_url: config.webhook_proxy_url.clone(),
+            hmac_secret: config.webhook_hmac_secret.clone(),
+        };
+        plugins.push(webhook_plugin(&config.notification_backend, target));
+    }
+    match (secret_env("TELEGRAM_BOT_TOKEN"), env::var("TELEGRAM_CHAT_ID").ok()) {
+        (Some(bot_token), Some(chat_id)) => plugins.push(Box::new(TelegramPlugin::new(client, bot_token, chat_id))),
+        (None, None) => {}
+        _ => {
+            eprintln!("Error: TELEGRAM_BOT_TOKEN and TELEGRAM_CHAT_ID must be set together");
+            process::exit(1);
+        }
+    }
+    if let Some(smtp) = &config.smtp {
+        match EmailPlugin::new(smtp, Duration::from_secs(config.webhook_timeout_secs)) {
+            Ok(plugin) => plugins.push(Box::new(plugin)),
+            Err(e) => {
+                eprintln!("Error: Invalid SMTP configuration: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+    plugins
+}
+
+/// The email channel, when SMTP_HOST is set. SMTP_PORT defaults to the usual
+/// port of the SMTP_TLS mode
+fn smtp_from_env() -> Option<SmtpConfig> {
+    let host = env::var("SMTP_HOST").ok()?;
+    let tls = parse_env("SMTP_TLS", SmtpTls::StartTls);
+    let port = parse_env("SMTP_PORT", tls.default_port());
+    if tls == SmtpTls::None && port == 465 {
+        eprintln!("Error: SMTP_TLS=none can't be used with SMTP_PORT=465, which expects implicit TLS");
+        process::exit(1);
+    }
+    let credentials = match (env::var("SMTP_USERNAME").ok(), secret_env("SMTP_PASSWORD")) {
+        (Some(username), Some(password)) => Some((username, password)),
+        (None, None) => None,
+        _ => {
+            eprintln!("Error: SMTP_USERNAME and SMTP_PASSWORD must be set together");
+            process::exit(1);
+        }
+    };
+    let mailbox = |key: &str, raw: &str| raw.parse().unwrap_or_else(|e| {
+        eprintln!("Error: Invalid address '{}' in {}: {}", raw, key, e);
+        process::exit(1);
+    });
+    let from = mailbox("SMTP_FROM", &get_env("SMTP_FROM"));
+    let to: Vec<_> = parse_list(&get_env("SMTP_TO")).iter().map(|raw| mailbox("SMTP_TO", raw)).collect();
+    if to.is_empty() {
+        eprintln!("Error: SMTP_TO needs at least one address");
+        process::exit(1);
+    }
+    Some(SmtpConfig { host, port, tls, credentials, from, to })
+}
+
+/// The InfluxDB export, when INFLUXDB_URL is set. INFLUXDB_TOKEN, INFLUXDB_ORG
+/// and INFLUXDB_BUCKET are then required; exits if one is missing
+fn influx_config_from_env() -> Option<InfluxConfig> {
+    let url = secret_env("INFLUXDB_URL")?;
+    let required = |key: &str| secret_env(key).unwrap_or_else(|| {
+        eprintln!("Error: INFLUXDB_URL requires {}", key);
+        process::exit(1);
+    });
+    let batch_cycles = parse_env("INFLUXDB_BATCH_CYCLES", 10);
+    if batch_cycles == 0 {
+        eprintln!("Error: INFLUXDB_BATCH_CYCLES must be at least 1");
+        process::exit(1);
+    }
+    Some(InfluxConfig { url, token: required("INFLUXDB_TOKEN"), org: required("INFLUXDB_ORG"), bucket: required("INFLUXDB_BUCKET"), batch_cycles })
+}
+
+// --- Helpers ---
+
+/// REMOTE_RPC_URLS (comma-separated, in priority order) or the single REMOTE_RPC_URL
+fn remote_rpcs_from_env() -> Vec<String> {
+    match env::var("REMOTE_RPC_URLS") {
+        Ok(raw) if !parse_list(&raw).is_empty() => parse_list(&raw).iter().map(|url| resolve_secret_value(url)).collect(),
+        _ => vec![get_secret_env("REMOTE_RPC_URL")],
+    }
+}
+
+/// Splits a comma-separated list, dropping empty entries
+pub fn parse_list(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// BEACON_VALIDATOR_INDICES as a list of validator indices
+fn validator_indices_from_env() -> Vec<u64> {
+    let raw = env::var("BEACON_VALIDATOR_INDICES").unwrap_or_default();
+    parse_list(&raw).iter()
+        .map(|index| index.parse().unwrap_or_else(|_| {
+            eprintln!("Error: Invalid validator index in BEACON_VALIDATOR_INDICES: {}", index);
+            process::exit(1);
+        }))
+        .collect()
+}
+
+/// MONITOR_PENDING_ADDRESS with MAX_PENDING_NONCE_GAP (default 10) and
+/// WATCH_NONCE_ADDRESS with MAX_NONCE_GAP (default 5); both may be set
+fn pending_nonce_addresses_from_env() -> Vec<(String, u64)> {
+    [("MONITOR_PENDING_ADDRESS", "MAX_PENDING_NONCE_GAP", 10), ("WATCH_NONCE_ADDRESS", "MAX_NONCE_GAP", 5)].into_iter()
+        .filter_map(|(address, gap, default)| Some((env::var(address).ok()?, parse_env(gap, default))))
+        .collect()
+}
+
+/// The trusted checkpoint verified at startup: CHECKPOINT_BLOCK and the
+/// CHECKPOINT_STATE_ROOT it must have, which is then required
+fn checkpoint_from_env() -> Option<(u64, String)> {
+    let block = parse_env_opt("CHECKPOINT_BLOCK")?;
+    Some((block, get_env("CHECKPOINT_STATE_ROOT")))
+}
+
+/// The eth_call check is enabled by CONTRACT_CALL_ADDRESS and needs its data and expected result
+fn contract_call_from_env() -> Option<ContractCallConfig> {
+    let address = env::var("CONTRACT_CALL_ADDRESS").ok()?;
+    Some(ContractCallConfig {
+        address,
+        data: get_env("CONTRACT_CALL_DATA"),
+        expected_result: get_env("CONTRACT_CALL_EXPECTED_RESULT"),
+    })
+}
+
+/// Reads the Engine API secret from JWT_SECRET_PATH, exiting on invalid input
+fn jwt_secret_from_env() -> Option<Vec<u8>> {
+    let path = env::var("JWT_SECRET_PATH").ok()?;
+    let secret = std::fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| parse_jwt_secret(&contents));
+    Some(secret.unwrap_or_else(|e| {
+        eprintln!("Error: Invalid JWT_SECRET_PATH file '{}': {}", path, e);
+        process::exit(1);
+    }))
+}
+
+/// EXPECTED_MINING_STATE: `true`, `false` or `ignore` (default, no check)
+fn expected_mining_from_env() -> Option<bool> {
+    match env::var("EXPECTED_MINING_STATE").as_deref() {
+        Ok("true") => Some(true),
+        Ok("false") => Some(false),
+        Ok("ignore") | Err(_) => None,
+        Ok(other) => {
+            eprintln!("Error: Invalid value for 'EXPECTED_MINING_STATE': {} (expected true, false or ignore)", other);
+            process::exit(1);
+        }
+    }
+}
+
+/// Compiles the optional TRACE_PATTERN regex, exiting on invalid input
+fn trace_pattern_from_env() -> Option<Regex> {
+    let raw = env::var("TRACE_PATTERN").ok()?;
+    Some(Regex::new(&raw).unwrap_or_else(|e| {
+        eprintln!("Error: Invalid TRACE_PATTERN: {}", e);
+        process::exit(1);
+    }))
+}
+
+/// Compiles CLIENT_LOG_ERROR_PATTERNS (comma-separated regexes), exiting on
+/// invalid input
+fn client_log_patterns_from_env() -> RegexSet {
+    let patterns = env::var("CLIENT_LOG_ERROR_PATTERNS")
+        .map(|raw| parse_list(&raw))
+        .unwrap_or_else(|_| vec![crate::clientlog::DEFAULT_ERROR_PATTERN.to_string()]);
+    RegexSet::new(&patterns).unwrap_or_else(|e| {
+        eprintln!("Error: Invalid CLIENT_LOG_ERROR_PATTERNS: {}", e);
+        process::exit(1);
+    })
+}
+
+/// Loads the METHOD_ROUTING TOML file, exiting on invalid input
+fn method_routing_from_env() -> Option<MethodRouting> {
+    let path = env::var("METHOD_ROUTING").ok()?;
+    let routing = std::fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|raw| parse_routing(&raw));
+    Some(routing.unwrap_or_else(|e| {
+        eprintln!("Error: Invalid METHOD_ROUTING file '{}': {}", path, e);
+        process::exit(1);
+    }))
+}
+
+/// Parses the optional HEADER_ALERT_PATTERN rules, exiting on invalid input
+fn header_rules_from_env() -> Vec<HeaderRule> {
+    match env::var("HEADER_ALERT_PATTERN") {
+        Ok(raw) => parse_rules(&raw).unwrap_or_else(|e| {
+            eprintln!("Error: Invalid HEADER_ALERT_PATTERN: {}", e);
+            process::exit(1);
+        }),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Reads the optional PROMETHEUS_EXTRA_LABELS JSON object, exiting on invalid input
+fn prometheus_labels_from_env() -> Vec<(String, String)> {
+    match env::var("PROMETHEUS_EXTRA_LABELS") {
+        Ok(raw) => parse_extra_labels(&raw).unwrap_or_else(|e| {
+            eprintln!("Error: Invalid PROMETHEUS_EXTRA_LABELS: {}", e);
+            process::exit(1);
+        }),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Reads the optional GROUPS JSON array, exiting on invalid input
+fn groups_from_env() -> Vec<GroupConfig> {
+    match env::var("GROUPS") {
+        Ok(raw) => parse_groups(&raw).unwrap_or_else(|e| {
+            eprintln!("Error: Invalid GROUPS configuration: {}", e);
+            process::exit(1);
+        }),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Reads the optional NODES configuration (inline or a file path), exiting on invalid input
+fn nodes_from_env() -> Vec<NodeConfig> {
+    match env::var("NODES") {
+        Ok(raw) => {
+            let mut nodes = read_nodes_config(&raw).unwrap_or_else(|e| {
+                eprintln!("Error: Invalid NODES configuration: {}", e);
+                process::exit(1);
+            });
+            for node in &mut nodes {
+                node.local_rpc = resolve_secret_value(&node.local_rpc);
+                node.remote_rpc = resolve_secret_value(&node.remote_rpc);
+                node.discord_webhook = node.discord_webhook.as_deref().map(resolve_secret_value);
+            }
+            nodes
+        }
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Fetches an environment variable or exits if not found
+pub fn get_env(key: &str) -> String {
+    env::var(key).unwrap_or_else(|_| {
+        eprintln!("Error: Required environment variable '{}' not set.", key);
+        process::exit(1);
+    })
+}
+
+/// Like `get_env`, for sensitive values that may be given as `file://` references
+fn get_secret_env(key: &str) -> String {
+    resolve_secret_value(&get_env(key))
+}
+
+/// Reads an optional sensitive value, resolving `file://` references
+fn secret_env(key: &str) -> Option<String> {
+    env::var(key).ok().map(|raw| resolve_secret_value(&raw))
+}
+
+/// `file:///run/secrets/x` stands for the contents of that file (trailing
+/// whitespace trimmed), e.g. a Kubernetes secret mounted as a file; any other
+/// value is used as is. Exits if the file can't be read.
+pub fn resolve_secret_value(val: &str) -> String {
+    let Some(path) = val.strip_prefix("file://") else {
+        return val.to_string();
+    };
+    match std::fs::read_to_string(path) {
+        Ok(contents) => contents.trim_end().to_string(),
+        Err(e) => {
+            eprintln!("Error: Failed to read secret file '{}': {}", path, e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Parses an optional environment variable, falling back to `default` when unset
+pub fn parse_env<T: FromStr>(key: &str, default: T) -> T {
+    parse_env_opt(key).unwrap_or(default)
+}
+
+/// Parses an optional environment variable, returning None when unset
+pub fn parse_env_opt<T: FromStr>(key: &str) -> Option<T> {
+    env::var(key).ok().map(|raw| {
+        raw.parse::<T>().unwrap_or_else(|_| {
+            eprintln!("Error: Environment variable '{}' has an invalid value: '{}'", key, raw);
+            process::exit(1);
+        })
+    })
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_list() {
+        assert_eq!(parse_list("a, b,,c "), vec!["a", "b", "c"]);
+        assert!(parse_list(" , ").is_empty());
+    }
+
+    #[test]
+    fn test_sni_override() {
+        let (url, addr) = sni_override("https://203.0.113.7:8545/rpc", "rpc.example.com").unwrap();
+        assert_eq!(url, "https://rpc.example.com:8545/rpc");
+        assert_eq!(addr, "203.0.113.7:8545".parse().unwrap());
+        let (url, addr) = sni_override("https://[2001:db8::1]/", "rpc.example.com").unwrap();
+        assert_eq!(url, "https://rpc.example.com/");
+        assert_eq!(addr, "[2001:db8::1]:443".parse().unwrap());
+        assert!(sni_override("http://203.0.113.7:8545", "rpc.example.com").is_err());
+        assert!(sni_override("https://node.local:8545", "rpc.example.com").is_err());
+    }
+
+    #[test]
+    fn test_weekend_lag_threshold() {
+        let at = |date: &str| DateTime::parse_from_rfc3339(date).unwrap().with_timezone(&Utc);
+        assert_eq!(lag_threshold_at(4, 2.5, at("2024-05-03T23:59:59Z")), 4);
+        assert_eq!(lag_threshold_at(4, 2.5, at("2024-05-04T00:00:00Z")), 10);
+        assert_eq!(lag_threshold_at(4, 2.5, at("2024-05-05T12:00:00Z")), 10);
+        assert_eq!(lag_threshold_at(4, 0.1, at("2024-05-05T12:00:00Z")), 1);
+        assert_eq!(lag_threshold_at(4, 2.5, at("2024-05-06T00:00:00Z")), 4);
+    }
+
+    #[test]
+    fn test_config_path_arg() {
+        let args = |list: &[&str]| list.iter().map(|arg| arg.to_string()).collect::<Vec<_>>().into_iter();
+        assert_eq!(config_path_arg(args(&["eth-alive", "--config", "/etc/eth-alive.toml"])), Some("/etc/eth-alive.toml".to_string()));
+        assert_eq!(config_path_arg(args(&["eth-alive", "--repl", "--config=prod.toml"])), Some("prod.toml".to_string()));
+        assert_eq!(config_path_arg(args(&["eth-alive", "--repl"])), None);
+    }
+
+    #[test]
+    fn test_config_file_vars() {
+        let contents = r#"
+            LOCAL_RPC_URL = "http://localhost:8545"
+            LAG_THRESHOLD = 5
+            ALERT_DEDUPE = true
+            REMOTE_RPC_URLS = ["https://a", "https://b"]
+
+            [[nodes]]
+            name = "mainnet"
+            local_rpc = "http://a:8545"
+            remote_rpc = "https://r"
+        "#;
+        let mut vars = config_file_vars(contents, "/etc/eth-alive.toml").unwrap();
+        vars.sort();
+        assert_eq!(vars, vec![
+            ("ALERT_DEDUPE".to_string(), "true".to_string()),
+            ("LAG_THRESHOLD".to_string(), "5".to_string()),
+            ("LOCAL_RPC_URL".to_string(), "http://localhost:8545".to_string()),
+            ("NODES".to_string(), "/etc/eth-alive.toml".to_string()),
+            ("REMOTE_RPC_URLS".to_string(), "https://a,https://b".to_string()),
+        ]);
+        assert!(config_file_vars("[routing]\nx = 1", "c.toml").unwrap_err().contains("'routing'"));
+    }
+
+    #[test]
+    fn test_resolve_secret_value() {
+        assert_eq!(resolve_secret_value("https://discord.com/api/webhooks/1/abc"), "https://discord.com/api/webhooks/1/abc");
+
+        let path = std::env::temp_dir().join(format!("eth-alive-secret-{}", std::process::id()));
+        std::fs::write(&path, "https://discord.com/api/webhooks/1/abc \n\n").unwrap();
+        let resolved = resolve_secret_value(&format!("file://{}", path.display()));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(resolved, "https://discord.com/api/webhooks/1/abc");
+    }
+}
@@ -0,0 +1,202 @@
+
+// --- Poll History (SQLite) ---
+
+use std::sync::{Arc, Mutex};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
+
+/// One row of the 'polls' table
+pub struct PollRecord {
+    pub timestamp: DateTime<Utc>,
+    pub local_block: Option<u64>,
+    pub remote_block: Option<u64>,
+    pub local_up: bool,
+    pub remote_up: bool,
+    pub rpc_latency_ms: u64,
+}
+
+impl PollRecord {
+    /// Remote minus local; negative when the local node is ahead
+    fn lag(&self) -> Option<i64> {
+        match (self.local_block, self.remote_block) {
+            (Some(local), Some(remote)) => Some(remote as i64 - local as i64),
+            _ => None,
+        }
+    }
+}
+
+/// Aggregated view of the stored poll history
+#[derive(Debug, PartialEq)]
+pub struct LagSummary {
+    pub polls: u64,
+    pub first: Option<String>,
+    pub last: Option<String>,
+    pub local_down: u64,
+    pub remote_down: u64,
+    pub avg_lag: Option<f64>,
+    pub max_lag: Option<i64>,
+    pub lagging_polls: u64,
+    pub avg_latency_ms: Option<f64>,
+}
+
+/// Handle to the poll history database.
+/// rusqlite is blocking, so writes from the poll loop go through spawn_blocking.
+#[derive(Clone)]
+pub struct PollDb {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl PollDb {
+    pub fn open(path: &str) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS polls (
+                timestamp      TEXT NOT NULL,
+                local_block    INTEGER,
+                remote_block   INTEGER,
+                lag            INTEGER,
+                local_up       INTEGER NOT NULL,
+                remote_up      INTEGER NOT NULL,
+                rpc_latency_ms INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS polls_timestamp ON polls (timestamp);",
+        )?;
+        Ok(PollDb { conn: Arc::new(Mutex::new(conn)) })
+    }
+
+    /// Deletes rows older than `days`. Returns the number of rows removed
+    pub fn prune(&self, days: u64) -> rusqlite::Result<usize> {
+        let cutoff = (Utc::now() - chrono::Duration::days(days as i64)).format(TIMESTAMP_FORMAT).to_string();
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM polls WHERE timestamp < ?1", params![cutoff])
+    }
+
+    /// Inserts a poll row without blocking the async runtime
+    pub async fn insert(&self, record: PollRecord) {
+        let db = self.clone();
+        let result = tokio::task::spawn_blocking(move || db.insert_blocking(&record)).await;
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => error!("[ERROR] Failed to write poll to SQLite: {}", e),
+            Err(e) => error!("[ERROR] SQLite writer task failed: {}", e),
+        }
+    }
+
+    fn insert_blocking(&self, record: &PollRecord) -> rusqlite::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO polls (timestamp, local_block, remote_block, lag, local_up, remote_up, rpc_latency_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                record.timestamp.format(TIMESTAMP_FORMAT).to_string(),
+                record.local_block.map(|b| b as i64),
+                record.remote_block.map(|b| b as i64),
+                record.lag(),
+                record.local_up,
+                record.remote_up,
+                record.rpc_latency_ms as i64,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Summarises the stored history. Polls with lag >= `lag_threshold` count as lagging
+    pub fn lag_summary(&self, lag_threshold: u64) -> rusqlite::Result<LagSummary> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT COUNT(*), MIN(timestamp), MAX(timestamp),
+                    COALESCE(SUM(local_up = 0), 0), COALESCE(SUM(remote_up = 0), 0),
+                    AVG(lag), MAX(lag), COALESCE(SUM(lag >= ?1), 0), AVG(rpc_latency_ms)
+             FROM polls",
+            params![lag_threshold as i64],
+            |row| Ok(LagSummary {
+                polls: row.get::<_, i64>(0)? as u64,
+                first: row.get(1)?,
+                last: row.get(2)?,
+                local_down: row.get::<_, i64>(3)? as u64,
+                remote_down: row.get::<_, i64>(4)? as u64,
+                avg_lag: row.get(5)?,
+                max_lag: row.get(6)?,
+                lagging_polls: row.get::<_, i64>(7)? as u64,
+                avg_latency_ms: row.get(8)?,
+            }),
+        )
+    }
+}
+
+/// Prints the lag summary report for `--analyze`
+pub fn print_report(path: &str, lag_threshold: u64) -> rusqlite::Result<()> {
+    let summary = PollDb::open(path)?.lag_summary(lag_threshold)?;
+
+    println!("eth-alive poll history: {}", path);
+    if summary.polls == 0 {
+        println!("  No polls recorded yet.");
+        return Ok(());
+    }
+
+    let pct = |n: u64| n as f64 / summary.polls as f64 * 100.0;
+    println!("  Period:            {} -> {}", summary.first.unwrap_or_default(), summary.last.unwrap_or_default());
+    println!("  Polls:             {}", summary.polls);
+    println!("  Local Down:        {} ({:.1}%)", summary.local_down, pct(summary.local_down));
+    println!("  Remote Down:       {} ({:.1}%)", summary.remote_down, pct(summary.remote_down));
+    println!("  Average Lag:       {:.2} blocks", summary.avg_lag.unwrap_or(0.0));
+    println!("  Max Lag:           {} blocks", summary.max_lag.unwrap_or(0));
+    println!("  Lagging Polls:     {} ({:.1}%) at threshold {}", summary.lagging_polls, pct(summary.lagging_polls), lag_threshold);
+    println!("  Average Latency:   {:.0} ms", summary.avg_latency_ms.unwrap_or(0.0));
+    Ok(())
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(timestamp: DateTime<Utc>, local: Option<u64>, remote: Option<u64>) -> PollRecord {
+        PollRecord {
+            timestamp,
+            local_block: local,
+            remote_block: remote,
+            local_up: local.is_some(),
+            remote_up: remote.is_some(),
+            rpc_latency_ms: 100,
+        }
+    }
+
+    #[test]
+    fn test_lag_summary() {
+        let db = PollDb::open(":memory:").unwrap();
+        let now = Utc::now();
+        db.insert_blocking(&record(now, Some(100), Some(101))).unwrap();
+        db.insert_blocking(&record(now, Some(100), Some(105))).unwrap();
+        db.insert_blocking(&record(now, None, Some(106))).unwrap();
+
+        let summary = db.lag_summary(3).unwrap();
+        assert_eq!(summary.polls, 3);
+        assert_eq!(summary.local_down, 1);
+        assert_eq!(summary.remote_down, 0);
+        assert_eq!(summary.avg_lag, Some(3.0));
+        assert_eq!(summary.max_lag, Some(5));
+        assert_eq!(summary.lagging_polls, 1);
+    }
+
+    #[test]
+    fn test_empty_summary() {
+        let db = PollDb::open(":memory:").unwrap();
+        let summary = db.lag_summary(3).unwrap();
+        assert_eq!(summary.polls, 0);
+        assert_eq!(summary.max_lag, None);
+    }
+
+    #[test]
+    fn test_prune_removes_old_rows() {
+        let db = PollDb::open(":memory:").unwrap();
+        db.insert_blocking(&record(Utc::now() - chrono::Duration::days(10), Some(1), Some(1))).unwrap();
+        db.insert_blocking(&record(Utc::now(), Some(2), Some(2))).unwrap();
+
+        assert_eq!(db.prune(7).unwrap(), 1);
+        assert_eq!(db.lag_summary(3).unwrap().polls, 1);
+    }
+}
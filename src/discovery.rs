@@ -0,0 +1,110 @@
+
+// --- P2P Discovery Probe ---
+//
+// A node whose UDP discovery port is firewalled keeps its existing peers but
+// is never found by new ones. The probe sends a signed discv4 PING from this
+// host and waits for the PONG; only a node reachable over UDP can answer.
+
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use k256::ecdsa::SigningKey;
+use sha3::{Digest, Keccak256};
+use tokio::net::UdpSocket;
+
+use crate::rlp::{rlp_bytes, rlp_list, rlp_uint};
+
+/// discv4 protocol version carried in PING
+const DISCOVERY_VERSION: u64 = 4;
+
+const PING: u8 = 0x01;
+const PONG: u8 = 0x02;
+
+/// Offset of the packet type: hash (32 bytes) + signature (65 bytes)
+const TYPE_OFFSET: usize = 97;
+
+/// PINGs sent before giving up, and how long to wait for a PONG after each
+const ATTEMPTS: u32 = 3;
+const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Sends discv4 PINGs to `target` and returns true once a PONG comes back
+pub async fn probe(target: SocketAddr, tcp_port: u16) -> std::io::Result<bool> {
+    let bind: SocketAddr = if target.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" }.parse().expect("valid bind address");
+    let socket = UdpSocket::bind(bind).await?;
+    let from = socket.local_addr()?;
+
+    // A throwaway identity: the node answers any well-formed PING
+    let key = SigningKey::random(&mut rand::rngs::OsRng);
+    let mut buf = [0u8; 1280];
+
+    for _ in 0..ATTEMPTS {
+        socket.send_to(&ping_packet(&key, from, target, tcp_port), target).await?;
+        let deadline = tokio::time::Instant::now() + ATTEMPT_TIMEOUT;
+        while let Ok(received) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buf)).await {
+            let (len, source) = received?;
+            if source.ip() == target.ip() && len > TYPE_OFFSET && buf[TYPE_OFFSET] == PONG {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// hash || signature || type || rlp([version, from, to, expiration])
+fn ping_packet(key: &SigningKey, from: SocketAddr, to: SocketAddr, tcp_port: u16) -> Vec<u8> {
+    let expiration = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0) + 20;
+    let data = rlp_list(&[
+        rlp_uint(DISCOVERY_VERSION),
+        endpoint(from, from.port()),
+        endpoint(to, tcp_port),
+        rlp_uint(expiration),
+    ]);
+
+    let mut signed = vec![PING];
+    signed.extend_from_slice(&data);
+    let (signature, recovery_id) = key.sign_prehash_recoverable(&Keccak256::digest(&signed))
+        .expect("signing a 32 byte digest can't fail");
+
+    let mut body = signature.to_bytes().to_vec();
+    body.push(recovery_id.to_byte());
+    body.extend_from_slice(&signed);
+
+    let mut packet = Keccak256::digest(&body).to_vec();
+    packet.extend_from_slice(&body);
+    packet
+}
+
+/// rlp([ip, udp_port, tcp_port])
+fn endpoint(addr: SocketAddr, tcp_port: u16) -> Vec<u8> {
+    let ip = match addr.ip() {
+        IpAddr::V4(ip) => ip.octets().to_vec(),
+        IpAddr::V6(ip) => ip.octets().to_vec(),
+    };
+    rlp_list(&[rlp_bytes(&ip), rlp_uint(addr.port() as u64), rlp_uint(tcp_port as u64)])
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ping_packet_layout() {
+        let key = SigningKey::random(&mut rand::rngs::OsRng);
+        let from: SocketAddr = "10.0.0.1:40000".parse().unwrap();
+        let to: SocketAddr = "10.0.0.2:30303".parse().unwrap();
+        let packet = ping_packet(&key, from, to, 30303);
+
+        assert_eq!(packet[TYPE_OFFSET], PING);
+        // The leading hash covers everything after it
+        assert_eq!(&packet[..32], Keccak256::digest(&packet[32..]).as_slice());
+
+        // The signature recovers to the sender's key (how the node learns our ID)
+        let signature = k256::ecdsa::Signature::from_slice(&packet[32..96]).unwrap();
+        let recovery_id = k256::ecdsa::RecoveryId::from_byte(packet[96]).unwrap();
+        let digest = Keccak256::digest(&packet[TYPE_OFFSET..]);
+        let recovered = k256::ecdsa::VerifyingKey::recover_from_prehash(&digest, &signature, recovery_id).unwrap();
+        assert_eq!(&recovered, key.verifying_key());
+    }
+}
@@ -0,0 +1,154 @@
+
+// --- Disk I/O Saturation ---
+//
+// IO_SATURATION_CHECK=true samples how busy the disk holding DATA_DIR is: the
+// kernel's per-device "time spent doing I/O" counter in /proc/diskstats, read
+// twice IO_SAMPLE_WINDOW apart. A node stuck on I/O keeps up with blocks but
+// answers RPC slowly, which the block comparison alone can't see.
+// DISK_WRITE_THROUGHPUT_WARN_MB_PER_SEC samples the same device's sectors
+// written, over WRITE_SAMPLE_WINDOW, to catch write pressure during a sync.
+
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use sysinfo::Disks;
+use tokio::sync::watch;
+
+const DISKSTATS_PATH: &str = "/proc/diskstats";
+
+/// Utilization is measured over this window
+pub const IO_SAMPLE_WINDOW: Duration = Duration::from_secs(10);
+
+/// Write throughput is measured over this window, so short bursts even out
+pub const WRITE_SAMPLE_WINDOW: Duration = Duration::from_secs(30);
+
+/// /proc/diskstats counts sectors of 512 bytes, whatever the device's sector size
+const SECTOR_BYTES: u64 = 512;
+
+/// The /proc/diskstats name (e.g. "nvme0n1p2" or "dm-0") of the disk mounted
+/// at the deepest mount point containing `path`
+pub fn device_for_path(path: &str) -> Option<String> {
+    let path = fs::canonicalize(path).ok()?;
+    let disks = Disks::new_with_refreshed_list();
+    let disk = disks.list().iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())?;
+    // /dev/mapper/* names are symlinks to the dm-N device the kernel reports
+    let device = Path::new(disk.name());
+    let device = fs::canonicalize(device).unwrap_or_else(|_| device.to_path_buf());
+    let name = device.file_name()?.to_str()?.to_string();
+    io_ticks(&fs::read_to_string(DISKSTATS_PATH).ok()?, &name).map(|_| name)
+}
+
+/// Milliseconds `device` has spent doing I/O since boot (field 13 of its line)
+fn io_ticks(diskstats: &str, device: &str) -> Option<u64> {
+    diskstats_field(diskstats, device, 12)
+}
+
+/// Sectors written to `device` since boot (field 10 of its line)
+fn sectors_written(diskstats: &str, device: &str) -> Option<u64> {
+    diskstats_field(diskstats, device, 9)
+}
+
+fn diskstats_field(diskstats: &str, device: &str, index: usize) -> Option<u64> {
+    diskstats.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .find(|fields| fields.get(2) == Some(&device))
+        .and_then(|fields| fields.get(index)?.parse().ok())
+}
+
+/// Share of `elapsed` the device was busy, in percent
+fn utilization_pct(ticks_before: u64, ticks_after: u64, elapsed: Duration) -> f64 {
+    let busy_ms = ticks_after.saturating_sub(ticks_before) as f64;
+    (100.0 * busy_ms / elapsed.as_millis().max(1) as f64).min(100.0)
+}
+
+/// Megabytes (10^6 bytes) written per second over `elapsed`
+fn write_mb_per_sec(sectors_before: u64, sectors_after: u64, elapsed: Duration) -> f64 {
+    let bytes = sectors_after.saturating_sub(sectors_before) * SECTOR_BYTES;
+    bytes as f64 / 1_000_000.0 / elapsed.as_secs_f64().max(0.001)
+}
+
+/// Spawns a task measuring the utilization of `device` every IO_SAMPLE_WINDOW;
+/// the receiver holds the latest value, None until the first window completes
+pub fn spawn_sampler(device: String) -> watch::Receiver<Option<f64>> {
+    spawn_counter_sampler(device, IO_SAMPLE_WINDOW, io_ticks, utilization_pct, crate::metrics::record_disk_io_utilization)
+}
+
+/// Like `spawn_sampler`, for the write throughput over WRITE_SAMPLE_WINDOW
+pub fn spawn_write_sampler(device: String) -> watch::Receiver<Option<f64>> {
+    spawn_counter_sampler(device, WRITE_SAMPLE_WINDOW, sectors_written, write_mb_per_sec, crate::metrics::record_disk_write_throughput)
+}
+
+/// Reads the counter of `device` every `window` and publishes the rate
+/// between two readings
+fn spawn_counter_sampler(
+    device: String,
+    window: Duration,
+    counter: fn(&str, &str) -> Option<u64>,
+    rate: fn(u64, u64, Duration) -> f64,
+    record: fn(f64),
+) -> watch::Receiver<Option<f64>> {
+    let (sender, receiver) = watch::channel(None);
+    tokio::spawn(async move {
+        let mut previous: Option<(Instant, u64)> = None;
+        loop {
+            match fs::read_to_string(DISKSTATS_PATH).ok().and_then(|stats| counter(&stats, &device)) {
+                Some(value) => {
+                    let now = Instant::now();
+                    if let Some((at, before)) = previous {
+                        let value = rate(before, value, now - at);
+                        record(value);
+                        if sender.send(Some(value)).is_err() {
+                            return;
+                        }
+                    }
+                    previous = Some((now, value));
+                }
+                None => {
+                    error!("[ERROR] Failed to read I/O statistics of {} from {}", device, DISKSTATS_PATH);
+                    previous = None;
+                }
+            }
+            tokio::time::sleep(window).await;
+        }
+    });
+    receiver
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DISKSTATS: &str = "\
+ 259       0 nvme0n1 1042761 297245 96691949 384197 3327359 2373810 242363414 4885802 0 1263844 5579405 0 0 0 0 203681 309405
+ 259       2 nvme0n1p2 1041198 297245 96653941 383851 3327359 2373810 242363414 4885802 0 1263472 5269654 0 0 0 0 0 0
+ 253       0 dm-0 1338135 0 96651370 779813 5701169 0 242363414 18925370 0 1300360 19705183 0 0 0 0 0 0";
+
+    #[test]
+    fn test_io_ticks() {
+        assert_eq!(io_ticks(DISKSTATS, "nvme0n1p2"), Some(1263472));
+        assert_eq!(io_ticks(DISKSTATS, "dm-0"), Some(1300360));
+        assert_eq!(io_ticks(DISKSTATS, "sda"), None);
+        assert_eq!(sectors_written(DISKSTATS, "nvme0n1p2"), Some(242363414));
+    }
+
+    #[test]
+    fn test_write_mb_per_sec() {
+        // 3,000,000 sectors of 512 bytes in 30s
+        assert_eq!(write_mb_per_sec(1_000_000, 4_000_000, Duration::from_secs(30)), 51.2);
+        assert_eq!(write_mb_per_sec(1_000, 1_000, Duration::from_secs(30)), 0.0);
+    }
+
+    #[test]
+    fn test_utilization_pct() {
+        let window = Duration::from_secs(10);
+        assert_eq!(utilization_pct(1_000, 10_000, window), 90.0);
+        assert_eq!(utilization_pct(1_000, 1_000, window), 0.0);
+        // Merged in-flight requests can count slightly more than the window
+        assert_eq!(utilization_pct(0, 10_050, window), 100.0);
+    }
+}
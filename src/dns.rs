@@ -0,0 +1,53 @@
+
+// --- DNS Resolution ---
+//
+// reqwest's default resolver runs getaddrinfo on a blocking thread, which can
+// hang for 30+ seconds on a misconfigured DNS server and eat the whole RPC
+// timeout. This resolver bounds the lookup with DNS_TIMEOUT_SECS instead.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use hickory_resolver::config::{ResolverConfig, ResolverOpts};
+use hickory_resolver::TokioAsyncResolver;
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+
+/// reqwest resolver with a hard deadline on each lookup
+pub struct TimeoutResolver {
+    resolver: Arc<TokioAsyncResolver>,
+    timeout: Duration,
+}
+
+impl TimeoutResolver {
+    /// Uses the system resolver configuration (/etc/resolv.conf), or public defaults if it can't be read
+    pub fn new(timeout: Duration) -> Self {
+        let (config, mut opts) = hickory_resolver::system_conf::read_system_conf()
+            .unwrap_or_else(|_| (ResolverConfig::default(), ResolverOpts::default()));
+        opts.timeout = timeout;
+        TimeoutResolver { resolver: Arc::new(TokioAsyncResolver::tokio(config, opts)), timeout }
+    }
+}
+
+impl Resolve for TimeoutResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.resolver.clone();
+        let timeout = self.timeout;
+        Box::pin(async move {
+            let host = name.as_str();
+            match tokio::time::timeout(timeout, resolver.lookup_ip(host)).await {
+                Ok(Ok(lookup)) => {
+                    // The port is filled in by the connector
+                    let addrs: Vec<SocketAddr> = lookup.iter().map(|ip| SocketAddr::new(ip, 0)).collect();
+                    Ok(Box::new(addrs.into_iter()) as Addrs)
+                }
+                Ok(Err(e)) => Err(e.into()),
+                Err(_) => {
+                    error!("[ERROR] DNS resolution timed out for {}", host);
+                    Err(format!("DNS resolution timed out for {}", host).into())
+                }
+            }
+        })
+    }
+}
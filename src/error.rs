@@ -0,0 +1,127 @@
+
+// --- Errors ---
+//
+// What went wrong talking to a node or a webhook. The main loop picks the
+// alert by variant: an unreachable node is down, while a node answering with
+// a JSON-RPC error is alive but unhealthy.
+
+#[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
+pub enum AppError {
+    /// The request failed or timed out, or the HTTP status was an error
+    HttpError(reqwest::Error),
+    /// The node answered with a JSON-RPC 'error' object
+    RpcError { code: i64, message: String },
+    /// The response wasn't the JSON (or hex quantity) expected
+    ParseError(String),
+    /// The client doesn't report a field a check reads
+    NotReported(String),
+    /// The 'newHeads' subscription on LOCAL_WS_URL is down (TRANSPORT_MODE=ws)
+    WsError(String),
+    /// The alert webhook couldn't be reached or rejected the alert
+    WebhookError(reqwest::Error),
+    /// The SMTP server couldn't be reached or rejected the alert
+    EmailError(String),
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AppError::HttpError(e) => write!(f, "{}", e),
+            AppError::RpcError { message, .. } => write!(f, "RPC Error: {}", message),
+            AppError::ParseError(message) => write!(f, "Invalid response: {}", message),
+            AppError::NotReported(field) => write!(f, "'{}' is not reported by this client", field),
+            AppError::WsError(message) => write!(f, "WebSocket: {}", message),
+            AppError::WebhookError(e) => write!(f, "Webhook: {}", e),
+            AppError::EmailError(message) => write!(f, "Email: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AppError::HttpError(e) | AppError::WebhookError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for AppError {
+    fn from(e: reqwest::Error) -> Self {
+        AppError::HttpError(e)
+    }
+}
+
+/// The helpers' own "Invalid response: ..." messages
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::ParseError(message.strip_prefix("Invalid response: ").unwrap_or(message).to_string())
+    }
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::from(message.as_str())
+    }
+}
+
+impl From<std::num::ParseIntError> for AppError {
+    fn from(e: std::num::ParseIntError) -> Self {
+        AppError::ParseError(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        AppError::ParseError(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::ParseError(e.to_string())
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for AppError {
+    fn from(e: jsonwebtoken::errors::Error) -> Self {
+        AppError::ParseError(e.to_string())
+    }
+}
+
+impl From<tokio_tungstenite::tungstenite::Error> for AppError {
+    fn from(e: tokio_tungstenite::tungstenite::Error) -> Self {
+        AppError::WsError(e.to_string())
+    }
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A reqwest error without any network access
+    fn reqwest_error() -> reqwest::Error {
+        reqwest::Client::new().get("not a url").build().unwrap_err()
+    }
+
+    #[test]
+    fn test_helper_errors_are_classified() {
+        assert!(matches!(AppError::from(reqwest_error()), AppError::HttpError(_)));
+        assert!(matches!(AppError::from("Invalid response: 'result' field missing"), AppError::ParseError(message) if message == "'result' field missing"));
+        assert!(matches!(AppError::from("0xzz".parse::<u64>().unwrap_err()), AppError::ParseError(_)));
+    }
+
+    #[test]
+    fn test_display() {
+        let rpc = AppError::RpcError { code: -32000, message: "header not found".to_string() };
+        assert_eq!(rpc.to_string(), "RPC Error: header not found");
+        assert_eq!(AppError::ParseError("'result' field missing".to_string()).to_string(), "Invalid response: 'result' field missing");
+        assert_eq!(AppError::NotReported("rpc.http.connections".to_string()).to_string(), "'rpc.http.connections' is not reported by this client");
+        assert_eq!(AppError::WsError("no newHeads".to_string()).to_string(), "WebSocket: no newHeads");
+        assert!(AppError::WebhookError(reqwest_error()).to_string().starts_with("Webhook: "));
+        assert!(std::error::Error::source(&AppError::HttpError(reqwest_error())).is_some());
+    }
+}
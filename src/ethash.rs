@@ -0,0 +1,163 @@
+
+// --- Ethash Seal ---
+//
+// The cheap half of Ethash verification: given the header's mixHash and nonce,
+// the final hash can be recomputed without the DAG and compared against the
+// target 2^256 / difficulty. A mismatched mixHash itself would need the full
+// DAG and is not checked.
+
+use serde_json::Value;
+use sha3::{Digest, Keccak256, Keccak512};
+
+use crate::rlp::{rlp_bytes, rlp_list};
+use crate::rpc::{parse_hex_bytes, RpcResult};
+
+/// Header fields hashed into the seal, in RLP order (mixHash and nonce excluded)
+const SEAL_FIELDS: [&str; 13] = [
+    "parentHash", "sha3Uncles", "miner", "stateRoot", "transactionsRoot", "receiptsRoot", "logsBloom",
+    "difficulty", "number", "gasLimit", "gasUsed", "timestamp", "extraData",
+];
+
+fn hex_bytes(block: &Value, field: &str) -> RpcResult<Vec<u8>> {
+    let hex = block.get(field).and_then(|v| v.as_str())
+        .ok_or_else(|| format!("Invalid response: '{}' field missing", field))?;
+    let bytes = parse_hex_bytes(hex)?;
+    // RLP integers are minimal big-endian, so 0x0 is the empty string
+    let start = if is_quantity(field) { bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len()) } else { 0 };
+    Ok(bytes[start..].to_vec())
+}
+
+fn is_quantity(field: &str) -> bool {
+    matches!(field, "difficulty" | "number" | "gasLimit" | "gasUsed" | "timestamp" | "baseFeePerGas")
+}
+
+/// Keccak-256 of the header without mixHash and nonce
+fn seal_hash(block: &Value) -> RpcResult<[u8; 32]> {
+    let mut fields = SEAL_FIELDS.iter().map(|field| hex_bytes(block, field).map(|b| rlp_bytes(&b))).collect::<RpcResult<Vec<_>>>()?;
+    // London added the base fee to the header
+    if block.get("baseFeePerGas").is_some() {
+        fields.push(rlp_bytes(&hex_bytes(block, "baseFeePerGas")?));
+    }
+    Ok(Keccak256::digest(rlp_list(&fields)).into())
+}
+
+/// The block's difficulty and nonce, or None on PoS (difficulty 0, nonce 0)
+pub fn pow_seal(block: &Value) -> RpcResult<Option<(u128, u64)>> {
+    let difficulty = hex_bytes(block, "difficulty")?;
+    if difficulty.len() > 16 {
+        return Err("Invalid response: difficulty exceeds 128 bits".into());
+    }
+    let difficulty = difficulty.iter().fold(0u128, |acc, b| (acc << 8) | *b as u128);
+    let nonce = hex_bytes(block, "nonce")?.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64);
+    Ok((difficulty != 0 && nonce != 0).then_some((difficulty, nonce)))
+}
+
+/// Whether the nonce meets the block's difficulty
+pub fn seal_is_valid(block: &Value, difficulty: u128, nonce: u64) -> RpcResult<bool> {
+    let mix_hash = hex_bytes(block, "mixHash")?;
+    let mut seed_input = seal_hash(block)?.to_vec();
+    seed_input.extend_from_slice(&nonce.to_le_bytes());
+    let seed = Keccak512::digest(&seed_input);
+    let result = Keccak256::digest([seed.as_slice(), &mix_hash].concat());
+    Ok(meets_target(result.into(), difficulty))
+}
+
+/// `hash <= 2^256 / difficulty`, checked as `hash * difficulty <= 2^256`
+fn meets_target(hash: [u8; 32], difficulty: u128) -> bool {
+    if difficulty <= 1 {
+        return true;
+    }
+    // 256-bit hash times 128-bit difficulty in 64-bit limbs, least significant first
+    let limbs: Vec<u64> = hash.chunks(8).rev().map(|c| u64::from_be_bytes(c.try_into().expect("8-byte chunk"))).collect();
+    let diff = [difficulty as u64, (difficulty >> 64) as u64];
+    let mut product = [0u64; 6];
+    for (i, limb) in limbs.iter().enumerate() {
+        let mut carry = 0u128;
+        for (j, d) in diff.iter().enumerate() {
+            let sum = product[i + j] as u128 + (*limb as u128) * (*d as u128) + carry;
+            product[i + j] = sum as u64;
+            carry = sum >> 64;
+        }
+        let mut k = i + diff.len();
+        while carry != 0 {
+            let sum = product[k] as u128 + carry;
+            product[k] = sum as u64;
+            carry = sum >> 64;
+            k += 1;
+        }
+    }
+    // 2^256 is limb 4 == 1 with everything below zero
+    match (product[5], product[4]) {
+        (0, 0) => true,
+        (0, 1) => product[..4].iter().all(|limb| *limb == 0),
+        _ => false,
+    }
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mainnet block 1
+    fn block_one() -> Value {
+        serde_json::json!({
+            "hash": "0x88e96d4537bea4d9c05d12549907b32561d3bf31f45aae734cdc119f13406cb6",
+            "parentHash": "0xd4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3",
+            "sha3Uncles": "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347",
+            "miner": "0x05a56e2d52c817161883f50c441c3228cfe54d9f",
+            "stateRoot": "0xd67e4d450343046425ae4271474353857ab860dbc0a1dde64b41b5cd3a532bf3",
+            "transactionsRoot": "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+            "receiptsRoot": "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+            "logsBloom": format!("0x{}", "0".repeat(512)),
+            "difficulty": "0x3ff800000",
+            "number": "0x1",
+            "gasLimit": "0x1388",
+            "gasUsed": "0x0",
+            "timestamp": "0x55ba4224",
+            "extraData": "0x476574682f76312e302e302f6c696e75782f676f312e342e32",
+            "mixHash": "0x969b900de27b6ac6a67742365dd65f55a0526c41fd18e1b16f1a1215c2e66f59",
+            "nonce": "0x539bd4979fef1ec4"
+        })
+    }
+
+    #[test]
+    fn test_header_encoding_matches_block_hash() {
+        let block = block_one();
+        let mut fields: Vec<Vec<u8>> = SEAL_FIELDS.iter().map(|field| rlp_bytes(&hex_bytes(&block, field).unwrap())).collect();
+        fields.push(rlp_bytes(&hex_bytes(&block, "mixHash").unwrap()));
+        fields.push(rlp_bytes(&hex_bytes(&block, "nonce").unwrap()));
+        let hash = Keccak256::digest(rlp_list(&fields));
+        assert_eq!(hash.to_vec(), hex_bytes(&block, "hash").unwrap());
+    }
+
+    #[test]
+    fn test_seal_validation() {
+        let block = block_one();
+        let (difficulty, nonce) = pow_seal(&block).unwrap().unwrap();
+        assert_eq!(difficulty, 0x3ff800000);
+        assert!(seal_is_valid(&block, difficulty, nonce).unwrap());
+        assert!(!seal_is_valid(&block, difficulty, nonce + 1).unwrap());
+    }
+
+    #[test]
+    fn test_pos_blocks_have_no_seal() {
+        let mut block = block_one();
+        block["difficulty"] = "0x0".into();
+        block["nonce"] = "0x0000000000000000".into();
+        assert_eq!(pow_seal(&block).unwrap(), None);
+    }
+
+    #[test]
+    fn test_meets_target() {
+        let mut hash = [0u8; 32];
+        hash[0] = 0x80;
+        // 2^255 * 2 == 2^256 is exactly on the target, one more is over it
+        assert!(meets_target(hash, 2));
+        assert!(!meets_target(hash, 3));
+        hash[31] = 1;
+        assert!(!meets_target(hash, 2));
+        assert!(meets_target([0xff; 32], 1));
+    }
+}
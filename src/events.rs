@@ -0,0 +1,345 @@
+
+// --- Event Bus ---
+//
+// The single-node poll loop only judges what it sees and publishes the result
+// as a `MonitoringEvent` on a broadcast channel. Logging, alert delivery,
+// metrics, the state file and the snapshot outputs each run as a handler task
+// with their own subscription, so a slow webhook never holds up the next poll.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+use crate::notify::{AlertSeverity, AlertState, Notifier};
+use crate::redis_status::RedisStatus;
+use crate::snapshot::Snapshot;
+use crate::state::State;
+
+/// Events a handler may fall behind by before it starts missing some
+const EVENT_CAPACITY: usize = 64;
+
+/// What one poll found out about the local node
+#[derive(Clone, Debug)]
+pub enum MonitoringEvent {
+    /// Every poll: both heads, None where the fetch failed
+    BlockFetched { local: Option<u64>, remote: Option<u64> },
+    /// LAG_THRESHOLD or more blocks behind the remote
+    LagDetected { local: u64, remote: u64, lag: u64 },
+    /// More than LOCAL_AHEAD_ALERT_THRESHOLD blocks ahead of the remote
+    LocalAhead { local: u64, remote: u64, lead: u64 },
+    /// Unreachable, or answering with a JSON-RPC error
+    NodeDown { remote: u64, cause: DownCause },
+    /// Back within LAG_THRESHOLD after one of the events above
+    Recovered { local: u64, remote: u64 },
+}
+
+#[derive(Clone, Debug)]
+pub enum DownCause {
+    RpcError { code: i64, message: String },
+    Unreachable(String),
+}
+
+impl DownCause {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DownCause::RpcError { .. } => "local_node_rpc_error",
+            DownCause::Unreachable(_) => "local_node_down",
+        }
+    }
+
+    fn severity(&self) -> AlertSeverity {
+        match self {
+            DownCause::RpcError { .. } => AlertSeverity::Warn,
+            DownCause::Unreachable(_) => AlertSeverity::Critical,
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            DownCause::RpcError { code, message } => tr!("local_node_rpc_error", code = code, message = message),
+            DownCause::Unreachable(error) => tr!("local_node_down", error = error),
+        }
+    }
+}
+
+/// How the poll loop treats a lag behind the remote
+#[derive(Debug, PartialEq)]
+pub enum LagVerdict {
+    /// Initial sync: progress is logged, nothing alerts
+    Syncing,
+    Synced,
+    Lagging,
+    /// LAG_EXIT_THRESHOLD reached: alert and exit
+    Exit,
+}
+
+/// A node in initial sync is expected to be far behind, so it neither alerts
+/// nor exits, however large the lag
+pub fn lag_verdict(lag: u64, syncing: bool, lag_threshold: u64, exit_threshold: Option<u64>) -> LagVerdict {
+    if syncing {
+        LagVerdict::Syncing
+    } else if exit_threshold.is_some_and(|exit_lag| lag >= exit_lag) {
+        LagVerdict::Exit
+    } else if lag < lag_threshold {
+        LagVerdict::Synced
+    } else {
+        LagVerdict::Lagging
+    }
+}
+
+/// The sending side, plus the handler tasks so shutdown can wait for them
+pub struct EventBus {
+    sender: broadcast::Sender<MonitoringEvent>,
+    handlers: Vec<JoinHandle<()>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CAPACITY);
+        EventBus { sender, handlers: Vec::new() }
+    }
+
+    /// Starts a handler with a subscription of its own; it sees the events
+    /// emitted from now on
+    pub fn spawn_handler<F, Fut>(&mut self, name: &'static str, handler: F)
+    where
+        F: FnOnce(Events) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let events = Events { name, receiver: self.sender.subscribe() };
+        self.handlers.push(tokio::spawn(handler(events)));
+    }
+
+    pub fn emit(&self, event: MonitoringEvent) {
+        // Only fails without handlers, and then nobody is interested
+        let _ = self.sender.send(event);
+    }
+
+    /// Closes the channel and waits until every handler has worked through
+    /// the events still queued (e.g. an alert being delivered)
+    pub async fn close(self) {
+        drop(self.sender);
+        for handler in self.handlers {
+            let _ = handler.await;
+        }
+    }
+}
+
+/// One handler's subscription
+pub struct Events {
+    name: &'static str,
+    receiver: broadcast::Receiver<MonitoringEvent>,
+}
+
+impl Events {
+    /// The next event, or None once the bus is closed
+    pub async fn next(&mut self) -> Option<MonitoringEvent> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) => return Some(event),
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!("[WARN] Event handler '{}' fell behind and missed {} events", self.name, missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+// --- Handlers ---
+
+/// Prints the lag, fork and outage lines
+pub async fn log_events(mut events: Events) {
+    while let Some(event) = events.next().await {
+        match event {
+            MonitoringEvent::BlockFetched { .. } => {}
+            MonitoringEvent::LagDetected { local, remote, lag } => {
+                warn!(local_block = local, remote_block = remote, lag = lag; "{}", tr!("node_lagging", local = local, remote = remote, lag = lag));
+            }
+            MonitoringEvent::LocalAhead { local, remote, lead } => {
+                error!(local_block = local, remote_block = remote; "{}", tr!("local_ahead", lead = lead, local = local, remote = remote));
+            }
+            MonitoringEvent::NodeDown { remote, cause } => match &cause {
+                DownCause::RpcError { message, .. } => warn!(remote_block = remote, error = message.clone(); "{}", cause.message()),
+                DownCause::Unreachable(error) => error!(remote_block = remote, error = error.clone(); "{}", cause.message()),
+            },
+            MonitoringEvent::Recovered { local, remote } => {
+                info!(local_block = local, remote_block = remote; "[RECOVERED] Local node back in sync | Block: {}", local);
+            }
+        }
+    }
+}
+
+/// Sends the alerts, all under one cooldown. After a restart during an
+/// incident the cooldown starts right away, as the status update already
+/// reported it
+pub async fn alert_events(mut events: Events, notifier: Arc<Notifier>, resumed_incident: bool) {
+    let mut alert_state = AlertState { last_alert_time: resumed_incident.then(chrono::Utc::now), ..AlertState::default() };
+    let mut last_alert_lag: Option<u64> = None;
+    while let Some(event) = events.next().await {
+        match event {
+            MonitoringEvent::BlockFetched { .. } => {}
+            MonitoringEvent::LagDetected { local, remote, lag } => {
+                let mut msg = tr!("node_lagging", local = local, remote = remote, lag = lag);
+                if let Some(trend) = lag_trend(last_alert_lag, lag) {
+                    msg = format!("{} {}", msg, trend);
+                }
+                if notifier.process_alert(AlertSeverity::Warn, "node_lagging", &msg, &mut alert_state).await {
+                    last_alert_lag = Some(lag);
+                }
+            }
+            MonitoringEvent::LocalAhead { local, remote, lead } => {
+                let msg = tr!("local_ahead", lead = lead, local = local, remote = remote);
+                notifier.process_alert(AlertSeverity::Critical, "local_ahead", &msg, &mut alert_state).await;
+            }
+            MonitoringEvent::NodeDown { cause, .. } => {
+                notifier.process_alert(cause.severity(), cause.kind(), &cause.message(), &mut alert_state).await;
+                last_alert_lag = None;
+            }
+            MonitoringEvent::Recovered { .. } => {
+                alert_state.last_alert_time = None;
+                last_alert_lag = None;
+            }
+        }
+    }
+}
+
+/// Describes how the lag moved since the previous lag alert
+fn lag_trend(previous: Option<u64>, lag: u64) -> Option<String> {
+    match previous {
+        Some(previous) if lag < previous => Some(tr!("lag_improving", previous = previous)),
+        Some(previous) if lag > previous => Some(tr!("lag_worsening", previous = previous)),
+        _ => None,
+    }
+}
+
+/// Updates the block gauges
+pub async fn metrics_events(mut events: Events) {
+    while let Some(event) = events.next().await {
+        if let MonitoringEvent::BlockFetched { local, remote } = event {
+            crate::metrics::record_blocks(local, remote);
+        }
+    }
+}
+
+/// Keeps the open incident in STATE_FILE current, so a restart during an
+/// incident follows it up instead of alerting from scratch
+pub async fn state_events(mut events: Events, mut state: State, state_file: String) {
+    while let Some(event) = events.next().await {
+        let kind = match &event {
+            MonitoringEvent::BlockFetched { .. } => continue,
+            MonitoringEvent::LagDetected { .. } => Some("node_lagging"),
+            MonitoringEvent::LocalAhead { .. } => Some("local_ahead"),
+            MonitoringEvent::NodeDown { cause, .. } => Some(cause.kind()),
+            MonitoringEvent::Recovered { .. } => None,
+        };
+        if state.track_incident(kind, Utc::now()) && let Err(e) = state.save(&state_file) {
+            error!("[ERROR] Failed to write state file '{}': {}", state_file, e);
+        }
+    }
+}
+
+/// Writes SNAPSHOT_FILE and publishes to Redis whenever both heads are known
+pub async fn snapshot_events(mut events: Events, snapshot_file: Option<String>, redis: Option<RedisStatus>, lag_threshold: u64, weekend_multiplier: f64, ahead_threshold: u64) {
+    while let Some(event) = events.next().await {
+        if let MonitoringEvent::BlockFetched { local: Some(local), remote: Some(remote) } = event {
+            let snapshot = || poll_snapshot(local, remote, lag_threshold, weekend_multiplier, ahead_threshold, Utc::now());
+            if let Some(path) = &snapshot_file {
+                snapshot().write(path);
+            }
+            if let Some(redis) = &redis {
+                redis.publish(snapshot());
+            }
+        }
+    }
+}
+
+
+/// The snapshot of a poll at `now`, healthy by the lag threshold in effect then
+fn poll_snapshot(local: u64, remote: u64, lag_threshold: u64, weekend_multiplier: f64, ahead_threshold: u64, now: DateTime<Utc>) -> Snapshot {
+    Snapshot::new(local, remote, crate::config::lag_threshold_at(lag_threshold, weekend_multiplier, now), ahead_threshold)
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_lag_trend() {
+        assert_eq!(lag_trend(Some(8), 5).as_deref(), Some("(improving: was 8 blocks)"));
+        assert_eq!(lag_trend(Some(5), 8).as_deref(), Some("(worsening: was 5 blocks)"));
+        assert_eq!(lag_trend(Some(5), 5), None);
+        assert_eq!(lag_trend(None, 5), None);
+    }
+
+    #[test]
+    fn test_lag_verdict() {
+        assert_eq!(lag_verdict(1, false, 3, Some(100)), LagVerdict::Synced);
+        assert_eq!(lag_verdict(5, false, 3, Some(100)), LagVerdict::Lagging);
+        assert_eq!(lag_verdict(100, false, 3, Some(100)), LagVerdict::Exit);
+        assert_eq!(lag_verdict(100, false, 3, None), LagVerdict::Lagging);
+        // A planned resync neither pages nor gets restarted by the supervisor
+        assert_eq!(lag_verdict(5_000_000, true, 3, Some(100)), LagVerdict::Syncing);
+    }
+
+    #[test]
+    fn test_snapshot_health_follows_weekend_threshold() {
+        let friday = DateTime::parse_from_rfc3339("2024-05-03T12:00:00Z").unwrap().with_timezone(&Utc);
+        let saturday = DateTime::parse_from_rfc3339("2024-05-04T12:00:00Z").unwrap().with_timezone(&Utc);
+        assert!(!poll_snapshot(100, 104, 3, 2.0, 10, friday).healthy);
+        assert!(poll_snapshot(100, 104, 3, 2.0, 10, saturday).healthy);
+        assert!(!poll_snapshot(100, 104, 3, 1.0, 10, saturday).healthy);
+    }
+
+    #[tokio::test]
+    async fn test_state_handler_tracks_the_incident() {
+        let path = std::env::temp_dir().join(format!("eth-alive-state-events-{}.json", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+        let mut bus = EventBus::new();
+        let state_file = path.clone();
+        bus.spawn_handler("state", move |events| state_events(events, State::default(), state_file));
+        bus.emit(MonitoringEvent::LagDetected { local: 90, remote: 100, lag: 10 });
+        bus.emit(MonitoringEvent::NodeDown { remote: 100, cause: DownCause::Unreachable("connection refused".to_string()) });
+        bus.close().await;
+        let incident = State::load(&path).unwrap().incident.unwrap();
+        assert_eq!(incident.kind, "local_node_down");
+
+        let mut bus = EventBus::new();
+        let state_file = path.clone();
+        let state = State::load(&path).unwrap();
+        bus.spawn_handler("state", move |events| state_events(events, state, state_file));
+        bus.emit(MonitoringEvent::Recovered { local: 100, remote: 100 });
+        bus.close().await;
+        assert!(State::load(&path).unwrap().incident.is_none());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_every_handler_sees_every_event() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut bus = EventBus::new();
+        for name in ["first", "second"] {
+            let seen = seen.clone();
+            bus.spawn_handler(name, move |mut events| async move {
+                while let Some(event) = events.next().await {
+                    if let MonitoringEvent::BlockFetched { local: Some(local), .. } = event {
+                        seen.lock().unwrap().push((name, local));
+                    }
+                }
+            });
+        }
+        bus.emit(MonitoringEvent::BlockFetched { local: Some(1), remote: Some(1) });
+        bus.emit(MonitoringEvent::BlockFetched { local: Some(2), remote: None });
+        bus.close().await;
+
+        let mut seen = seen.lock().unwrap().clone();
+        seen.sort();
+        assert_eq!(seen, vec![("first", 1), ("first", 2), ("second", 1), ("second", 2)]);
+    }
+}
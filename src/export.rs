@@ -0,0 +1,79 @@
+
+// --- CSV Export ---
+//
+// One row per poll (EXPORT_CSV_PATH) for post-incident analysis in a
+// spreadsheet. The file is only ever appended to, so restarts keep history.
+
+use chrono::{DateTime, Utc};
+use tokio::io::AsyncWriteExt;
+
+/// Column names, printed by `--export-csv-header`
+pub const CSV_HEADER: &str = "timestamp,local_block,remote_block,lag,local_latency_ms,remote_latency_ms,local_up,remote_up";
+
+pub struct CsvRow {
+    pub timestamp: DateTime<Utc>,
+    pub local_block: Option<u64>,
+    pub remote_block: Option<u64>,
+    pub local_latency_ms: u64,
+    pub remote_latency_ms: u64,
+}
+
+impl CsvRow {
+    /// Unknown blocks (and the lag they'd give) are left empty
+    fn to_line(&self) -> String {
+        let lag = match (self.local_block, self.remote_block) {
+            (Some(local), Some(remote)) => Some(remote.saturating_sub(local)),
+            _ => None,
+        };
+        format!(
+            "{},{},{},{},{},{},{},{}\n",
+            self.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            optional(self.local_block),
+            optional(self.remote_block),
+            optional(lag),
+            self.local_latency_ms,
+            self.remote_latency_ms,
+            self.local_block.is_some(),
+            self.remote_block.is_some(),
+        )
+    }
+
+    /// Appends the row to `path`, creating the file if needed and logging on failure
+    pub async fn append(&self, path: &str) {
+        let result = async {
+            let mut file = tokio::fs::OpenOptions::new().create(true).append(true).open(path).await?;
+            file.write_all(self.to_line().as_bytes()).await
+        }.await;
+        if let Err(e) = result {
+            error!("[ERROR] Failed to append to CSV export '{}': {}", path, e);
+        }
+    }
+}
+
+fn optional(value: Option<u64>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_csv_row_matches_header() {
+        let row = CsvRow {
+            timestamp: Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(),
+            local_block: Some(100),
+            remote_block: Some(103),
+            local_latency_ms: 12,
+            remote_latency_ms: 85,
+        };
+        assert_eq!(row.to_line(), "2024-05-01T12:00:00Z,100,103,3,12,85,true,true\n");
+        assert_eq!(row.to_line().split(',').count(), CSV_HEADER.split(',').count());
+
+        let down = CsvRow { local_block: None, ..row };
+        assert_eq!(down.to_line(), "2024-05-01T12:00:00Z,,103,,12,85,false,true\n");
+    }
+}
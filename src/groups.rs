@@ -0,0 +1,140 @@
+
+// --- Node Groups ---
+//
+// A group is a set of local nodes that are judged together: individual node
+// problems are only logged, and an alert fires once fewer than `min_healthy`
+// nodes in the group are healthy.
+
+use std::time::Duration;
+
+use futures::future::join_all;
+use serde::Deserialize;
+
+use crate::checks::CheckOutcome;
+use crate::notify::AlertState;
+use crate::redact_url;
+use crate::rpc::fetch_block_number;
+
+/// A group definition as read from the GROUPS environment variable
+#[derive(Debug, Deserialize)]
+pub struct GroupConfig {
+    pub name: String,
+    pub nodes: Vec<String>,
+    /// Defaults to every node in the group
+    pub min_healthy: Option<usize>,
+}
+
+impl GroupConfig {
+    pub fn min_healthy(&self) -> usize {
+        self.min_healthy.unwrap_or(self.nodes.len())
+    }
+}
+
+/// Parses the GROUPS JSON array and validates each group
+pub fn parse_groups(input: &str) -> Result<Vec<GroupConfig>, String> {
+    let groups: Vec<GroupConfig> = serde_json::from_str(input).map_err(|e| e.to_string())?;
+
+    for group in &groups {
+        if group.nodes.is_empty() {
+            return Err(format!("group '{}' has no nodes", group.name));
+        }
+        if group.min_healthy() > group.nodes.len() {
+            return Err(format!(
+                "group '{}' requires {} healthy nodes but only has {}",
+                group.name, group.min_healthy(), group.nodes.len()
+            ));
+        }
+    }
+
+    Ok(groups)
+}
+
+/// A group together with its alert cooldown state
+pub struct GroupState {
+    pub config: GroupConfig,
+    pub alert_state: AlertState,
+}
+
+/// How many nodes are polled at once (FLEET_BATCH_SIZE), and the pause
+/// between batches (FLEET_BATCH_DELAY_MS), to stay under RPC rate limits
+pub struct Batching {
+    pub size: usize,
+    pub delay: Duration,
+}
+
+/// Polls the nodes of the group concurrently, one batch at a time, and logs
+/// the unhealthy ones. Returns the group-level outcome.
+pub async fn poll_group(client: &reqwest::Client, group: &GroupConfig, remote: u64, lag_threshold: u64, batching: &Batching) -> CheckOutcome {
+    let batches = group.nodes.len().div_ceil(batching.size);
+    let mut results = Vec::with_capacity(group.nodes.len());
+    for (batch, nodes) in group.nodes.chunks(batching.size).enumerate() {
+        if batch > 0 {
+            tokio::time::sleep(batching.delay).await;
+        }
+        debug!("[DEBUG] Group '{}' | Polling batch {}/{} ({} nodes)", group.name, batch + 1, batches, nodes.len());
+        results.extend(join_all(nodes.iter().map(|url| fetch_block_number(client, url))).await);
+    }
+
+    let mut healthy = 0;
+    for (url, result) in group.nodes.iter().zip(results) {
+        match result {
+            Ok(local) if remote.saturating_sub(local) < lag_threshold => healthy += 1,
+            Ok(local) => warn!(
+                "[WARN] Group '{}' | Node {} lagging | Local: {} | Remote: {} | Lag: {}",
+                group.name, redact_url(url), local, remote, remote - local
+            ),
+            Err(e) => warn!("[WARN] Group '{}' | Node {} down | Error: {}", group.name, redact_url(url), e),
+        }
+    }
+
+    group_outcome(&group.name, healthy, group.nodes.len(), group.min_healthy())
+}
+
+fn group_outcome(name: &str, healthy: usize, total: usize, min_healthy: usize) -> CheckOutcome {
+    if healthy < min_healthy {
+        CheckOutcome::Alert(tr!("group_degraded", group = name, healthy = healthy, total = total, min = min_healthy))
+    } else {
+        CheckOutcome::Ok(format!("[OK] Group '{}' | Healthy: {}/{}", name, healthy, total))
+    }
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_groups() {
+        let input = r#"[{"name": "validators", "nodes": ["http://a:8545", "http://b:8545"], "min_healthy": 1}]"#;
+        let groups = parse_groups(input).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].name, "validators");
+        assert_eq!(groups[0].min_healthy(), 1);
+    }
+
+    #[test]
+    fn test_min_healthy_defaults_to_all_nodes() {
+        let input = r#"[{"name": "rpc", "nodes": ["http://a:8545", "http://b:8545", "http://c:8545"]}]"#;
+        let groups = parse_groups(input).unwrap();
+        assert_eq!(groups[0].min_healthy(), 3);
+    }
+
+    #[test]
+    fn test_min_healthy_larger_than_group_is_rejected() {
+        let input = r#"[{"name": "rpc", "nodes": ["http://a:8545"], "min_healthy": 2}]"#;
+        assert!(parse_groups(input).is_err());
+    }
+
+    #[test]
+    fn test_empty_group_is_rejected() {
+        let input = r#"[{"name": "rpc", "nodes": []}]"#;
+        assert!(parse_groups(input).is_err());
+    }
+
+    #[test]
+    fn test_group_alerts_below_min_healthy() {
+        assert!(matches!(group_outcome("v", 3, 5, 4), CheckOutcome::Alert(_)));
+        assert!(matches!(group_outcome("v", 4, 5, 4), CheckOutcome::Ok(_)));
+    }
+}
@@ -0,0 +1,145 @@
+
+// --- Response Header Watch ---
+//
+// RPC proxies often report rate limits or backend versions in response
+// headers (X-RateLimit-Remaining, X-Node-Version, ...). Every JSON-RPC
+// response passes through `observe`: watched headers are logged at debug
+// level, and headers matching an alert rule produce a warning.
+
+use std::sync::OnceLock;
+use reqwest::header::HeaderMap;
+
+use crate::redact_url;
+
+/// Comparison in a HEADER_ALERT_PATTERN rule
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl std::fmt::Display for Op {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let symbol = match self {
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Eq => "==",
+            Op::Ne => "!=",
+        };
+        f.write_str(symbol)
+    }
+}
+
+/// One rule, e.g. `X-RateLimit-Remaining < 100`
+#[derive(Debug, PartialEq)]
+pub struct HeaderRule {
+    header: String,
+    op: Op,
+    value: String,
+}
+
+impl HeaderRule {
+    /// Numeric comparison when both sides are numbers, otherwise only == and != apply
+    fn matches(&self, actual: &str) -> bool {
+        match (actual.trim().parse::<f64>(), self.value.parse::<f64>()) {
+            (Ok(actual), Ok(limit)) => match self.op {
+                Op::Lt => actual < limit,
+                Op::Le => actual <= limit,
+                Op::Gt => actual > limit,
+                Op::Ge => actual >= limit,
+                Op::Eq => actual == limit,
+                Op::Ne => actual != limit,
+            },
+            _ => match self.op {
+                Op::Eq => actual.trim() == self.value,
+                Op::Ne => actual.trim() != self.value,
+                _ => false,
+            },
+        }
+    }
+}
+
+/// Parses comma-separated rules like `X-RateLimit-Remaining < 100, X-Node-Version != 1.2`
+pub fn parse_rules(raw: &str) -> Result<Vec<HeaderRule>, String> {
+    // Two-character operators first so '<=' isn't read as '<'
+    const OPS: [(&str, Op); 6] = [("<=", Op::Le), (">=", Op::Ge), ("==", Op::Eq), ("!=", Op::Ne), ("<", Op::Lt), (">", Op::Gt)];
+
+    raw.split(',').map(str::trim).filter(|rule| !rule.is_empty()).map(|rule| {
+        let (pos, symbol, op) = OPS.iter()
+            .filter_map(|(symbol, op)| rule.find(symbol).map(|pos| (pos, *symbol, *op)))
+            .min_by_key(|(pos, symbol, _)| (*pos, usize::MAX - symbol.len()))
+            .ok_or_else(|| format!("rule '{}' has no comparison operator", rule))?;
+        let header = rule[..pos].trim();
+        let value = rule[pos + symbol.len()..].trim();
+        if header.is_empty() || value.is_empty() {
+            return Err(format!("rule '{}' needs a header name and a value", rule));
+        }
+        Ok(HeaderRule { header: header.to_string(), op, value: value.to_string() })
+    }).collect()
+}
+
+struct HeaderWatch {
+    names: Vec<String>,
+    rules: Vec<HeaderRule>,
+}
+
+static WATCH: OnceLock<HeaderWatch> = OnceLock::new();
+
+/// Enables header watching for all further RPC responses
+pub fn init(names: Vec<String>, rules: Vec<HeaderRule>) {
+    WATCH.set(HeaderWatch { names, rules }).ok();
+}
+
+/// Logs watched headers of a response and warns about those matching a rule
+pub fn observe(url: &str, headers: &HeaderMap) {
+    let Some(watch) = WATCH.get() else {
+        return;
+    };
+
+    for name in &watch.names {
+        if let Some(value) = headers.get(name.as_str()).and_then(|v| v.to_str().ok()) {
+            debug!("[DEBUG] {} header {}: {}", redact_url(url), name, value);
+        }
+    }
+    for rule in &watch.rules {
+        if let Some(value) = headers.get(rule.header.as_str()).and_then(|v| v.to_str().ok())
+            && rule.matches(value)
+        {
+            warn!("[WARN] {} header {}: {} (alert rule: {} {})", redact_url(url), rule.header, value, rule.op, rule.value);
+        }
+    }
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rules() {
+        let rules = parse_rules("X-RateLimit-Remaining < 100, X-Node-Version != 1.2.0,X-Load>=0.9").unwrap();
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules[0], HeaderRule { header: "X-RateLimit-Remaining".to_string(), op: Op::Lt, value: "100".to_string() });
+        assert_eq!(rules[2].op, Op::Ge);
+        assert!(parse_rules("X-RateLimit-Remaining 100").is_err());
+        assert!(parse_rules("< 100").is_err());
+    }
+
+    #[test]
+    fn test_rule_matches() {
+        let rules = parse_rules("X-RateLimit-Remaining < 100, X-Node-Version != 1.2.0").unwrap();
+        assert!(rules[0].matches("42"));
+        assert!(!rules[0].matches("100"));
+        assert!(rules[1].matches("1.1.9"));
+        assert!(!rules[1].matches("1.2.0"));
+        // Non-numeric values never satisfy an ordering rule
+        assert!(!rules[0].matches("unlimited"));
+    }
+}
@@ -0,0 +1,36 @@
+
+// --- Shell Hooks ---
+//
+// Operator-supplied shell commands run on node state transitions
+// (ON_DOWN_EXEC / ON_RECOVERY_EXEC). They run in the background so a slow
+// command never delays the poll loop.
+
+use tokio::process::Command;
+
+/// Runs `command` through `sh -c` in the background and logs its output
+pub fn spawn_hook(name: &'static str, command: &str) {
+    info!("[INFO] Running {}: {}", name, command);
+    let mut child = Command::new("sh");
+    child.arg("-c").arg(command);
+
+    tokio::spawn(async move {
+        match child.output().await {
+            Ok(output) => {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                for line in stdout.lines() {
+                    info!("[INFO] {} stdout: {}", name, line);
+                }
+                for line in stderr.lines() {
+                    warn!("[WARN] {} stderr: {}", name, line);
+                }
+                if output.status.success() {
+                    info!("[INFO] {} finished: {}", name, output.status);
+                } else {
+                    error!("[ERROR] {} failed: {}", name, output.status);
+                }
+            }
+            Err(e) => error!("[ERROR] Failed to run {}: {}", name, e),
+        }
+    });
+}
@@ -0,0 +1,100 @@
+
+// --- Alert Translations ---
+//
+// Alert templates live in translations/<locale>.json and are embedded at
+// build time. Placeholders use the `{name}` syntax. Any key missing from the
+// selected locale falls back to English.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+type Templates = HashMap<String, String>;
+
+const ENGLISH: &str = include_str!("../translations/en.json");
+
+/// Supported locales and their embedded template files
+const LOCALES: &[(&str, &str)] = &[
+    ("en", ENGLISH),
+    ("es", include_str!("../translations/es.json")),
+    ("ja", include_str!("../translations/ja.json")),
+    ("zh", include_str!("../translations/zh.json")),
+];
+
+struct Translations {
+    selected: Templates,
+    fallback: Templates,
+}
+
+impl Translations {
+    fn lookup(&self, key: &str) -> Option<&String> {
+        self.selected.get(key).or_else(|| self.fallback.get(key))
+    }
+}
+
+static TRANSLATIONS: OnceLock<Translations> = OnceLock::new();
+
+fn parse(raw: &str) -> Templates {
+    serde_json::from_str(raw).expect("embedded translation file is invalid JSON")
+}
+
+/// Loads the templates for `locale`. Call once at startup
+pub fn init(locale: &str) -> Result<(), String> {
+    let raw = LOCALES.iter()
+        .find(|(name, _)| *name == locale)
+        .map(|(_, raw)| *raw)
+        .ok_or_else(|| format!("unsupported locale '{}'", locale))?;
+
+    TRANSLATIONS.set(Translations { selected: parse(raw), fallback: parse(ENGLISH) }).ok();
+    Ok(())
+}
+
+/// Looks up `key` and substitutes `{name}` placeholders
+pub fn render(key: &str, args: &[(&str, String)]) -> String {
+    let translations = TRANSLATIONS.get_or_init(|| Translations { selected: parse(ENGLISH), fallback: parse(ENGLISH) });
+    let template = translations.lookup(key)
+        .cloned()
+        .unwrap_or_else(|| key.to_string());
+
+    args.iter().fold(template, |msg, (name, value)| msg.replace(&format!("{{{}}}", name), value))
+}
+
+/// Renders a translated alert message: `tr!("node_lagging", local = 1, remote = 2)`
+macro_rules! tr {
+    ($key:expr $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::i18n::render($key, &[$((stringify!($name), $value.to_string())),*])
+    };
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_substitutes_placeholders() {
+        let msg = render("node_lagging", &[("local", "10".into()), ("remote", "15".into()), ("lag", "5".into())]);
+        assert_eq!(msg, "🚨[WARN] NODE LAGGING! Local: 10 | Remote: 15 | Lag: 5 blocks");
+    }
+
+    #[test]
+    fn test_missing_key_falls_back_to_english() {
+        let translations = Translations { selected: Templates::new(), fallback: parse(ENGLISH) };
+        assert!(translations.lookup("local_node_down").unwrap().contains("LOCAL NODE DOWN"));
+    }
+
+    #[test]
+    fn test_locales_only_use_known_keys() {
+        let english = parse(ENGLISH);
+        for (name, raw) in LOCALES {
+            for key in parse(raw).keys() {
+                assert!(english.contains_key(key), "locale '{}' has unknown key '{}'", name, key);
+            }
+        }
+    }
+
+    #[test]
+    fn test_unknown_locale_is_rejected() {
+        assert!(init("xx").is_err());
+    }
+}
@@ -0,0 +1,138 @@
+
+// --- InfluxDB Export ---
+//
+// With INFLUXDB_URL set, every poll is recorded as one `eth_alive` point
+// (tags node and chain) and written to the InfluxDB v2 `/api/v2/write`
+// endpoint in line protocol. Points are buffered and written together every
+// INFLUXDB_BATCH_CYCLES polls, by a task of their own so the main loop never
+// waits on the database.
+
+use chrono::{DateTime, Utc};
+use tokio::sync::mpsc;
+
+use crate::redact_url;
+
+/// Measurement name of the points
+const MEASUREMENT: &str = "eth_alive";
+
+/// Points that may queue up while a write is in flight; later ones are dropped
+const POINT_CAPACITY: usize = 1000;
+
+/// INFLUXDB_* settings
+#[derive(Clone, Debug)]
+pub struct InfluxConfig {
+    pub url: String,
+    pub token: String,
+    pub org: String,
+    pub bucket: String,
+    /// Polls buffered before a write
+    pub batch_cycles: usize,
+}
+
+/// The state of one poll
+pub struct Point {
+    pub timestamp: DateTime<Utc>,
+    pub local_block: Option<u64>,
+    pub remote_block: Option<u64>,
+    pub local_latency_ms: u64,
+    pub remote_latency_ms: u64,
+}
+
+impl Point {
+    /// The line protocol line; unknown blocks (and the lag they'd give) are left out
+    fn to_line(&self, node: &str, chain: &str) -> String {
+        let lag = match (self.local_block, self.remote_block) {
+            (Some(local), Some(remote)) => Some(remote.saturating_sub(local)),
+            _ => None,
+        };
+        let fields: Vec<String> = [
+            ("local_block", self.local_block),
+            ("remote_block", self.remote_block),
+            ("lag", lag),
+            ("local_latency_ms", Some(self.local_latency_ms)),
+            ("remote_latency_ms", Some(self.remote_latency_ms)),
+        ].into_iter()
+            .filter_map(|(name, value)| value.map(|value| format!("{}={}i", name, value)))
+            .collect();
+        format!(
+            "{},node={},chain={} {} {}",
+            MEASUREMENT,
+            escape_tag(node),
+            escape_tag(chain),
+            fields.join(","),
+            self.timestamp.timestamp(),
+        )
+    }
+}
+
+/// Tag values escape commas, equals signs and spaces
+fn escape_tag(value: &str) -> String {
+    value.replace(',', "\\,").replace('=', "\\=").replace(' ', "\\ ")
+}
+
+/// Hands points to the writer task without ever waiting on InfluxDB
+pub struct InfluxWriter {
+    sender: mpsc::Sender<String>,
+    node: String,
+    chain: String,
+}
+
+impl InfluxWriter {
+    /// Spawns the writer task; `node` and `chain` tag every point
+    pub fn spawn(config: InfluxConfig, client: reqwest::Client, node: &str, chain: &str) -> Self {
+        let (sender, receiver) = mpsc::channel(POINT_CAPACITY);
+        tokio::spawn(write_loop(config, client, receiver));
+        InfluxWriter { sender, node: node.to_string(), chain: chain.to_string() }
+    }
+
+    pub fn record(&self, point: Point) {
+        if self.sender.try_send(point.to_line(&self.node, &self.chain)).is_err() {
+            debug!("[DEBUG] InfluxDB writes falling behind, dropping a point");
+        }
+    }
+}
+
+/// Collects `batch_cycles` lines and writes them in one request. A failed
+/// write is logged and its points dropped, so an outage can't grow the buffer
+async fn write_loop(config: InfluxConfig, client: reqwest::Client, mut receiver: mpsc::Receiver<String>) {
+    let write_url = format!("{}/api/v2/write", config.url.trim_end_matches('/'));
+    let mut batch: Vec<String> = Vec::with_capacity(config.batch_cycles);
+    while let Some(line) = receiver.recv().await {
+        batch.push(line);
+        if batch.len() < config.batch_cycles {
+            continue;
+        }
+        let body = batch.join("\n");
+        batch.clear();
+        let result = client.post(&write_url)
+            .query(&[("org", config.org.as_str()), ("bucket", config.bucket.as_str()), ("precision", "s")])
+            .header(reqwest::header::AUTHORIZATION, format!("Token {}", config.token))
+            .header(reqwest::header::CONTENT_TYPE, "text/plain; charset=utf-8")
+            .body(body)
+            .send().await
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = result {
+            warn!("[WARN] Failed to write to InfluxDB {}: {}", redact_url(&config.url), e.without_url());
+        }
+    }
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_line_protocol() {
+        let timestamp = DateTime::parse_from_rfc3339("2024-05-01T12:00:00Z").unwrap().with_timezone(&Utc);
+        let point = Point { timestamp, local_block: Some(100), remote_block: Some(103), local_latency_ms: 12, remote_latency_ms: 80 };
+        assert_eq!(
+            point.to_line("validator 1", "mainnet"),
+            "eth_alive,node=validator\\ 1,chain=mainnet local_block=100i,remote_block=103i,lag=3i,local_latency_ms=12i,remote_latency_ms=80i 1714564800",
+        );
+
+        let down = Point { timestamp, local_block: None, remote_block: Some(103), local_latency_ms: 10000, remote_latency_ms: 80 };
+        assert_eq!(down.to_line("a", "b"), "eth_alive,node=a,chain=b remote_block=103i,local_latency_ms=10000i,remote_latency_ms=80i 1714564800");
+    }
+}
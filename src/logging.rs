@@ -0,0 +1,309 @@
+
+// --- Logging ---
+//
+// Every log line goes through `emit` so the destination can be switched at
+// startup: the terminal (default) or the system syslog (LOG_DEST=syslog).
+// Terminal output is plain text, journal fields with LOG_FORMAT=journal, or
+// `tracing` events with LOG_FORMAT=pretty / json (for log aggregators), which
+// also carry structured fields such as `local_block` and `lag`.
+// Debug lines are dropped unless LOG_LEVEL=debug.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use syslog::{Facility, Formatter5424, Logger, LoggerBackend};
+
+/// Severity of a log line
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+/// Where log lines are written (LOG_DEST)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LogDest {
+    Stdout,
+    Syslog,
+}
+
+impl std::str::FromStr for LogDest {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stdout" => Ok(LogDest::Stdout),
+            "syslog" => Ok(LogDest::Syslog),
+            other => Err(format!("unknown log destination '{}'", other)),
+        }
+    }
+}
+
+/// Lowest severity that is written (LOG_LEVEL)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LogLevel {
+    Info,
+    Debug,
+}
+
+impl std::str::FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "info" => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            other => Err(format!("unknown log level '{}'", other)),
+        }
+    }
+}
+
+/// How terminal log lines are formatted (LOG_FORMAT)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LogFormat {
+    Plain,
+    Journal,
+    /// Human-readable tracing output with timestamps, levels and fields
+    Pretty,
+    /// One JSON object per line
+    Json,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(LogFormat::Plain),
+            "journal" => Ok(LogFormat::Journal),
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!("unknown log format '{}'", other)),
+        }
+    }
+}
+
+/// Structured context of a log line, written as fields by the tracing formats
+#[derive(Debug, Default)]
+pub struct Fields {
+    pub local_block: Option<u64>,
+    pub remote_block: Option<u64>,
+    pub lag: Option<u64>,
+    pub error: Option<String>,
+}
+
+type SyslogLogger = Logger<LoggerBackend, Formatter5424>;
+
+static SYSLOG: OnceLock<Mutex<SyslogLogger>> = OnceLock::new();
+
+static DEBUG_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Writes debug lines too (LOG_LEVEL=debug)
+pub fn enable_debug() {
+    DEBUG_ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Node name written as NODE_NAME; only set when LOG_FORMAT=journal
+static JOURNAL_NODE: OnceLock<String> = OnceLock::new();
+
+/// Writes all further terminal log lines as journal entries tagged with `node_name`
+pub fn init_journal(node_name: &str) {
+    JOURNAL_NODE.set(node_name.to_string()).ok();
+}
+
+/// Formats a journal entry: one KEY=value field per line, terminated by an empty line
+fn journal_entry(severity: Severity, node_name: &str, message: &str) -> String {
+    // syslog(3) priorities: err=3, warning=4, info=6, debug=7
+    let priority = match severity {
+        Severity::Debug => 7,
+        Severity::Info => 6,
+        Severity::Warn => 4,
+        Severity::Error => 3,
+    };
+    // Fields are line-delimited, so a message must stay on one line
+    let message = message.replace('\n', " ");
+    format!("PRIORITY={}\nMESSAGE={}\nNODE_NAME={}\n", priority, message, node_name)
+}
+
+/// Set once `setup_tracing` has installed the subscriber
+static TRACING: AtomicBool = AtomicBool::new(false);
+
+/// Writes all further terminal log lines as `tracing` events (LOG_FORMAT=pretty or json)
+pub fn setup_tracing(format: LogFormat) {
+    let builder = tracing_subscriber::fmt().with_max_level(tracing::Level::DEBUG);
+    let installed = match format {
+        LogFormat::Json => builder.json().try_init(),
+        _ => builder.try_init(),
+    };
+    if installed.is_ok() {
+        TRACING.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Whether lines are written as structured records, where decorations like
+/// banner separators don't belong
+pub fn structured() -> bool {
+    TRACING.load(Ordering::Relaxed)
+}
+
+fn trace_event(severity: Severity, message: &str, fields: &Fields) {
+    let Fields { local_block, remote_block, lag, error } = fields;
+    let error = error.as_deref();
+    match severity {
+        Severity::Debug => tracing::debug!(local_block, remote_block, lag, error, "{}", message),
+        Severity::Info => tracing::info!(local_block, remote_block, lag, error, "{}", message),
+        Severity::Warn => tracing::warn!(local_block, remote_block, lag, error, "{}", message),
+        Severity::Error => tracing::error!(local_block, remote_block, lag, error, "{}", message),
+    }
+}
+
+/// Routes all further log lines to syslog (RFC 5424, facility LOG_DAEMON)
+pub fn init_syslog(app_name: &str) -> Result<(), syslog::Error> {
+    let formatter = Formatter5424 {
+        facility: Facility::LOG_DAEMON,
+        hostname: None,
+        process: app_name.to_string(),
+        pid: std::process::id(),
+    };
+    let logger = syslog::unix(formatter)?;
+    SYSLOG.set(Mutex::new(logger)).ok();
+    Ok(())
+}
+
+/// Writes a log line to the active destination
+pub fn emit(severity: Severity, message: &str) {
+    emit_fields(severity, message, &Fields::default());
+}
+
+/// Like `emit`, with structured fields for the tracing formats
+pub fn emit_fields(severity: Severity, message: &str, fields: &Fields) {
+    if severity == Severity::Debug && !DEBUG_ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let message = &crate::metadata::annotate(message);
+
+    let Some(logger) = SYSLOG.get() else {
+        if structured() {
+            trace_event(severity, message, fields);
+            return;
+        }
+        if let Some(node_name) = JOURNAL_NODE.get() {
+            // One stream keeps entries whole; PRIORITY carries the severity
+            println!("{}", journal_entry(severity, node_name, message));
+            return;
+        }
+        match severity {
+            Severity::Error => eprintln!("{}", message),
+            _ => println!("{}", message),
+        }
+        return;
+    };
+
+    let mut logger = logger.lock().unwrap();
+    let entry = (0, Default::default(), message);
+    let result = match severity {
+        Severity::Debug => logger.debug(entry),
+        Severity::Info => logger.info(entry),
+        Severity::Warn => logger.warning(entry),
+        Severity::Error => logger.err(entry),
+    };
+    if let Err(e) = result {
+        eprintln!("{} (syslog write failed: {})", message, e);
+    }
+}
+
+// Each macro optionally takes structured fields before the format string:
+// `info!(local_block = block, lag = lag; "[OK] Synced | Block: {}", block)`
+
+macro_rules! log_fields {
+    ($($field:ident = $value:expr),+) => {
+        $crate::logging::Fields { $($field: Some($value.into()),)+ ..Default::default() }
+    };
+}
+
+macro_rules! debug {
+    ($($field:ident = $value:expr),+ ; $($arg:tt)*) => { $crate::logging::emit_fields($crate::logging::Severity::Debug, &format!($($arg)*), &log_fields!($($field = $value),+)) };
+    ($($arg:tt)*) => { $crate::logging::emit($crate::logging::Severity::Debug, &format!($($arg)*)) };
+}
+
+macro_rules! info {
+    ($($field:ident = $value:expr),+ ; $($arg:tt)*) => { $crate::logging::emit_fields($crate::logging::Severity::Info, &format!($($arg)*), &log_fields!($($field = $value),+)) };
+    ($($arg:tt)*) => { $crate::logging::emit($crate::logging::Severity::Info, &format!($($arg)*)) };
+}
+
+macro_rules! warn {
+    ($($field:ident = $value:expr),+ ; $($arg:tt)*) => { $crate::logging::emit_fields($crate::logging::Severity::Warn, &format!($($arg)*), &log_fields!($($field = $value),+)) };
+    ($($arg:tt)*) => { $crate::logging::emit($crate::logging::Severity::Warn, &format!($($arg)*)) };
+}
+
+macro_rules! error {
+    ($($field:ident = $value:expr),+ ; $($arg:tt)*) => { $crate::logging::emit_fields($crate::logging::Severity::Error, &format!($($arg)*), &log_fields!($($field = $value),+)) };
+    ($($arg:tt)*) => { $crate::logging::emit($crate::logging::Severity::Error, &format!($($arg)*)) };
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_log_dest() {
+        assert_eq!("stdout".parse::<LogDest>().unwrap(), LogDest::Stdout);
+        assert_eq!("syslog".parse::<LogDest>().unwrap(), LogDest::Syslog);
+        assert!("file".parse::<LogDest>().is_err());
+    }
+
+    /// Collects what a subscriber writes
+    #[derive(Clone, Default)]
+    struct Buffer(std::sync::Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_output_has_structured_fields() {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt().json().with_writer(move || writer.clone()).finish();
+        tracing::subscriber::with_default(subscriber, || {
+            let fields = log_fields!(local_block = 100u64, remote_block = 105u64, lag = 5u64);
+            trace_event(Severity::Warn, "NODE LAGGING!", &fields);
+            trace_event(Severity::Error, "LOCAL NODE DOWN!", &log_fields!(error = "connection refused"));
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines[0]["level"], "WARN");
+        assert_eq!(lines[0]["fields"]["message"], "NODE LAGGING!");
+        assert_eq!(lines[0]["fields"]["local_block"], 100);
+        assert_eq!(lines[0]["fields"]["remote_block"], 105);
+        assert_eq!(lines[0]["fields"]["lag"], 5);
+        assert_eq!(lines[1]["fields"]["error"], "connection refused");
+        // Unset fields are left out rather than written as null
+        assert!(lines[1]["fields"].get("lag").is_none());
+    }
+
+    #[test]
+    fn test_parse_log_format() {
+        assert_eq!("json".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert_eq!("pretty".parse::<LogFormat>().unwrap(), LogFormat::Pretty);
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+
+    #[test]
+    fn test_journal_entry_fields() {
+        let entry = journal_entry(Severity::Warn, "eth-alive", "NODE LAGGING!\nLag: 5");
+        assert_eq!(entry, "PRIORITY=4\nMESSAGE=NODE LAGGING! Lag: 5\nNODE_NAME=eth-alive\n");
+        assert!(journal_entry(Severity::Error, "n", "x").starts_with("PRIORITY=3\n"));
+    }
+}
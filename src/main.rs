@@ -1,233 +1,819 @@
 
 // --- Imports ---
 
-use std::time::Duration;
+#[macro_use]
+mod logging;
+#[macro_use]
+mod i18n;
+
+mod beacon;
+mod checks;
+mod clientlog;
+mod cloudwatch;
+mod config;
+mod db;
+mod discovery;
+mod diskio;
+mod dns;
+mod error;
+mod ethash;
+mod events;
+mod export;
+mod groups;
+mod headers;
+mod hooks;
+mod influx;
+mod metadata;
+mod metrics;
+mod nodes;
+mod notify;
+mod pidfile;
+mod plugins;
+mod proof;
+mod redis_status;
+mod remote;
+mod repl;
+mod response_size;
+mod rlp;
+mod routing;
+mod rpc;
+mod selfmon;
+mod snapshot;
+mod stall;
+mod status_page;
+mod state;
+mod trigger;
+mod ws;
+
 use std::env;
-use dotenvy::dotenv;
-use serde::Serialize;
-use serde_json::Value; 
+use std::net::{IpAddr, SocketAddr};
 use std::process;
-use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use chrono::Utc;
 use colored::Colorize;
+use tokio::signal::unix::{signal, SignalKind};
 use url::Url;
 
-// --- Data Structures & Configuration ---
+use checks::{build_checks, check_checkpoint_state_root, check_enr_stability, check_protocol_version, check_rpc_bind_address, infer_block_time, poll_interval_warning, CheckContext, CheckOutcome, ScheduledCheck};
+use config::{get_env, parse_env, Config};
+use db::{PollDb, PollRecord};
+use error::AppError;
+use events::{lag_verdict, DownCause, EventBus, LagVerdict, MonitoringEvent};
+use groups::{poll_group, GroupState};
+use logging::{LogDest, LogFormat, LogLevel};
+use notify::{Alert, AlertSeverity, AlertState, Notifier};
+use remote::RemotePool;
+use response_size::ResponseSizes;
+use rpc::{fetch_block_number_sized, fetch_sync_status, SyncStatus};
+use state::State;
+use ws::TransportMode;
+
+// --- Main Execution ---
 
-/// Application configuration loaded from the environment
-struct Config {
-    local_rpc: String,
-    remote_rpc: String,
-    lag_threshold: u64,
-    alert_cooldown_minutes: u64,
-    poll_interval_seconds: u64,
-    discord_webhook: String,
+fn main() {
+    // Setting environment variables is only sound before other threads exist
+    config::load_env();
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to start the tokio runtime");
+    runtime.block_on(start());
 }
 
-impl Config {
-    fn from_env() -> Self {
-        dotenv().ok(); // Load .env file if present, ignore if file is missing
-
-        // optional LAG_THRESHOLD (u64), defaulting to 3
-        let lag_threshold = env::var("LAG_THRESHOLD")
-            .unwrap_or_else(|_| "3".to_string()) // Default to string "3"
-            .parse::<u64>()
-            .expect("LAG_THRESHOLD must be a valid number");
-        
-        // optional ALERT_COOLDOWN_MINUTES u64, defaulting to 15
-        let alert_cooldown_minutes = env::var("ALERT_COOLDOWN_MINUTES")
-            .unwrap_or_else(|_| "15".to_string())
-            .parse::<u64>()
-            .expect("ALERT_COOLDOWN_MINUTES must be a valid number");
-        // optional POLL_INTERVAL_SECONDS u64, defaulting to 60 secs 
-        let poll_interval_seconds = env::var("POLL_INTERVAL_SECONDS")
-            .unwrap_or_else(|_| "60".to_string())
-            .parse::<u64>()
-            .expect("POLL_INTERVAL_SECONDS must be a valid number");
-
-        Config {
-            local_rpc: get_env("LOCAL_RPC_URL"),
-            remote_rpc: get_env("REMOTE_RPC_URL"),
-            lag_threshold,
-            alert_cooldown_minutes,
-            poll_interval_seconds,
-            discord_webhook: get_env("DISCORD_WEBHOOK_URL"),
+async fn start() {
+    if env::args().any(|arg| arg == "--analyze") {
+        analyze();
+    }
+    if env::args().any(|arg| arg == "--export-csv-header") {
+        println!("{}", export::CSV_HEADER);
+        return;
+    }
+    if env::args().nth(1).as_deref() == Some("test-alert") {
+        test_alert(env::args().nth(2)).await;
+    }
+
+    println!("eth-alive daemon starting up...");
+
+    let mut config = Config::from_env();
+    if let Err(e) = i18n::init(&config.alert_locale) {
+        eprintln!("Error: Invalid ALERT_LOCALE: {}", e);
+        process::exit(1);
+    }
+    if config.log_dest == LogDest::Syslog && let Err(e) = logging::init_syslog(&config.node_label) {
+        eprintln!("Error: Failed to connect to syslog: {}", e);
+        process::exit(1);
+    }
+    match config.log_format {
+        LogFormat::Plain => {}
+        LogFormat::Journal => logging::init_journal(&config.node_label),
+        LogFormat::Pretty | LogFormat::Json => logging::setup_tracing(config.log_format),
+    }
+    if config.log_level == LogLevel::Debug {
+        logging::enable_debug();
+    }
+    if !config.watch_headers.is_empty() || !config.header_alert_rules.is_empty() {
+        headers::init(config.watch_headers.clone(), std::mem::take(&mut config.header_alert_rules));
+    }
+    if let Some(url) = config.metadata_url.clone() {
+        metadata::init(url, config.metadata_fields.clone(), config.metadata_refresh_hours).await;
+    }
+    if let Some(namespace) = config.cloudwatch_namespace.clone() {
+        cloudwatch::init(namespace, config.aws_region.clone().expect("validated in Config::from_env"), config.node_label.clone()).await;
+    }
+    if let Some(url) = config.status_page_url.clone() {
+        status_page::init(url, config.status_refresh_minutes).await;
+    }
+    if config.force_http10 {
+        rpc::force_http10();
+    }
+    notify::install_panic_hook(&config);
+    let local_client = build_rpc_client(config.local_bind_address, config.local_rpc_sni.as_ref(), config.dns_timeout_secs);
+    let remote_client = build_rpc_client(config.remote_bind_address, None, config.dns_timeout_secs);
+
+    if env::args().any(|arg| arg == "--repl") {
+        repl::run(&config, &local_client, &remote_client).await;
+        return;
+    }
+    let notifier = Notifier::new(&mut config);
+
+    let pid_file = config.pid_file.as_deref().map(|path| open_pid_file(path, config.pid_file_exit_if_running));
+
+    if !config.nodes.is_empty() {
+        run_fleet(config, local_client, remote_client, notifier).await;
+        if let Some(pid_file) = pid_file {
+            pid_file.remove();
         }
-    }     
-}
+        return;
+    }
 
-/// Represents the JSON payload sent to Discord
-#[derive(Serialize)]
-struct DiscordBody {
-    content: String,
+    info!("Configuration Loaded. Starting Watchdog Loop...");
+    print_rule();
+    info!("  Local Node:        {}", redact_url(&config.local_rpc));
+    for remote in &config.remote_rpcs {
+        info!("  Remote Node:       {}", redact_url(remote));
+    }
+    if config.remote_rpcs.len() > 1 {
+        info!("  Remote Strategy:   {:?}", config.remote_strategy);
+    }
+    if config.remote_agreement_check {
+        info!("  Remote Agreement:  within {} blocks", config.remote_disagreement_threshold);
+    }
+    if config.remote_rpcs.len() > 1 && config.circuit_failure_threshold > 0 {
+        info!("  Remote Circuits:   open after {} failures, probe every {}s", config.circuit_failure_threshold, config.circuit_probe_interval_secs);
+    }
+    if let Some((hostname, addr)) = &config.local_rpc_sni {
+        info!("  Local SNI:         {} (connecting to {})", hostname, addr);
+    }
+    if let Some(addr) = config.local_bind_address {
+        info!("  Local Bind:        {}", addr);
+    }
+    if let Some(addr) = config.remote_bind_address {
+        info!("  Remote Bind:       {}", addr);
+    }
+    info!("  Threshold:         {} blocks", config.lag_threshold);
+    if config.weekend_lag_threshold_multiplier != 1.0 {
+        info!("  Weekend Threshold: x{} on Saturdays and Sundays (UTC)", config.weekend_lag_threshold_multiplier);
+    }
+    if let Some(exit_lag) = config.lag_exit_threshold {
+        info!("  Exit Threshold:    {} blocks", exit_lag);
+    }
+    info!("  Ahead Threshold:   {} blocks", config.local_ahead_alert_threshold);
+    info!("  Notif Cooldown:    {} minutes", config.alert_cooldown_minutes); 
+    info!("  Polling:           Every {} seconds", config.poll_interval_seconds);
+    info!("  Alert Locale:      {}", config.alert_locale);
+    info!("  Log Destination:   {:?} ({}, {:?})", config.log_dest, config.node_label, config.log_format);
+    info!("  Webhook Timeout:   {} seconds", config.webhook_timeout_secs);
+    if let Some(smtp) = &config.smtp {
+        info!("  Email:             {}:{} (TLS: {})", smtp.host, smtp.port, smtp.tls.label());
+    }
+    info!("  DNS Timeout:       {} seconds", config.dns_timeout_secs);
+    info!("  Metrics:           http://0.0.0.0:{}/metrics", config.metrics_port);
+    if let Some(routing) = &config.method_routing {
+        info!("  Method Routing:    {} routes to {} endpoints", routing.routes.len(), routing.endpoints.len());
+    }
+
+    let result = run_loop(config, local_client, remote_client, notifier).await;
+    if let Some(pid_file) = pid_file {
+        pid_file.remove();
+    }
+    match result {
+        Ok(0) => {}
+        Ok(code) => process::exit(code),
+        Err(e) => {
+            error!("[ERROR] {}", e);
+            process::exit(1);
+        }
+    }
 }
 
+/// Exit code once the lag reaches LAG_EXIT_THRESHOLD
+const LAG_EXIT_CODE: i32 = 2;
 
-// --- Main Execution ---
+/// Runs the watchdog loop until SIGTERM or SIGINT, or until LAG_EXIT_THRESHOLD
+/// is reached. A signal never interrupts a poll in progress: the current
+/// iteration finishes, then the loop stops. Returns the process exit code
+async fn run_loop(mut config: Config, local_client: reqwest::Client, remote_client: reqwest::Client, notifier: Notifier) -> Result<i32, Box<dyn std::error::Error>> {
+    let mut shutdown = tokio::spawn(shutdown_signal());
 
-#[tokio::main]
-async fn main() {
-    println!("eth-alive daemon starting up...");
+    check_poll_interval(&config, &remote_client).await;
 
-    let config = Config::from_env();
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(10))
-        .build()
-        .expect("Failed to build HTTP client");
+    let mut checks = build_checks(&config);
+    for scheduled in &checks {
+        info!("  Check:             {} (every {} polls)", scheduled.check.name(), scheduled.config.interval_multiplier);
+    }
+
+    let db = config.sqlite_path.as_deref().map(|path| open_db(path, config.db_history_days));
+    if let Some(path) = &config.sqlite_path {
+        info!("  SQLite History:    {} ({} days)", path, config.db_history_days);
+    }
+
+    let trigger = match config.trigger_socket_path.as_deref() {
+        Some(path) => {
+            info!("  Trigger Socket:    {}", path);
+            let listener = trigger::spawn_listener(path)
+                .map_err(|e| format!("Failed to bind trigger socket '{}': {}", path, e))?;
+            Some(listener)
+        }
+        None => None,
+    };
+
+    if !config.ws_logs_addresses.is_empty() {
+        let ws_url = config.local_ws.clone().ok_or("WS_SUBSCRIBE_LOGS_ADDRESS requires LOCAL_WS_URL")?;
+        info!("  Log Subscription:  {} contracts via {}", config.ws_logs_addresses.len(), redact_url(&ws_url));
+        ws::spawn_log_watcher(ws_url, config.ws_logs_addresses.clone(), config.ws_logs_topics.clone());
+    }
+
+    let mut groups: Vec<GroupState> = config.groups.drain(..)
+        .map(|config| GroupState { config, alert_state: AlertState::default() })
+        .collect();
+    for group in &groups {
+        info!("  Group:             {} ({} nodes, min healthy {})", group.config.name, group.config.nodes.len(), group.config.min_healthy());
+    }
+
+    let mut state = State::load(&config.state_file).unwrap_or_else(|e| {
+        error!("[ERROR] Failed to load state file '{}', starting fresh: {}", config.state_file, e);
+        State::default()
+    });
+
+    if config.protocol_version_check {
+        match check_protocol_version(&local_client, &config.local_rpc, &mut state).await {
+            Ok(outcome) => {
+                report_outcome(&notifier, AlertSeverity::Warn, "protocol_version", outcome, &mut AlertState::default()).await;
+                save_state(&state, &config.state_file);
+            }
+            Err(e) => error!("[ERROR] Check 'protocol_version' failed: {}", e),
+        }
+    }
+
+    if config.check_enr_stability {
+        match check_enr_stability(&local_client, &config.local_rpc, &mut state).await {
+            Ok(outcome) => {
+                report_outcome(&notifier, AlertSeverity::Warn, "enr_stability", outcome, &mut AlertState::default()).await;
+                save_state(&state, &config.state_file);
+            }
+            Err(e) => error!("[ERROR] Check 'enr_stability' failed: {}", e),
+        }
+    }
 
-    println!("Configuration Loaded. Starting Watchdog Loop...");
-    println!("{}", "-------------------------------------------------".dimmed());
-    println!("  Local Node:        {}", redact_url(&config.local_rpc));
-    println!("  Remote Node:       {}", redact_url(&config.remote_rpc));
-    println!("  Threshold:         {} blocks", config.lag_threshold);
-    println!("  Notif Cooldown:    {} minutes", config.alert_cooldown_minutes); 
-    println!("  Polling:           Every {} seconds", config.poll_interval_seconds);
+    if let Some(expected) = &config.expected_rpc_bind_address {
+        match check_rpc_bind_address(&local_client, &config.local_rpc, expected).await {
+            Ok(outcome) => report_outcome(&notifier, AlertSeverity::Warn, "rpc_bind_address", outcome, &mut AlertState::default()).await,
+            Err(e) => error!("[ERROR] Check 'rpc_bind_address' failed: {}", e),
+        }
+    }
+
+    if let Some((block, expected)) = &config.checkpoint {
+        match check_checkpoint_state_root(&local_client, &config.local_rpc, *block, expected).await {
+            Ok(outcome) => report_outcome(&notifier, AlertSeverity::Critical, "checkpoint_state_root", outcome, &mut AlertState::default()).await,
+            Err(e) => error!("[ERROR] Check 'checkpoint_state_root' failed: {}", e),
+        }
+    }
 
-    let mut last_alert_time: Option<DateTime<Utc>> = None;
-    let alert_cooldown = chrono::Duration::minutes(config.alert_cooldown_minutes as i64);
+    metrics::init(config.metrics_port, config.victoria_metrics_url.as_deref(), Duration::from_secs(config.poll_interval_seconds), &config.prometheus_extra_labels);
+
+    let redis = match &config.redis_url {
+        Some(url) => {
+            info!("  Redis Status:      {} (eth-alive:{}:status)", redact_url(url), config.node_label);
+            let redis = redis_status::RedisStatus::spawn(url, &config.node_label, config.poll_interval_seconds)
+                .map_err(|e| format!("Invalid REDIS_URL: {}", e))?;
+            Some(redis)
+        }
+        None => None,
+    };
+
+    let influx = config.influxdb.clone().map(|influxdb| {
+        info!("  InfluxDB:          {} ({}/{}, every {} polls)", redact_url(&influxdb.url), influxdb.org, influxdb.bucket, influxdb.batch_cycles);
+        influx::InfluxWriter::spawn(influxdb, remote_client.clone(), &config.node_label, &config.chain_name)
+    });
+
+    let notifier = Arc::new(notifier);
+    let mut bus = EventBus::new();
+    bus.spawn_handler("logging", events::log_events);
+    // An incident still open in the state file is followed up, not started over
+    let mut resumed_incident = state.incident.clone();
+    let resumed = resumed_incident.is_some();
+    let mut incident_open = resumed;
+    bus.spawn_handler("alerts", |events| events::alert_events(events, notifier.clone(), resumed));
+    bus.spawn_handler("metrics", events::metrics_events);
+    let state_file = config.state_file.clone();
+    bus.spawn_handler("state", move |events| events::state_events(events, state, state_file));
+    let (snapshot_file, lag_threshold, weekend_multiplier, ahead_threshold) = (config.snapshot_file.clone(), config.lag_threshold, config.weekend_lag_threshold_multiplier, config.local_ahead_alert_threshold);
+    bus.spawn_handler("snapshot", move |events| events::snapshot_events(events, snapshot_file, redis, lag_threshold, weekend_multiplier, ahead_threshold));
+
+    let mut remotes = RemotePool::new(config.remote_rpcs.clone(), config.remote_strategy)
+        .with_circuit_breakers(config.circuit_failure_threshold, Duration::from_secs(config.circuit_probe_interval_secs));
+    let mut agreement_alert = AlertState::default();
+    let mut local_down = false;
+    let mut stall = stall::StallTracker::new(config.stall_threshold_seconds);
+    let mut stall_alert = AlertState::default();
+    let mut response_sizes = ResponseSizes::new();
+    let mut size_alert = AlertState::default();
+    let mut self_monitor = if config.self_monitor {
+        selfmon::SelfMonitor::new().map_err(|e| warn!("[WARN] Self monitoring unavailable: {}", e)).ok()
+    } else {
+        None
+    };
+    let mut self_alert = AlertState::default();
+    let mut cycle: u64 = 0;
+    let mut exit_code = 0;
+
+    // TRANSPORT_MODE=ws: every new head runs an iteration, while the remote
+    // (and everything else) is still polled every POLL_INTERVAL_SECONDS
+    let poll_interval = Duration::from_secs(config.poll_interval_seconds);
+    let mut heads = match (config.transport_mode, &config.local_ws) {
+        (TransportMode::Ws, Some(url)) => {
+            info!("  Transport:         newHeads via {}", redact_url(url));
+            let mut heads = ws::spawn_head_watcher(url.clone());
+            // Give the subscription a moment so the first poll doesn't report the node down
+            let _ = tokio::time::timeout(Duration::from_secs(10), heads.changed()).await;
+            Some(heads)
+        }
+        _ => None,
+    };
+    let mut last_remote: Option<(Instant, u64)> = None;
 
     loop {
-        let remote_result = fetch_block_number(&client, &config.remote_rpc).await;
-        let local_result = fetch_block_number(&client, &config.local_rpc).await;
+        let full_poll = heads.is_none() || last_remote.is_none_or(|(at, _)| at.elapsed() >= poll_interval);
+        let remote_started = Instant::now();
+        let remote_result = if let (false, Some((_, block))) = (full_poll, last_remote) {
+            Ok(block)
+        } else if config.remote_agreement_check {
+            // Every remote is queried anyway, so pick the reference from those results
+            let results = remotes.fetch_all(&remote_client).await;
+            if let Some(outcome) = remote::agreement_outcome(remotes.urls(), &results, config.remote_disagreement_threshold) {
+                report_outcome(&notifier, AlertSeverity::Warn, "remote_agreement", outcome, &mut agreement_alert).await;
+            }
+            remotes.select(results)
+        } else {
+            remotes.fetch(&remote_client).await
+        };
+        if full_poll {
+            last_remote = remote_result.as_ref().ok().map(|block| (Instant::now(), *block));
+        }
+        let remote_latency_ms = remote_started.elapsed().as_millis() as u64;
+        let started = Instant::now();
+        let (response_size, local_result) = match &heads {
+            Some(heads) => (None, heads.borrow().ok_or_else(|| AppError::WsError("No newHeads from LOCAL_WS_URL".to_string()))),
+            None => fetch_block_number_sized(&local_client, &config.local_rpc).await,
+        };
+        let rpc_latency_ms = started.elapsed().as_millis() as u64;
+        // Tells a planned resync apart from a node that fell behind; clients
+        // without 'eth_syncing' are treated as synced
+        let sync_status = match &local_result {
+            Ok(_) => fetch_sync_status(&local_client, &config.local_rpc).await.ok(),
+            Err(_) => None,
+        };
+        if let Some(status) = &sync_status {
+            metrics::record_sync_progress(status.progress_pct());
+        }
+
+        // Growing response bodies (error pages, stack traces) are an early sign of trouble
+        if let Some(size) = response_size {
+            response_sizes.record(size);
+            if let Some(p99) = response_sizes.p99() {
+                metrics::record_response_size_p99(p99);
+            }
+        }
+        if let (Some(max), Some(p99)) = (config.max_response_size_bytes, response_sizes.p99()) && p99 > max {
+            let outcome = CheckOutcome::Alert(tr!("large_responses", p99 = p99, max = max));
+            report_outcome(&notifier, AlertSeverity::Warn, "response_size", outcome, &mut size_alert).await;
+        }
+        let remote_block = remote_result.as_ref().ok().copied();
+        let local_block = local_result.as_ref().ok().copied();
+        bus.emit(MonitoringEvent::BlockFetched { local: local_block, remote: remote_block });
+        let local_up = local_block.is_some();
+
+        // Shell hooks fire once per incident, on the down/up transitions
+        if local_up == local_down {
+            local_down = !local_up;
+            let hook = if local_down {
+                config.on_down_exec.as_deref().map(|cmd| ("ON_DOWN_EXEC", cmd))
+            } else {
+                config.on_recovery_exec.as_deref().map(|cmd| ("ON_RECOVERY_EXEC", cmd))
+            };
+            if let Some((name, cmd)) = hook {
+                hooks::spawn_hook(name, cmd);
+            }
+        }
+        // Judged on the local head alone, so a slow remote can't mask a frozen node
+        if let Some(block) = local_block && let Some(stalled) = stall.observe(block, Utc::now()) {
+            let msg = tr!("node_stalled", block = block, seconds = stalled.num_seconds());
+            error!(local_block = block; "{}", msg);
+            notifier.process_alert(AlertSeverity::Critical, "node_stalled", &msg, &mut stall_alert).await;
+        }
+
         match (remote_result, local_result) {
 
+            // REMOTE AT GENESIS: block 0 is no reference, don't report a false [OK]
+            (Ok(0), Ok(_)) => {
+                warn!("[WARN] Remote node at genesis or not initialized");
+            }
+
             // HEALTHY: Both RPCs responded
             (Ok(remote), Ok(local)) => {
                 if local <= remote {
                     let lag = remote - local;
-                    if lag < config.lag_threshold {
-                        // All good: Print to terminal only
-                        println!("[OK] Synced | Block: {} | Lag: {}", local, lag);
-                        last_alert_time = None;
-                    } else {
-                        // Problem: Lagging too far behind
-                        let msg = format!("🚨[WARN] NODE LAGGING! Local: {} | Remote: {} | Lag: {} blocks", local, remote, lag);
-                        println!("{}", msg);
-
-                        // Send alert, with cooldown check
-                        process_alert(&client, &config.discord_webhook, &msg, &mut last_alert_time, alert_cooldown).await;   
+                    let syncing = matches!(sync_status, Some(SyncStatus::Syncing { .. }));
+                    match lag_verdict(lag, syncing, config.lag_threshold_at(Utc::now()), config.lag_exit_threshold) {
+                        LagVerdict::Syncing => {
+                            // Initial sync: report progress, a large lag is expected
+                            if let Some(SyncStatus::Syncing { current_block, highest_block, blocks_remaining }) = sync_status {
+                                info!(local_block = local, remote_block = remote, lag = lag; "[SYNCING] Block {} of {} | Remaining: {} | Lag: {}", current_block, highest_block, blocks_remaining, lag);
+                            }
+                        }
+                        // Catastrophic lag: hand over to the process supervisor
+                        LagVerdict::Exit => {
+                            error!(local_block = local, remote_block = remote, lag = lag; "[FATAL] LAG EXCEEDS EXIT THRESHOLD, STOPPING");
+                            let msg = tr!("lag_exit", local = local, remote = remote, lag = lag);
+                            if let Err(e) = notifier.send_alert(&Alert::new(AlertSeverity::Critical, "lag_exit", &msg)).await {
+                                error!("Error: Failed to send alert: {}", e);
+                            }
+                            exit_code = LAG_EXIT_CODE;
+                            break;
+                        }
+                        LagVerdict::Synced => {
+                            // All good: Print to terminal only
+                            info!(local_block = local, remote_block = remote, lag = lag; "[OK] Synced | Block: {} | Lag: {}", local, lag);
+                            // Recovery is reported once, when the incident ends
+                            if incident_open {
+                                incident_open = false;
+                                bus.emit(MonitoringEvent::Recovered { local, remote });
+                            }
+                        }
+                        LagVerdict::Lagging => {
+                            // Problem: Lagging too far behind
+                            incident_open = true;
+                            bus.emit(MonitoringEvent::LagDetected { local, remote, lag });
+                        }
                     }
                 } else {
                         // Local ahead: a reorg or if remote is slow 
                         let lead = local - remote; 
-                        println!("[INFO] Local is ahead | Local: {} | Remote: {} | Lead: {}", local, remote, lead);
+                        if lead > config.local_ahead_alert_threshold {
+                            // Far ahead of every reference: possibly on a fork
+                            incident_open = true;
+                            bus.emit(MonitoringEvent::LocalAhead { local, remote, lead });
+                        } else {
+                            info!(local_block = local, remote_block = remote; "[INFO] Local is ahead | Local: {} | Remote: {} | Lead: {}", local, remote, lead);
+                        }
                     }
             }
 
             // REMOTE DIED: Skip health check (SoT is lost)
             (Err(e), _) => {
-                eprintln!("[ERROR] FAILED to fetch Remote RPC: {}", e);
+                error!(error = e.to_string(); "[ERROR] FAILED to fetch Remote RPC: {}", e);
             }
 
-            // LOCAL DIED: Node is down
-            (Ok(_), Err(e)) => {
-                let msg = format!("🚨[CRITICAL] LOCAL NODE DOWN! Error: {}", e);
-                eprintln!("{}", msg);
+            // LOCAL UNHEALTHY: The node answers, but with a JSON-RPC error
+            (Ok(remote), Err(AppError::RpcError { code, message })) => {
+                let cause = DownCause::RpcError { code, message };
+                incident_open = true;
+                bus.emit(MonitoringEvent::NodeDown { remote, cause });
+            }
+
+            // LOCAL DIED: Node is down (unreachable, or not speaking JSON-RPC)
+            (Ok(remote), Err(e)) => {
+                let cause = DownCause::Unreachable(e.to_string());
+                incident_open = true;
+                bus.emit(MonitoringEvent::NodeDown { remote, cause });
+            }
+        }
+
+        // First judgement after a restart during an incident: say how it stands
+        if remote_block.is_some_and(|block| block > 0) && let Some(incident) = resumed_incident.take() {
+            let since = incident.since.format("%Y-%m-%d %H:%M UTC");
+            let msg = match (incident_open, local_block) {
+                (true, Some(local)) => tr!("incident_ongoing", kind = incident.kind, since = since, status = tr!("incident_status", local = local, remote = remote_block.unwrap_or_default())),
+                (true, None) => tr!("incident_ongoing", kind = incident.kind, since = since, status = tr!("incident_local_unavailable")),
+                (false, _) => tr!("incident_resolved", kind = incident.kind, since = since, block = local_block.unwrap_or_default()),
+            };
+            info!("{}", msg);
+            if let Err(e) = notifier.send_alert(&Alert::new(AlertSeverity::Warn, "incident_status", &msg)).await {
+                error!("Error: Failed to send alert: {}", e);
+            }
+        }
+
+        if let Some(path) = &config.export_csv_path {
+            export::CsvRow {
+                timestamp: Utc::now(),
+                local_block,
+                remote_block,
+                local_latency_ms: rpc_latency_ms,
+                remote_latency_ms,
+            }.append(path).await;
+        }
+
+        if let Some(influx) = &influx {
+            influx.record(influx::Point {
+                timestamp: Utc::now(),
+                local_block,
+                remote_block,
+                local_latency_ms: rpc_latency_ms,
+                remote_latency_ms,
+            });
+        }
+
+        if let Some(db) = &db {
+            db.insert(PollRecord {
+                timestamp: Utc::now(),
+                local_block,
+                remote_block,
+                local_up,
+                remote_up: remote_block.is_some(),
+                rpc_latency_ms,
+            }).await;
+        }
+
+        // Secondary checks only make sense while the local node is answering
+        if local_up && full_poll {
+            let ctx = CheckContext { client: &local_client, rpc_url: &config.local_rpc, routing: config.method_routing.as_ref() };
+            run_due_checks(&mut checks, &ctx, cycle, &notifier).await;
+        }
+
+        // Groups are judged against the same remote reference
+        if let Some(remote) = remote_block.filter(|block| *block > 0 && full_poll) {
+            for group in &mut groups {
+                let outcome = poll_group(&local_client, &group.config, remote, config.lag_threshold_at(Utc::now()), &config.fleet_batching).await;
+                report_outcome(&notifier, AlertSeverity::Critical, "group", outcome, &mut group.alert_state).await;
+            }
+        }
 
-                process_alert(&client, &config.discord_webhook, &msg, &mut last_alert_time, alert_cooldown).await;
+        if full_poll
+            && cycle.is_multiple_of(selfmon::SELF_MONITOR_INTERVAL)
+            && let Some(usage) = self_monitor.as_mut().and_then(|monitor| monitor.sample())
+        {
+            metrics::record_self_usage(usage.memory_rss_bytes, usage.cpu_seconds);
+            let outcome = selfmon::self_usage_outcome(&usage, config.self_max_memory_mb);
+            report_outcome(&notifier, AlertSeverity::Warn, "self_monitor", outcome, &mut self_alert).await;
+        }
+
+        if full_poll {
+            cycle += 1;
+        }
+        let sleep = tokio::time::sleep(poll_interval);
+        // Wake early if someone writes to the trigger socket
+        let triggered = async {
+            match &trigger {
+                Some(trigger) => trigger.notified().await,
+                None => std::future::pending().await,
             }
+        };
+        let new_head = async {
+            match heads.as_mut() {
+                // A closed channel would resolve immediately, forever
+                Some(heads) => if heads.changed().await.is_err() { std::future::pending().await },
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            _ = sleep => {}
+            _ = triggered => {}
+            _ = new_head => {}
+            _ = &mut shutdown => break,
         }
-        tokio::time::sleep(Duration::from_secs(config.poll_interval_seconds)).await;
     }
+
+    bus.close().await;
+    print_rule();
+    info!("eth-alive shutting down after {} polls.", cycle);
+    Ok(exit_code)
 }
 
-// --- Helpers ---
+/// Fleet size above which DEDICATED_CLIENTS logs a memory warning
+const DEDICATED_CLIENTS_WARN_NODES: usize = 20;
+
+/// Polls the NODES pairs until SIGTERM or SIGINT; each pair is judged and
+/// alerts on its own, so the single-pair secondary checks don't apply
+async fn run_fleet(mut config: Config, local_client: reqwest::Client, remote_client: reqwest::Client, notifier: Notifier) {
+    info!("Configuration Loaded. Starting Fleet Loop...");
+    print_rule();
+    for node in &config.nodes {
+        let threshold = node.lag_threshold.unwrap_or(config.lag_threshold);
+        let interval = node.poll_interval_seconds.unwrap_or(config.poll_interval_seconds);
+        info!("  Node:              {} | {} vs {} | Threshold: {} blocks | Every {}s", node.name, redact_url(&node.local_rpc), redact_url(&node.remote_rpc), threshold, interval);
+    }
+    info!("  Notif Cooldown:    {} minutes", config.alert_cooldown_minutes);
+    if let Some(smtp) = &config.smtp {
+        info!("  Email:             {}:{} (TLS: {})", smtp.host, smtp.port, smtp.tls.label());
+    }
+    info!("  Concurrency:       {} nodes fetched at once", config.fleet_max_concurrent_checks);
+    if config.dedicated_clients {
+        info!("  HTTP Clients:      dedicated per node");
+    }
+    print_rule();
+    if config.dedicated_clients && config.nodes.len() > DEDICATED_CLIENTS_WARN_NODES {
+        warn!("[WARN] DEDICATED_CLIENTS with {} nodes keeps {} separate connection pools; expect noticeably higher memory use", config.nodes.len(), 2 * config.nodes.len());
+    }
+    // Warn from 80% of the limit on, before nodes start losing their own gauges
+    if config.nodes.len() * 5 >= config.max_metric_labels * 4 {
+        warn!("[WARN] {} nodes against MAX_METRIC_LABELS={}; nodes past the limit only count toward network=\"overflow\"", config.nodes.len(), config.max_metric_labels);
+    }
+    metrics::set_max_network_labels(config.max_metric_labels);
+
+    metrics::init(config.metrics_port, config.victoria_metrics_url.as_deref(), Duration::from_secs(config.poll_interval_seconds), &config.prometheus_extra_labels);
+    let states = nodes::node_states(std::mem::take(&mut config.nodes), &config);
+    let defaults = nodes::FleetDefaults {
+        lag_threshold: config.lag_threshold,
+        weekend_lag_threshold_multiplier: config.weekend_lag_threshold_multiplier,
+        poll_interval_seconds: config.poll_interval_seconds,
+    };
+    let (stop, stopped) = tokio::sync::watch::channel(false);
+    let notifier = std::sync::Arc::new(notifier);
+    let limit = nodes::ConcurrencyLimit::new(config.fleet_max_concurrent_checks);
+    let tasks = if config.dedicated_clients {
+        let (local_bind, remote_bind, dns_timeout) = (config.local_bind_address, config.remote_bind_address, config.dns_timeout_secs);
+        let clients = || (build_rpc_client(local_bind, None, dns_timeout), build_rpc_client(remote_bind, None, dns_timeout));
+        nodes::spawn_nodes(states, clients, notifier, limit, defaults, stopped)
+    } else {
+        nodes::spawn_nodes(states, || (local_client.clone(), remote_client.clone()), notifier, limit, defaults, stopped)
+    };
 
-/// Fetches an environment variable or exits if not found
-fn get_env (key: &str) -> String {
-    env::var(key).unwrap_or_else(|_| {
-        eprintln!("Error: Required environment variable '{}' not set.", key);
-        process::exit(1);
-    })
+    shutdown_signal().await;
+    let _ = stop.send(true);
+    let polls: u64 = futures::future::join_all(tasks).await.into_iter().map(|polls| polls.unwrap_or(0)).sum();
+
+    print_rule();
+    info!("eth-alive shutting down after {} polls.", polls);
 }
 
-/// Performs 'eth_blockNumber' JSON-RPC call to the specified URL
-async fn fetch_block_number(client: &reqwest::Client, url: &str) -> Result<u64, Box<dyn std::error::Error>> {
-    let payload = serde_json::json!({
-        "jsonrpc": "2.0",
-        "method": "eth_blockNumber",
-        "params": [],
-        "id": 1
-    });
+/// Banner separator, left out of structured (tracing) output
+fn print_rule() {
+    if !logging::structured() {
+        println!("{}", "-------------------------------------------------".dimmed());
+    }
+}
 
-    // Send Request & Check HTTP Status
-    let resp = client.post(url)
-        .json(&payload)
-        .send()
-        .await?
-        .error_for_status()?;
-
-    // Parse as Generic JSON Value
-    let body: Value = resp.json().await?;
-
-    // Check for RPC error
-    if let Some(err) = body.get("error") {
-        let err_msg = err.get("message").and_then(|m| m.as_str()).unwrap_or("Unknown RPC error");
-        return Err(format!("RPC Error: {}", err_msg).into());
-    }
-    
-    // Extract result 
-    let result_str = body.get("result")
-        .and_then(|v| v.as_str())
-        .ok_or("Invalid response: 'result' field missing or not a string")?;
-    
-    // Parse Hex
-    let block_number = parse_hex_to_u64(result_str)?;
-
-    Ok(block_number)
+/// Resolves on SIGTERM (e.g. `systemctl stop`) or SIGINT (Ctrl-C)
+async fn shutdown_signal() {
+    let mut terminate = signal(SignalKind::terminate()).expect("Failed to install SIGTERM handler");
+    tokio::select! {
+        _ = terminate.recv() => {}
+        _ = tokio::signal::ctrl_c() => {}
+    }
 }
 
-/// Sends a Discord alert via webhook
-async fn send_alert(client: &reqwest::Client, url: &str, message: &str) -> Result<(), Box<dyn std::error::Error>> {
-    // If the URL is empty or the placeholder, don't try to send
-    if url.is_empty() || url.contains("REDACTED") {
-        return Ok(());
+// --- Helpers ---
+
+/// Builds an RPC client, optionally bound to a specific local IP
+fn build_rpc_client(bind_address: Option<IpAddr>, sni: Option<&(String, SocketAddr)>, dns_timeout_secs: u64) -> reqwest::Client {
+    let resolver = dns::TimeoutResolver::new(Duration::from_secs(dns_timeout_secs));
+    let mut builder = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .local_address(bind_address)
+        .dns_resolver(Arc::new(resolver));
+    // The URL names the SNI hostname; connections still go to the configured IP
+    if let Some((hostname, addr)) = sni {
+        builder = builder.resolve(hostname, *addr);
     }
+    builder.build().expect("Failed to build HTTP client")
+}
+
+/// `--analyze`: prints a lag summary from the SQLite history and exits
+fn analyze() -> ! {
+    let path = get_env("SQLITE_PATH");
+    let lag_threshold = parse_env("LAG_THRESHOLD", 3);
+
+    if let Err(e) = db::print_report(&path, lag_threshold) {
+        eprintln!("Error: Failed to analyze '{}': {}", path, e);
+        process::exit(1);
+    }
+    process::exit(0);
+}
 
-    let payload = DiscordBody {
-        content: message.to_string(),
+/// `test-alert <kind>`: sends a sample alert to every configured channel,
+/// bypassing the cooldown, and exits with 1 if any channel failed
+async fn test_alert(kind: Option<String>) -> ! {
+    let Some(kind) = kind.filter(|kind| notify::TEST_ALERT_KINDS.contains(&kind.as_str())) else {
+        eprintln!("Usage: eth-alive test-alert <kind>");
+        eprintln!("Kinds: {}", notify::TEST_ALERT_KINDS.join(", "));
+        process::exit(1);
     };
 
-    client.post(url)
-        .json(&payload)
-        .send()
-        .await?;
+    let config = Config::from_env();
+    if let Err(e) = i18n::init(&config.alert_locale) {
+        eprintln!("Error: Invalid ALERT_LOCALE: {}", e);
+        process::exit(1);
+    }
+    let message = notify::test_alert_message(&kind, Utc::now()).expect("kind checked above");
+    if config.notification_plugins.is_empty() {
+        eprintln!("Error: No alert channel configured (DISCORD_WEBHOOK_URL, TELEGRAM_BOT_TOKEN, SMTP_HOST)");
+        process::exit(1);
+    }
 
-    Ok(())
+    println!("Sending: {}", message);
+    let alert = Alert::new(AlertSeverity::Warn, &kind, &metadata::annotate(&message));
+    let mut failed = false;
+    for plugin in &config.notification_plugins {
+        match plugin.send(&alert).await {
+            Ok(()) => println!("{}: OK", plugin.name()),
+            Err(e) => {
+                eprintln!("{}: FAILED: {}", plugin.name(), e);
+                failed = true;
+            }
+        }
+    }
+    process::exit(if failed { 1 } else { 0 });
 }
 
-/// Checks cooldown logic and sends an alert if necessary. Updates last_alert_time
-async fn process_alert(
-    client: &reqwest::Client,
-    webhook_url: &str,
-    message: &str,
-    last_alert_time: &mut Option<DateTime<Utc>>, 
-    cooldown: chrono::Duration,
-) {
-    // Check if we should alert
-    let should_alert = match last_alert_time {
-        None => true,
-        Some(last) => Utc::now() - *last > cooldown, 
-    };
+/// Opens the poll history database and prunes expired rows, exiting on failure
+fn open_db(path: &str, history_days: u64) -> PollDb {
+    let db = PollDb::open(path).unwrap_or_else(|e| {
+        eprintln!("Error: Failed to open SQLite database '{}': {}", path, e);
+        process::exit(1);
+    });
+    match db.prune(history_days) {
+        Ok(removed) if removed > 0 => info!("[INFO] Pruned {} poll records older than {} days", removed, history_days),
+        Ok(_) => {}
+        Err(e) => error!("[ERROR] Failed to prune SQLite history: {}", e),
+    }
+    db
+}
 
-    if should_alert {
-        if let Err(e) = send_alert(client, webhook_url, message).await {
-            eprintln!("Error: Failed to send Discord alert: {}", e);
-        } else {
-            *last_alert_time = Some(Utc::now());
+/// Writes PID_FILE, first checking for a live instance that already holds it
+fn open_pid_file(path: &str, exit_if_running: bool) -> pidfile::PidFile {
+    if let Some(pid) = pidfile::running_instance(path) {
+        warn!("[WARN] Another instance may be running (PID {} in '{}')", pid, path);
+        if exit_if_running {
+            eprintln!("Error: Refusing to start while PID {} holds '{}'", pid, path);
+            process::exit(1);
+        }
+    }
+    pidfile::PidFile::create(path).unwrap_or_else(|e| {
+        eprintln!("Error: Failed to write PID file '{}': {}", path, e);
+        process::exit(1);
+    })
+}
+
+/// Persists the state file, logging (not exiting) on failure
+fn save_state(state: &State, path: &str) {
+    if let Err(e) = state.save(path) {
+        error!("[ERROR] Failed to write state file '{}': {}", path, e);
+    }
+}
+
+/// Runs every secondary check that is due on this poll cycle
+async fn run_due_checks(
+    checks: &mut [ScheduledCheck],
+    ctx: &CheckContext<'_>,
+    cycle: u64,
+    notifier: &Notifier,
+) {
+    for scheduled in checks.iter_mut() {
+        if let Some(outcome) = scheduled.run_if_due(ctx, cycle).await {
+            report_outcome(notifier, AlertSeverity::Warn, scheduled.check.name(), outcome, &mut scheduled.alert_state).await;
         }
     }
 }
 
-/// Converts a hex string (with or without '0x' prefix) to u64
-fn parse_hex_to_u64(hex: &str) -> Result<u64, std::num::ParseIntError> {
-    let clean_hex = hex.trim_start_matches("0x");
-    u64::from_str_radix(clean_hex, 16)
+/// Warns when the poll interval is much shorter than the chain needs
+async fn check_poll_interval(config: &Config, remote_client: &reqwest::Client) {
+    let block_time = match config.chain_block_time_secs {
+        Some(block_time) => block_time,
+        None => match infer_block_time(remote_client, &config.remote_rpcs[0]).await {
+            Ok(block_time) => block_time,
+            Err(e) => {
+                debug!("[DEBUG] Could not infer block time from the remote: {}", e);
+                return;
+            }
+        },
+    };
+    if let Some(warning) = poll_interval_warning(config.poll_interval_seconds, block_time, config.lag_threshold) {
+        warn!("{}", warning);
+    }
+}
+
+/// Prints a check outcome and forwards alerts through the cooldown logic
+async fn report_outcome(notifier: &Notifier, severity: AlertSeverity, kind: &str, outcome: CheckOutcome, alert_state: &mut AlertState) {
+    match outcome {
+        CheckOutcome::Ok(line) => info!("{}", line),
+        CheckOutcome::Alert(msg) => {
+            warn!("{}", msg);
+            notifier.process_alert(severity, kind, &msg, alert_state).await;
+        }
+    }
 }
 
 /// Hides the path/query of a URL to prevent leaking API keys in logs.
 /// Input: https://eth-mainnet.alchemyapi.io/v2/SECRET
 /// Output: https://eth-mainnet.alchemyapi.io/[REDACTED]
-
 fn redact_url(url_str: &str) -> String {
     match Url::parse(url_str) {
         Ok(u) => {
@@ -241,47 +827,3 @@ fn redact_url(url_str: &str) -> String {
     }
 }
 
-
-// --- TESTS ---
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_hex_parsing_with_prefix() {
-        // 0x10a = 266
-        let input = "0x10a";
-        let result = parse_hex_to_u64(input);
-        assert_eq!(result.unwrap(), 266);
-    }
-
-    #[test]
-    fn test_hex_parsing_without_prefix() {
-        // 10a = 266
-        let input = "10a";
-        let result = parse_hex_to_u64(input);
-        assert_eq!(result.unwrap(), 266);
-    }
-
-    #[test]
-    fn test_hex_parsing_uppercase() {
-        // 0x10A = 266
-        let input = "0x10A";
-        let result = parse_hex_to_u64(input);
-        assert_eq!(result.unwrap(), 266);
-    }
-
-    #[test]
-    fn test_hex_parsing_zero() {
-        let input = "0x0";
-        let result = parse_hex_to_u64(input);
-        assert_eq!(result.unwrap(), 0);
-    }
-
-    #[test]
-    fn test_invalid_hex() {
-        let input = "0xZZZ"; // Not a hex number
-        let result = parse_hex_to_u64(input);
-        assert!(result.is_err());
-    }
-}
\ No newline at end of file
@@ -0,0 +1,103 @@
+
+// --- Node Metadata Enrichment ---
+//
+// Node metadata (region, operator, ...) often lives in a CMDB rather than in
+// the environment. With METADATA_URL set, the configured METADATA_FIELDS are
+// fetched from that JSON endpoint at startup, refreshed every
+// METADATA_REFRESH_HOURS, and appended to every log line and alert.
+
+use std::sync::RwLock;
+use std::time::Duration;
+
+use serde_json::Value;
+
+/// Rendered context, e.g. "[region=eu-west operator=acme]"; empty until fetched
+static CONTEXT: RwLock<String> = RwLock::new(String::new());
+
+/// Appends the metadata context (if any) to a log line or alert message
+pub fn annotate(message: &str) -> String {
+    let context = CONTEXT.read().unwrap();
+    if context.is_empty() {
+        message.to_string()
+    } else {
+        format!("{} {}", message, context)
+    }
+}
+
+/// Fetches the metadata once, then keeps refreshing it in the background.
+/// A failed fetch keeps the previous values
+pub async fn init(url: String, fields: Vec<String>, refresh_hours: u64) {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .expect("Failed to build metadata HTTP client");
+
+    refresh(&client, &url, &fields).await;
+    tokio::spawn(async move {
+        let period = Duration::from_secs(refresh_hours * 3600);
+        let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        loop {
+            interval.tick().await;
+            refresh(&client, &url, &fields).await;
+        }
+    });
+}
+
+async fn refresh(client: &reqwest::Client, url: &str, fields: &[String]) {
+    match fetch(client, url).await {
+        Ok(document) => {
+            let values = extract_fields(&document, fields);
+            if values.len() < fields.len() {
+                warn!("[WARN] Metadata from {} is missing some of the fields: {}", crate::redact_url(url), fields.join(","));
+            }
+            *CONTEXT.write().unwrap() = format_context(&values);
+        }
+        Err(e) => error!("[ERROR] Failed to fetch metadata from {}: {}", crate::redact_url(url), e),
+    }
+}
+
+async fn fetch(client: &reqwest::Client, url: &str) -> Result<Value, reqwest::Error> {
+    client.get(url).send().await?.error_for_status()?.json().await
+}
+
+/// Picks the configured top-level fields; strings are used unquoted
+fn extract_fields(document: &Value, fields: &[String]) -> Vec<(String, String)> {
+    fields.iter()
+        .filter_map(|field| {
+            let value = match document.get(field)? {
+                Value::String(value) => value.clone(),
+                Value::Null => return None,
+                other => other.to_string(),
+            };
+            Some((field.clone(), value))
+        })
+        .collect()
+}
+
+fn format_context(values: &[(String, String)]) -> String {
+    if values.is_empty() {
+        return String::new();
+    }
+    let pairs: Vec<String> = values.iter().map(|(field, value)| format!("{}={}", field, value)).collect();
+    format!("[{}]", pairs.join(" "))
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_configured_fields() {
+        let document = serde_json::json!({ "region": "eu-west", "operator": "acme", "validator_index": 1234, "rack": null, "owner": "ops" });
+        let fields = ["region", "validator_index", "rack", "missing"].map(String::from);
+        let values = extract_fields(&document, &fields);
+        assert_eq!(values, vec![
+            ("region".to_string(), "eu-west".to_string()),
+            ("validator_index".to_string(), "1234".to_string()),
+        ]);
+        assert_eq!(format_context(&values), "[region=eu-west validator_index=1234]");
+        assert_eq!(format_context(&[]), "");
+    }
+}
@@ -0,0 +1,406 @@
+
+// --- Prometheus Metrics ---
+//
+// Block heights, lag and a count of the alerts sent, served on METRICS_PORT
+// for Prometheus to scrape (any path works, conventionally /metrics), and
+// optionally pushed to VictoriaMetrics in the same text format. Labels from
+// PROMETHEUS_EXTRA_LABELS (e.g. a tenant) are attached to every metric.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+
+use crate::notify::AlertSeverity;
+
+/// VictoriaMetrics endpoint accepting the Prometheus text exposition format
+const VICTORIA_IMPORT_PATH: &str = "/api/v1/import/prometheus";
+
+/// Label value of the aggregate exported for the networks past MAX_METRIC_LABELS
+const OVERFLOW_LABEL: &str = "overflow";
+
+/// The `network` label values handed out so far, capped at MAX_METRIC_LABELS
+/// so a large fleet can't blow up Prometheus' series count
+struct NetworkLabels {
+    max: usize,
+    labelled: BTreeSet<String>,
+    /// Networks past the cap, with their latest known lag
+    overflowed: BTreeMap<String, Option<u64>>,
+}
+
+/// How a network's metrics are exported
+#[derive(Debug, PartialEq)]
+enum NetworkLabel<'a> {
+    Own(&'a str),
+    /// Past the cap; `first` on the poll it went over
+    Overflow { first: bool },
+}
+
+impl NetworkLabels {
+    const fn new(max: usize) -> Self {
+        NetworkLabels { max, labelled: BTreeSet::new(), overflowed: BTreeMap::new() }
+    }
+
+    fn label<'a>(&mut self, network: &'a str) -> NetworkLabel<'a> {
+        if self.labelled.contains(network) {
+            return NetworkLabel::Own(network);
+        }
+        if self.labelled.len() < self.max {
+            self.labelled.insert(network.to_string());
+            return NetworkLabel::Own(network);
+        }
+        let first = !self.overflowed.contains_key(network);
+        self.overflowed.entry(network.to_string()).or_insert(None);
+        NetworkLabel::Overflow { first }
+    }
+
+    /// Records an overflowed network's lag; returns the highest lag among them
+    fn overflow_lag(&mut self, network: &str, lag: u64) -> u64 {
+        self.overflowed.insert(network.to_string(), Some(lag));
+        self.overflowed.values().flatten().copied().max().unwrap_or(lag)
+    }
+}
+
+static NETWORK_LABELS: Mutex<NetworkLabels> = Mutex::new(NetworkLabels::new(50));
+
+/// Sets MAX_METRIC_LABELS, before the first fleet poll
+pub fn set_max_network_labels(max: usize) {
+    NETWORK_LABELS.lock().unwrap_or_else(|e| e.into_inner()).max = max;
+}
+
+/// Installs the recorder and spawns the scrape endpoint task on all interfaces.
+/// With `victoria_url`, the same metrics are also pushed every `push_interval`.
+pub fn init(port: u16, victoria_url: Option<&str>, push_interval: Duration, extra_labels: &[(String, String)]) {
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let (recorder, exporter) = match with_labels(PrometheusBuilder::new(), extra_labels).with_http_listener(addr).build() {
+        Ok(built) => built,
+        Err(e) => {
+            error!("[ERROR] Failed to start metrics endpoint on {}: {}", addr, e);
+            return;
+        }
+    };
+    let handle = recorder.handle();
+    if let Err(e) = ::metrics::set_global_recorder(recorder) {
+        error!("[ERROR] Failed to install metrics recorder: {}", e);
+        return;
+    }
+    tokio::spawn(async move {
+        if let Err(e) = exporter.await {
+            error!("[ERROR] Metrics endpoint on {} stopped: {:?}", addr, e);
+        }
+    });
+    if let Some(url) = victoria_url {
+        tokio::spawn(push_to_victoria(handle, import_url(url), push_interval));
+    }
+}
+
+fn with_labels(builder: PrometheusBuilder, labels: &[(String, String)]) -> PrometheusBuilder {
+    labels.iter().fold(builder, |builder, (name, value)| builder.add_global_label(name, value))
+}
+
+/// Parses PROMETHEUS_EXTRA_LABELS, a JSON object of string values such as
+/// `{"tenant":"acme"}`, rejecting names Prometheus wouldn't accept
+pub fn parse_extra_labels(raw: &str) -> Result<Vec<(String, String)>, String> {
+    let object: serde_json::Map<String, serde_json::Value> = serde_json::from_str(raw).map_err(|e| e.to_string())?;
+    object.into_iter().map(|(name, value)| {
+        if !is_valid_label_name(&name) {
+            return Err(format!("'{}' is not a valid label name ([a-zA-Z_][a-zA-Z0-9_]*)", name));
+        }
+        match value {
+            serde_json::Value::String(value) => Ok((name, value)),
+            other => Err(format!("value of '{}' must be a string, got {}", name, other)),
+        }
+    }).collect()
+}
+
+/// `[a-zA-Z_][a-zA-Z0-9_]*`
+fn is_valid_label_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// `VICTORIA_METRICS_URL` may be the server root or the full import endpoint
+fn import_url(base: &str) -> String {
+    let base = base.trim_end_matches('/');
+    if base.ends_with(VICTORIA_IMPORT_PATH) {
+        base.to_string()
+    } else {
+        format!("{}{}", base, VICTORIA_IMPORT_PATH)
+    }
+}
+
+/// POSTs the rendered metrics on every tick; a failed push is logged and the
+/// next one carries the current values anyway
+async fn push_to_victoria(handle: PrometheusHandle, url: String, interval: Duration) {
+    let client = reqwest::Client::builder().timeout(Duration::from_secs(10)).build().expect("static client config");
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        let result = client.post(&url).body(handle.render()).send().await.and_then(|response| response.error_for_status());
+        if let Err(e) = result {
+            warn!("[WARN] Failed to push metrics to VictoriaMetrics: {}", e);
+        }
+    }
+}
+
+/// Updates the block gauges with the heights known this poll; the lag needs both
+pub fn record_blocks(local: Option<u64>, remote: Option<u64>) {
+    if let Some(local) = local {
+        ::metrics::gauge!("eth_alive_local_block").set(local as f64);
+    }
+    if let Some(remote) = remote {
+        ::metrics::gauge!("eth_alive_remote_block").set(remote as f64);
+    }
+    if let (Some(local), Some(remote)) = (local, remote) {
+        ::metrics::gauge!("eth_alive_block_lag").set(remote.saturating_sub(local) as f64);
+        crate::cloudwatch::record_lag(None, remote.saturating_sub(local));
+    }
+}
+
+/// Per-network variant of `record_blocks` for the NODES fleet. Networks past
+/// MAX_METRIC_LABELS get no gauges of their own: each counts once in
+/// eth_alive_metric_label_overflow_total, and only the highest lag among them
+/// is exported, as `eth_alive_block_lag{network="overflow"}`
+pub fn record_network_blocks(name: &str, local: Option<u64>, remote: Option<u64>) {
+    record_network_blocks_in(&mut NETWORK_LABELS.lock().unwrap_or_else(|e| e.into_inner()), name, local, remote);
+}
+
+fn record_network_blocks_in(labels: &mut NetworkLabels, name: &str, local: Option<u64>, remote: Option<u64>) {
+    let lag = local.zip(remote).map(|(local, remote)| remote.saturating_sub(local));
+    if let Some(lag) = lag {
+        crate::cloudwatch::record_lag(Some(name), lag);
+    }
+    match labels.label(name) {
+        NetworkLabel::Own(network) => {
+            if let Some(local) = local {
+                ::metrics::gauge!("eth_alive_local_block", "network" => network.to_string()).set(local as f64);
+            }
+            if let Some(remote) = remote {
+                ::metrics::gauge!("eth_alive_remote_block", "network" => network.to_string()).set(remote as f64);
+            }
+            if let Some(lag) = lag {
+                ::metrics::gauge!("eth_alive_block_lag", "network" => network.to_string()).set(lag as f64);
+            }
+        }
+        NetworkLabel::Overflow { first } => {
+            if first {
+                warn!("[WARN] MAX_METRIC_LABELS reached, '{}' is only counted in the network=\"{}\" lag", name, OVERFLOW_LABEL);
+                ::metrics::counter!("eth_alive_metric_label_overflow_total").increment(1);
+            }
+            if let Some(lag) = lag {
+                let highest = labels.overflow_lag(name, lag);
+                ::metrics::gauge!("eth_alive_block_lag", "network" => OVERFLOW_LABEL).set(highest as f64);
+            }
+        }
+    }
+}
+
+/// Updates the balance gauge of a monitored validator
+pub fn record_validator_balance(index: u64, balance_gwei: i64) {
+    ::metrics::gauge!("eth_alive_validator_balance_gwei", "index" => index.to_string()).set(balance_gwei as f64);
+}
+
+/// 1 when the latest RPC response carried `"jsonrpc": "2.0"`, 0 otherwise
+pub fn record_jsonrpc_version(valid: bool) {
+    ::metrics::gauge!("eth_alive_jsonrpc_version_valid").set(if valid { 1.0 } else { 0.0 });
+}
+
+/// Share of the last sample window the DATA_DIR disk was busy
+pub fn record_disk_io_utilization(pct: f64) {
+    ::metrics::gauge!("eth_alive_disk_io_utilization_pct").set(pct);
+}
+
+/// Megabytes per second written to the DATA_DIR disk over the last sample window
+pub fn record_disk_write_throughput(mb_per_sec: f64) {
+    ::metrics::gauge!("eth_alive_disk_write_throughput_mb_per_sec").set(mb_per_sec);
+}
+
+/// 'eth_protocolVersion', reported as hex ("0x44") or decimal; other forms are skipped
+pub fn record_protocol_version(version: &str) {
+    let parsed = match version.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => version.parse().ok(),
+    };
+    if let Some(version) = parsed {
+        ::metrics::gauge!("eth_alive_protocol_version").set(version as f64);
+    }
+}
+
+/// Peer connections by direction, from 'admin_peers'
+pub fn record_peers(inbound: u64, outbound: u64) {
+    ::metrics::gauge!("eth_alive_peers_inbound").set(inbound as f64);
+    ::metrics::gauge!("eth_alive_peers_outbound").set(outbound as f64);
+}
+
+/// Counts a poll whose reference block number came from `url`
+pub fn record_remote_selected(url: &str) {
+    ::metrics::counter!("eth_alive_remote_selected_total", "remote" => crate::redact_url(url)).increment(1);
+}
+
+/// EIP-4844 blobs in the latest block
+pub fn record_blob_count(blobs: u64) {
+    ::metrics::gauge!("eth_alive_blob_count_latest").set(blobs as f64);
+}
+
+/// Rolling average of the median priority fee reward, in gwei
+pub fn record_reward_average(average_wei: f64) {
+    ::metrics::gauge!("eth_alive_block_reward_average_gwei").set(average_wei / 1e9);
+}
+
+/// p99 size of the local `eth_blockNumber` response bodies
+pub fn record_response_size_p99(bytes: usize) {
+    ::metrics::gauge!("eth_alive_response_size_bytes_p99").set(bytes as f64);
+}
+
+/// eth-alive's own resident memory and CPU time (whole seconds, as counters count up)
+pub fn record_self_usage(memory_rss_bytes: u64, cpu_seconds: f64) {
+    ::metrics::gauge!("eth_alive_self_memory_rss_bytes").set(memory_rss_bytes as f64);
+    ::metrics::counter!("eth_alive_self_cpu_seconds_total").absolute(cpu_seconds as u64);
+}
+
+/// 'eth_syncing' progress, 100 once synced
+pub fn record_sync_progress(pct: f64) {
+    ::metrics::gauge!("eth_alive_sync_progress_pct").set(pct);
+}
+
+/// Transactions of a MONITOR_PENDING_ADDRESS or WATCH_NONCE_ADDRESS pending
+/// ahead of its mined nonce
+pub fn record_pending_nonces(address: &str, gap: u64) {
+    ::metrics::gauge!("eth_alive_pending_nonces", "address" => address.to_string()).set(gap as f64);
+}
+
+/// Counts an alert that was delivered
+pub fn record_alert(severity: AlertSeverity) {
+    ::metrics::counter!("eth_alive_alerts_total", "severity" => severity.label()).increment(1);
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_victoria_import_url() {
+        assert_eq!(import_url("http://vm:8428"), "http://vm:8428/api/v1/import/prometheus");
+        assert_eq!(import_url("http://vm:8428/"), "http://vm:8428/api/v1/import/prometheus");
+        assert_eq!(import_url("http://vm:8428/api/v1/import/prometheus"), "http://vm:8428/api/v1/import/prometheus");
+    }
+
+    #[test]
+    fn test_metrics_rendered_for_scrape() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        ::metrics::with_local_recorder(&recorder, || {
+            record_blocks(Some(100), Some(103));
+            record_blocks(None, Some(104));
+            record_alert(AlertSeverity::Critical);
+            record_alert(AlertSeverity::Critical);
+        });
+
+        let output = handle.render();
+        assert!(output.contains("eth_alive_local_block 100"));
+        assert!(output.contains("eth_alive_remote_block 104"));
+        // Not updated while the local node is down
+        assert!(output.contains("eth_alive_block_lag 3"));
+        assert!(output.contains("eth_alive_alerts_total{severity=\"critical\"} 2"));
+    }
+
+    #[test]
+    fn test_check_metrics_rendered() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        ::metrics::with_local_recorder(&recorder, || {
+            record_protocol_version("0x44");
+            record_protocol_version("eth/68");
+            record_peers(20, 5);
+            record_remote_selected("https://rpc.example.com/v2/secret-key");
+            record_remote_selected("https://rpc.example.com/v2/secret-key");
+            record_blob_count(6);
+            record_reward_average(2.5e9);
+            record_response_size_p99(52);
+            record_self_usage(48 * 1024 * 1024, 12.7);
+            record_sync_progress(99.5);
+            record_pending_nonces("0xabc", 3);
+        });
+
+        let output = handle.render();
+        assert!(output.contains("eth_alive_protocol_version 68"));
+        assert!(output.contains("eth_alive_peers_inbound 20"));
+        assert!(output.contains("eth_alive_peers_outbound 5"));
+        // The selection counter is labelled by the redacted URL, never the key
+        assert!(output.contains("eth_alive_remote_selected_total{remote=\"https://rpc.example.com/[REDACTED]\"} 2"), "{}", output);
+        assert!(!output.contains("secret-key"));
+        assert!(output.contains("eth_alive_blob_count_latest 6"));
+        assert!(output.contains("eth_alive_block_reward_average_gwei 2.5"));
+        assert!(output.contains("eth_alive_response_size_bytes_p99 52"));
+        assert!(output.contains("eth_alive_self_memory_rss_bytes 50331648"));
+        assert!(output.contains("eth_alive_self_cpu_seconds_total 12"));
+        assert!(output.contains("eth_alive_sync_progress_pct 99.5"));
+        assert!(output.contains("eth_alive_pending_nonces{address=\"0xabc\"} 3"));
+    }
+
+    #[test]
+    fn test_network_labels_capped() {
+        let mut labels = NetworkLabels::new(2);
+        assert_eq!(labels.label("mainnet"), NetworkLabel::Own("mainnet"));
+        assert_eq!(labels.label("holesky"), NetworkLabel::Own("holesky"));
+        assert_eq!(labels.label("sepolia"), NetworkLabel::Overflow { first: true });
+        assert_eq!(labels.label("sepolia"), NetworkLabel::Overflow { first: false });
+        assert_eq!(labels.label("mainnet"), NetworkLabel::Own("mainnet"));
+    }
+
+    #[test]
+    fn test_overflowed_networks_share_only_the_max_lag() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        let mut labels = NetworkLabels::new(1);
+        let mut render = |polls: &[(&str, u64, u64)]| {
+            ::metrics::with_local_recorder(&recorder, || {
+                for (name, local, remote) in polls {
+                    record_network_blocks_in(&mut labels, name, Some(*local), Some(*remote));
+                }
+            });
+            handle.render()
+        };
+
+        let output = render(&[("mainnet", 100, 101), ("sepolia", 5_000, 5_007), ("holesky", 900, 902)]);
+        assert!(output.contains("eth_alive_local_block{network=\"mainnet\"} 100"));
+        assert!(!output.contains("eth_alive_local_block{network=\"overflow\"}"), "{}", output);
+        assert!(!output.contains("eth_alive_remote_block{network=\"overflow\"}"));
+        assert!(output.contains("eth_alive_block_lag{network=\"overflow\"} 7"));
+        assert!(output.contains("eth_alive_metric_label_overflow_total 2"));
+
+        // The highest lag follows whichever overflowed network is furthest behind
+        let output = render(&[("sepolia", 5_007, 5_007), ("holesky", 902, 902), ("holesky", 903, 905)]);
+        assert!(output.contains("eth_alive_block_lag{network=\"overflow\"} 2"), "{}", output);
+        assert!(output.contains("eth_alive_metric_label_overflow_total 2"));
+    }
+
+    #[test]
+    fn test_extra_labels_on_every_metric() {
+        let labels = parse_extra_labels(r#"{"tenant":"acme","region":"us-east"}"#).unwrap();
+        let recorder = with_labels(PrometheusBuilder::new(), &labels).build_recorder();
+        let handle = recorder.handle();
+        ::metrics::with_local_recorder(&recorder, || {
+            record_blocks(Some(100), None);
+            record_alert(AlertSeverity::Warn);
+        });
+
+        let output = handle.render();
+        assert!(output.contains("eth_alive_local_block{region=\"us-east\",tenant=\"acme\"} 100"), "{}", output);
+        assert!(output.lines().filter(|line| line.starts_with("eth_alive_alerts_total")).all(|line| line.contains("tenant=\"acme\"")));
+    }
+
+    #[test]
+    fn test_extra_label_names_validated() {
+        assert!(parse_extra_labels(r#"{"_tenant2":"a"}"#).is_ok());
+        assert!(parse_extra_labels(r#"{"2tenant":"a"}"#).unwrap_err().contains("'2tenant'"));
+        assert!(parse_extra_labels(r#"{"tenant-id":"a"}"#).is_err());
+        assert!(parse_extra_labels(r#"{"tenant":1}"#).is_err());
+        assert!(parse_extra_labels(r#"["tenant"]"#).is_err());
+    }
+}
@@ -0,0 +1,372 @@
+
+// --- Node Fleet ---
+//
+// NODES lists independent local/remote pairs watched by one daemon, e.g. one
+// per network. Unlike a group, every pair is judged (and alerts) on its own,
+// polled by its own task with its own stall tracking and cooldown; alert
+// messages are prefixed with the node's name.
+
+use std::collections::HashMap;
+use std::fs;
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use tokio::sync::{watch, Semaphore};
+
+use crate::notify::{AlertSeverity, AlertState, Notifier};
+use crate::rpc::fetch_block_number;
+use crate::stall::StallTracker;
+
+/// A node pair as read from NODES
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct NodeConfig {
+    pub name: String,
+    pub local_rpc: String,
+    pub remote_rpc: String,
+    /// Overrides LAG_THRESHOLD for this pair
+    pub lag_threshold: Option<u64>,
+    /// Overrides DISCORD_WEBHOOK_URL for this pair
+    pub discord_webhook: Option<String>,
+    /// Overrides POLL_INTERVAL_SECONDS for this pair
+    pub poll_interval_seconds: Option<u64>,
+}
+
+/// The TOML form: one `[[nodes]]` (or `[[network]]`) table per pair
+#[derive(Deserialize)]
+struct NodesFile {
+    #[serde(alias = "network")]
+    nodes: Vec<NodeConfig>,
+}
+
+/// Parses NODES, given either as a JSON array or as TOML `[[nodes]]` tables,
+/// and validates the pairs
+pub fn parse_nodes_config(input: &str) -> Result<Vec<NodeConfig>, String> {
+    // `[[nodes]]` opens a TOML array of tables, a lone `[` a JSON array
+    let input = input.trim_start();
+    let nodes = if input.starts_with('[') && !input.starts_with("[[") {
+        serde_json::from_str::<Vec<NodeConfig>>(input).map_err(|e| e.to_string())?
+    } else {
+        toml::from_str::<NodesFile>(input).map_err(|e| e.to_string())?.nodes
+    };
+
+    if nodes.is_empty() {
+        return Err("no nodes defined".to_string());
+    }
+    for (i, node) in nodes.iter().enumerate() {
+        if node.name.trim().is_empty() {
+            return Err(format!("node #{} has no name", i + 1));
+        }
+        if nodes[..i].iter().any(|other| other.name == node.name) {
+            return Err(format!("node name '{}' is used more than once", node.name));
+        }
+        if node.local_rpc.is_empty() || node.remote_rpc.is_empty() {
+            return Err(format!("node '{}' needs both local_rpc and remote_rpc", node.name));
+        }
+        if node.poll_interval_seconds == Some(0) {
+            return Err(format!("node '{}' has a poll_interval_seconds of 0", node.name));
+        }
+    }
+    Ok(nodes)
+}
+
+/// NODES may hold the configuration itself or the path of a file containing it
+pub fn read_nodes_config(value: &str) -> Result<Vec<NodeConfig>, String> {
+    if Path::new(value).is_file() {
+        let contents = fs::read_to_string(value).map_err(|e| format!("{}: {}", value, e))?;
+        return parse_nodes_config(&contents);
+    }
+    parse_nodes_config(value)
+}
+
+/// A pair together with its own notifier (when the webhook is overridden),
+/// stall tracker and cooldown state
+pub struct NodeState {
+    pub config: NodeConfig,
+    pub notifier: Option<Notifier>,
+    pub stall: StallTracker,
+    pub alert_state: AlertState,
+    pub stall_alert: AlertState,
+}
+
+/// Builds the per-node state, keyed by node name
+pub fn node_states(nodes: Vec<NodeConfig>, base: &crate::config::Config) -> HashMap<String, NodeState> {
+    nodes.into_iter().map(|config| {
+        let state = NodeState {
+            notifier: config.discord_webhook.as_deref().map(|url| Notifier::for_webhook(base, url)),
+            stall: StallTracker::new(base.stall_threshold_seconds),
+            alert_state: AlertState::default(),
+            stall_alert: AlertState::default(),
+            config,
+        };
+        (state.config.name.clone(), state)
+    }).collect()
+}
+
+/// What a poll of one pair found
+#[derive(Debug, PartialEq)]
+enum NodeOutcome {
+    Synced { local: u64, lag: u64 },
+    Ahead { local: u64, remote: u64 },
+    Lagging { local: u64, remote: u64, lag: u64 },
+    Down { remote: u64, error: String },
+    RemoteFailed(String),
+}
+
+fn node_outcome(local: Result<u64, String>, remote: Result<u64, String>, lag_threshold: u64) -> NodeOutcome {
+    match (remote, local) {
+        (Err(e), _) => NodeOutcome::RemoteFailed(e),
+        (Ok(remote), Err(error)) => NodeOutcome::Down { remote, error },
+        (Ok(remote), Ok(local)) if local > remote => NodeOutcome::Ahead { local, remote },
+        (Ok(remote), Ok(local)) if remote - local >= lag_threshold => NodeOutcome::Lagging { local, remote, lag: remote - local },
+        (Ok(remote), Ok(local)) => NodeOutcome::Synced { local, lag: remote - local },
+    }
+}
+
+/// Caps how many pairs are fetching at the same time across the fleet
+/// (FLEET_MAX_CONCURRENT_CHECKS); further polls wait for a free slot
+#[derive(Clone)]
+pub struct ConcurrencyLimit(Arc<Semaphore>);
+
+impl ConcurrencyLimit {
+    pub fn new(max_concurrent: usize) -> Self {
+        ConcurrencyLimit(Arc::new(Semaphore::new(max_concurrent)))
+    }
+
+    /// Runs `task` once a slot is free, holding the slot until it completes
+    pub async fn run<F: Future>(&self, task: F) -> F::Output {
+        let _permit = self.0.acquire().await.expect("fleet semaphore is never closed");
+        task.await
+    }
+}
+
+impl NodeState {
+    /// Polls the local and remote side of the pair and alerts on problems.
+    /// Only the RPC calls count against `limit`, so a slow webhook doesn't
+    /// hold up other pairs
+    pub async fn poll(&mut self, local_client: &reqwest::Client, remote_client: &reqwest::Client, notifier: &Notifier, limit: &ConcurrencyLimit, defaults: &FleetDefaults) {
+        let (local, remote) = limit.run(async {
+            tokio::join!(
+                fetch_block_number(local_client, &self.config.local_rpc),
+                fetch_block_number(remote_client, &self.config.remote_rpc),
+            )
+        }).await;
+        let local = local.map_err(|e| e.to_string());
+        let remote = remote.map_err(|e| e.to_string());
+        let notifier = self.notifier.as_ref().unwrap_or(notifier);
+        let name = &self.config.name;
+        crate::metrics::record_network_blocks(name, local.as_ref().ok().copied(), remote.as_ref().ok().copied());
+
+        if let Ok(block) = local && let Some(stalled) = self.stall.observe(block, Utc::now()) {
+            let msg = format!("[{}] {}", name, tr!("node_stalled", block = block, seconds = stalled.num_seconds()));
+            error!(local_block = block; "{}", msg);
+            notifier.process_alert(AlertSeverity::Critical, "node_stalled", &msg, &mut self.stall_alert).await;
+        }
+
+        match node_outcome(local, remote, defaults.lag_threshold_at(&self.config, Utc::now())) {
+            NodeOutcome::Synced { local, lag } => {
+                info!(local_block = local, lag = lag; "[OK] [{}] Synced | Block: {} | Lag: {}", name, local, lag);
+                self.alert_state.last_alert_time = None;
+            }
+            NodeOutcome::Ahead { local, remote } => {
+                info!(local_block = local, remote_block = remote; "[INFO] [{}] Local is ahead | Local: {} | Remote: {}", name, local, remote);
+            }
+            NodeOutcome::Lagging { local, remote, lag } => {
+                let msg = format!("[{}] {}", name, tr!("node_lagging", local = local, remote = remote, lag = lag));
+                warn!(local_block = local, remote_block = remote, lag = lag; "{}", msg);
+                notifier.process_alert(AlertSeverity::Warn, "node_lagging", &msg, &mut self.alert_state).await;
+            }
+            NodeOutcome::Down { remote, error } => {
+                let msg = format!("[{}] {}", name, tr!("local_node_down", error = error));
+                error!(remote_block = remote, error = error.clone(); "{}", msg);
+                notifier.process_alert(AlertSeverity::Critical, "local_node_down", &msg, &mut self.alert_state).await;
+            }
+            NodeOutcome::RemoteFailed(e) => {
+                error!(error = e.clone(); "[ERROR] [{}] FAILED to fetch Remote RPC: {}", name, e);
+            }
+        }
+    }
+}
+
+/// Settings a pair falls back to when its config doesn't override them
+#[derive(Clone, Copy)]
+pub struct FleetDefaults {
+    pub lag_threshold: u64,
+    /// Scales the pairs' own thresholds as well
+    pub weekend_lag_threshold_multiplier: f64,
+    pub poll_interval_seconds: u64,
+}
+
+impl FleetDefaults {
+    /// The threshold `node` is judged by at `now`: its own or LAG_THRESHOLD,
+    /// times WEEKEND_LAG_THRESHOLD_MULTIPLIER on weekends
+    pub fn lag_threshold_at(&self, node: &NodeConfig, now: DateTime<Utc>) -> u64 {
+        crate::config::lag_threshold_at(node.lag_threshold.unwrap_or(self.lag_threshold), self.weekend_lag_threshold_multiplier, now)
+    }
+}
+
+/// Spawns one polling task per pair, each at its own interval, with the local
+/// and remote clients from `clients` (shared, or one pool per pair with
+/// DEDICATED_CLIENTS), fetching at most `limit` pairs at once. The tasks stop
+/// once `shutdown` turns true and return how many polls they made.
+pub fn spawn_nodes(
+    states: HashMap<String, NodeState>,
+    clients: impl Fn() -> (reqwest::Client, reqwest::Client),
+    notifier: Arc<Notifier>,
+    limit: ConcurrencyLimit,
+    defaults: FleetDefaults,
+    shutdown: watch::Receiver<bool>,
+) -> Vec<tokio::task::JoinHandle<u64>> {
+    states.into_values().map(|mut state| {
+        let (local_client, remote_client) = clients();
+        let (notifier, limit, mut shutdown) = (notifier.clone(), limit.clone(), shutdown.clone());
+        let interval = Duration::from_secs(state.config.poll_interval_seconds.unwrap_or(defaults.poll_interval_seconds));
+        tokio::spawn(async move {
+            let mut polls = 0;
+            while !*shutdown.borrow() {
+                state.poll(&local_client, &remote_client, &notifier, &limit, &defaults).await;
+                polls += 1;
+                tokio::select! {
+                    _ = tokio::time::sleep(interval) => {}
+                    _ = shutdown.changed() => {}
+                }
+            }
+            polls
+        })
+    }).collect()
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_nodes_json() {
+        let input = r#"[
+            {"name": "mainnet", "local_rpc": "http://a:8545", "remote_rpc": "https://r1", "lag_threshold": 5},
+            {"name": "holesky", "local_rpc": "http://b:8545", "remote_rpc": "https://r2", "discord_webhook": "https://hook"}
+        ]"#;
+        let nodes = parse_nodes_config(input).unwrap();
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[0], NodeConfig {
+            name: "mainnet".to_string(),
+            local_rpc: "http://a:8545".to_string(),
+            remote_rpc: "https://r1".to_string(),
+            lag_threshold: Some(5),
+            discord_webhook: None,
+            poll_interval_seconds: None,
+        });
+        assert_eq!(nodes[1].lag_threshold, None);
+        assert_eq!(nodes[1].discord_webhook.as_deref(), Some("https://hook"));
+    }
+
+    #[test]
+    fn test_parse_nodes_toml() {
+        let input = r#"
+            [[nodes]]
+            name = "mainnet"
+            local_rpc = "http://a:8545"
+            remote_rpc = "https://r1"
+
+            [[nodes]]
+            name = "holesky"
+            local_rpc = "http://b:8545"
+            remote_rpc = "https://r2"
+            lag_threshold = 10
+        "#;
+        let nodes = parse_nodes_config(input).unwrap();
+        assert_eq!(nodes.iter().map(|node| node.name.as_str()).collect::<Vec<_>>(), vec!["mainnet", "holesky"]);
+        assert_eq!(nodes[1].lag_threshold, Some(10));
+    }
+
+    #[test]
+    fn test_parse_network_sections() {
+        let input = r#"
+            # One process for every network
+            [[network]]
+            name = "mainnet"
+            local_rpc = "http://a:8545"
+            remote_rpc = "https://r1"
+
+            [[network]]
+            name = "devnet"
+            local_rpc = "http://localhost:8545"
+            remote_rpc = "http://localhost:9545"
+            poll_interval_seconds = 5
+        "#;
+        let nodes = parse_nodes_config(input).unwrap();
+        assert_eq!(nodes.iter().map(|node| node.name.as_str()).collect::<Vec<_>>(), vec!["mainnet", "devnet"]);
+        assert_eq!(nodes[1].poll_interval_seconds, Some(5));
+        assert!(parse_nodes_config(&input.replace("= 5", "= 0")).unwrap_err().contains("poll_interval_seconds"));
+    }
+
+    #[test]
+    fn test_invalid_nodes_are_rejected() {
+        assert!(parse_nodes_config("[]").is_err());
+        assert!(parse_nodes_config("not a config").is_err());
+        // Missing remote_rpc
+        assert!(parse_nodes_config(r#"[{"name": "a", "local_rpc": "http://a"}]"#).is_err());
+        let duplicate = r#"[
+            {"name": "a", "local_rpc": "http://a", "remote_rpc": "https://r"},
+            {"name": "a", "local_rpc": "http://b", "remote_rpc": "https://r"}
+        ]"#;
+        assert!(parse_nodes_config(duplicate).unwrap_err().contains("more than once"));
+        assert!(parse_nodes_config(r#"[{"name": " ", "local_rpc": "http://a", "remote_rpc": "https://r"}]"#).is_err());
+    }
+
+    #[test]
+    fn test_read_nodes_config_from_file() {
+        let path = std::env::temp_dir().join(format!("eth-alive-nodes-{}.toml", std::process::id()));
+        fs::write(&path, "[[nodes]]\nname = \"a\"\nlocal_rpc = \"http://a\"\nremote_rpc = \"https://r\"\n").unwrap();
+        let nodes = read_nodes_config(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(nodes[0].name, "a");
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limit_caps_running_tasks() {
+        let limit = ConcurrencyLimit::new(2);
+        let running = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let peak = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let tasks = (0..6).map(|_| {
+            let (limit, running, peak) = (limit.clone(), running.clone(), peak.clone());
+            tokio::spawn(async move {
+                limit.run(async {
+                    let now = running.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, std::sync::atomic::Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    running.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+                }).await
+            })
+        });
+        futures::future::join_all(tasks).await;
+        assert_eq!(peak.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_fleet_lag_threshold_on_weekends() {
+        let defaults = FleetDefaults { lag_threshold: 3, weekend_lag_threshold_multiplier: 2.0, poll_interval_seconds: 60 };
+        let mut node = parse_nodes_config(r#"[{"name": "mainnet", "local_rpc": "http://a:8545", "remote_rpc": "https://r1"}]"#).unwrap().remove(0);
+        let friday = DateTime::parse_from_rfc3339("2024-05-03T12:00:00Z").unwrap().with_timezone(&Utc);
+        let saturday = DateTime::parse_from_rfc3339("2024-05-04T12:00:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(defaults.lag_threshold_at(&node, friday), 3);
+        assert_eq!(defaults.lag_threshold_at(&node, saturday), 6);
+        node.lag_threshold = Some(5);
+        assert_eq!(defaults.lag_threshold_at(&node, friday), 5);
+        assert_eq!(defaults.lag_threshold_at(&node, saturday), 10);
+    }
+
+    #[test]
+    fn test_node_outcome() {
+        assert_eq!(node_outcome(Ok(100), Ok(101), 3), NodeOutcome::Synced { local: 100, lag: 1 });
+        assert_eq!(node_outcome(Ok(100), Ok(103), 3), NodeOutcome::Lagging { local: 100, remote: 103, lag: 3 });
+        assert_eq!(node_outcome(Ok(105), Ok(103), 3), NodeOutcome::Ahead { local: 105, remote: 103 });
+        assert_eq!(node_outcome(Err("refused".into()), Ok(103), 3), NodeOutcome::Down { remote: 103, error: "refused".into() });
+        assert_eq!(node_outcome(Ok(100), Err("timeout".into()), 3), NodeOutcome::RemoteFailed("timeout".into()));
+    }
+}
@@ -0,0 +1,513 @@
+
+// --- Notifications ---
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use tokio::sync::Semaphore;
+
+use crate::config::Config;
+use crate::error::AppError;
+use crate::plugins::{webhook_plugin, BoxedPlugin, NotificationPlugin, WebhookTarget};
+
+/// Header carrying the HMAC-SHA256 signature of the request body
+pub const SIGNATURE_HEADER: &str = "X-Eth-Alive-Signature";
+
+/// Header telling WEBHOOK_PROXY_URL where to forward the alert
+pub const TARGET_WEBHOOK_HEADER: &str = "X-Target-Webhook";
+
+/// Template used for generic webhooks when WEBHOOK_TEMPLATE isn't set
+const DEFAULT_WEBHOOK_TEMPLATE: &str = r#"{"message":"{message}"}"#;
+
+/// Payload format of the webhook, detected from its URL
+#[derive(Clone, Debug, PartialEq)]
+pub enum NotificationBackend {
+    Discord,
+    Slack,
+    /// JSON body from WEBHOOK_TEMPLATE, with `{message}` replaced by the
+    /// JSON-escaped alert, `{severity}` and `{kind}` by its severity and kind
+    /// (e.g. for PagerDuty or Opsgenie)
+    Generic { template: String },
+}
+
+impl NotificationBackend {
+    /// hooks.slack.com is Slack, discord.com/api/webhooks is Discord, anything else is generic
+    pub fn detect(webhook_url: &str, template: Option<String>) -> Self {
+        let Ok(url) = url::Url::parse(webhook_url) else {
+            return NotificationBackend::Discord;
+        };
+        match url.host_str() {
+            Some("hooks.slack.com") => NotificationBackend::Slack,
+            Some("discord.com" | "discordapp.com") if url.path().starts_with("/api/webhooks") => NotificationBackend::Discord,
+            _ => NotificationBackend::Generic { template: template.unwrap_or_else(|| DEFAULT_WEBHOOK_TEMPLATE.to_string()) },
+        }
+    }
+}
+
+/// One alert, as handed to the notification plugins
+#[derive(Clone, Debug)]
+pub struct Alert {
+    pub severity: AlertSeverity,
+    /// e.g. "local_node_down"
+    pub kind: String,
+    pub message: String,
+}
+
+impl Alert {
+    pub fn new(severity: AlertSeverity, kind: &str, message: &str) -> Self {
+        Alert { severity, kind: kind.to_string(), message: message.to_string() }
+    }
+}
+
+/// Delivers alerts to the configured plugins.
+/// Plugins use their own HTTP client so a slow webhook can't stall RPC monitoring.
+pub struct Notifier {
+    plugins: Vec<Box<dyn NotificationPlugin + Send + Sync>>,
+    timeout: Duration,
+    cooldown: chrono::Duration,
+    dedupe: bool,
+    /// Caps simultaneous alert deliveries; further alerts wait for a free slot
+    connections: Semaphore,
+    /// Minimum gap between two sends to the same plugin, and when each
+    /// plugin's last send started (or is scheduled to)
+    min_interval: Duration,
+    last_send: Mutex<HashMap<&'static str, Instant>>,
+}
+
+impl Notifier {
+    /// Takes over the alert channels configured in `config`
+    pub fn new(config: &mut Config) -> Self {
+        let plugins = std::mem::take(&mut config.notification_plugins);
+        Notifier::with_plugins(config, plugins)
+    }
+
+    /// Same settings as `new`, but delivering only to another webhook (a NODES override)
+    pub fn for_webhook(config: &Config, webhook_url: &str) -> Self {
+        let target = WebhookTarget {
+            client: webhook_client(config.webhook_timeout_secs),
+            url: webhook_url.to_string(),
+            proxy_url: config.webhook_proxy_url.clone(),
+            hmac_secret: config.webhook_hmac_secret.clone(),
+        };
+        let plugins = if webhook_enabled(webhook_url) { vec![webhook_plugin(&config.notification_backend, target)] } else { Vec::new() };
+        Notifier::with_plugins(config, plugins)
+    }
+
+    fn with_plugins(config: &Config, plugins: Vec<Box<dyn NotificationPlugin + Send + Sync>>) -> Self {
+        Notifier {
+            plugins,
+            timeout: Duration::from_secs(config.webhook_timeout_secs),
+            cooldown: chrono::Duration::minutes(config.alert_cooldown_minutes as i64),
+            dedupe: config.alert_dedupe,
+            connections: Semaphore::new(config.max_concurrent_webhooks),
+            min_interval: Duration::from_millis(config.min_webhook_interval_ms),
+            last_send: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sends an alert to every plugin. Succeeds if at least one delivered it;
+    /// the failures of the others are logged
+    pub async fn send_alert(&self, alert: &Alert) -> Result<(), AppError> {
+        if self.plugins.is_empty() {
+            return Ok(());
+        }
+        let alert = Alert { message: crate::metadata::annotate(&alert.message), ..alert.clone() };
+
+        let _permit = self.connections.acquire().await.expect("webhook semaphore is never closed");
+
+        let mut delivered = false;
+        let mut last_error = None;
+        for plugin in &self.plugins {
+            // Bursts are spaced out to stay under each webhook's rate limit. The
+            // slot is reserved before sleeping, so concurrent sends queue up behind it
+            let delay = {
+                let mut last_send = self.last_send.lock().unwrap();
+                let now = Instant::now();
+                let delay = send_delay(last_send.get(plugin.name()).copied(), now, self.min_interval);
+                last_send.insert(plugin.name(), now + delay);
+                delay
+            };
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+            let started = Instant::now();
+            let result = plugin.send(&alert).await;
+            let elapsed = started.elapsed();
+            if elapsed > self.timeout / 2 {
+                warn!("[WARN] {} slow: took {} ms (timeout {} s)", plugin.name(), elapsed.as_millis(), self.timeout.as_secs());
+            }
+            match result {
+                Ok(()) => delivered = true,
+                Err(e) => {
+                    if self.plugins.len() > 1 {
+                        error!("Error: {} failed to deliver alert: {}", plugin.name(), e);
+                    }
+                    last_error = Some(e);
+                }
+            }
+        }
+        match last_error {
+            Some(e) if !delivered => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks cooldown (and with ALERT_DEDUPE, repeat) logic and sends an alert if
+    /// necessary. Updates the alert state. Returns true if the alert was sent
+    pub async fn process_alert(&self, severity: AlertSeverity, kind: &str, message: &str, state: &mut AlertState) -> bool {
+        // Check if we should alert
+        let should_alert = match state.last_alert_time {
+            None => true,
+            Some(last) => Utc::now() - last > self.cooldown,
+        };
+
+        // The cooldown resets on recovery, so a flapping node would resend the same alert
+        let hash = alert_hash(kind, message);
+        if !should_alert || (self.dedupe && state.last_alert_hash == Some(hash)) {
+            return false;
+        }
+        if let Some(incident) = crate::status_page::known_incident() {
+            info!("[INFO] Alerts suppressed: known chain incident: {}", incident);
+            return false;
+        }
+
+        if let Err(e) = self.send_alert(&Alert::new(severity, kind, message)).await {
+            error!("Error: Failed to send alert: {}", e);
+            false
+        } else {
+            state.last_alert_time = Some(Utc::now());
+            state.last_alert_hash = Some(hash);
+            crate::metrics::record_alert(severity);
+            crate::cloudwatch::record_alert(kind);
+            true
+        }
+    }
+}
+
+/// HTTP client for alert deliveries, separate from the RPC clients
+pub fn webhook_client(timeout_secs: u64) -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(timeout_secs))
+        .build()
+        .expect("Failed to build webhook HTTP client")
+}
+
+/// How serious an alert is, as counted in metrics
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AlertSeverity {
+    Warn,
+    Critical,
+}
+
+impl AlertSeverity {
+    pub fn label(self) -> &'static str {
+        match self {
+            AlertSeverity::Warn => "warn",
+            AlertSeverity::Critical => "critical",
+        }
+    }
+}
+
+/// Cooldown and deduplication state of one alert source
+#[derive(Default)]
+pub struct AlertState {
+    pub last_alert_time: Option<DateTime<Utc>>,
+    /// Content hash of the last alert sent; kept across recoveries
+    pub last_alert_hash: Option<[u8; 32]>,
+}
+
+/// SHA256(message + alert_kind)
+fn alert_hash(kind: &str, message: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(message.as_bytes());
+    hasher.update(kind.as_bytes());
+    hasher.finalize().into()
+}
+
+/// Block number used in test alerts, in the range of a mainnet head
+const TEST_BLOCK: u64 = 21_000_000;
+
+/// Alert kinds accepted by `test-alert`
+pub const TEST_ALERT_KINDS: &[&str] = &["node_lagging", "local_node_down", "local_ahead", "lag_exit", "low_peer_count"];
+
+/// A realistic alert of the given kind, marked `[TEST]` and timestamped.
+/// None for an unknown kind
+pub fn test_alert_message(kind: &str, now: DateTime<Utc>) -> Option<String> {
+    let (local, remote) = (TEST_BLOCK, TEST_BLOCK + 5);
+    let message = match kind {
+        "node_lagging" => tr!("node_lagging", local = local, remote = remote, lag = 5),
+        "local_node_down" => tr!("local_node_down", error = "error sending request for url (http://localhost:8545/): connection refused"),
+        "local_ahead" => tr!("local_ahead", lead = 15, local = remote + 10, remote = local),
+        "lag_exit" => tr!("lag_exit", local = local, remote = local + 1000, lag = 1000),
+        "low_peer_count" => tr!("low_peer_count", peers = 2, min = 5),
+        _ => return None,
+    };
+    Some(format!("[TEST] {} {}", now.format("%Y-%m-%d %H:%M:%S UTC"), message))
+}
+
+/// Installs a panic hook that sends a crash notification to every alert
+/// channel before the default panic handler runs, so a bug in a check doesn't
+/// take the daemon down silently
+pub fn install_panic_hook(config: &Config) {
+    // The Notifier takes over the configured plugins later, so the hook gets a set of its own
+    let plugins = std::sync::Arc::new(crate::config::notification_plugins_from_env(config));
+    let timeout = Duration::from_secs(config.webhook_timeout_secs);
+    let default_hook = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        let message = tr!("crashed", message = panic_message(info));
+        let plugins = std::sync::Arc::clone(&plugins);
+        // The panicking thread may be a tokio worker, so send from a fresh one
+        let _ = std::thread::spawn(move || send_alert_blocking(&plugins, timeout, &message)).join();
+        default_hook(info);
+    }));
+}
+
+/// Delivers a crash alert to every plugin on a runtime of its own, for use
+/// where the caller's runtime can't be relied on. Failures are printed
+fn send_alert_blocking(plugins: &[BoxedPlugin], timeout: Duration, message: &str) {
+    if plugins.is_empty() {
+        return;
+    }
+    let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Error: Failed to send crash notification: {}", e);
+            return;
+        }
+    };
+
+    let alert = Alert::new(AlertSeverity::Critical, "crashed", &crate::metadata::annotate(message));
+    runtime.block_on(async {
+        for plugin in plugins {
+            match tokio::time::timeout(timeout, plugin.send(&alert)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => eprintln!("Error: {} failed to send crash notification: {}", plugin.name(), e),
+                Err(_) => eprintln!("Error: {} timed out sending crash notification", plugin.name()),
+            }
+        }
+    });
+}
+
+/// Panic payload and location, e.g. "index out of bounds (src/checks.rs:42:9)"
+fn panic_message(info: &std::panic::PanicHookInfo) -> String {
+    let payload = info.payload();
+    let message = payload.downcast_ref::<&str>().copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic");
+    match info.location() {
+        Some(location) => format!("{} ({})", message, location),
+        None => message.to_string(),
+    }
+}
+
+/// An empty URL or the placeholder disables notifications
+pub fn webhook_enabled(url: &str) -> bool {
+    !url.is_empty() && !url.contains("REDACTED")
+}
+
+/// The X-Eth-Alive-Signature value for `body`, if a secret is configured
+pub fn signature_header(hmac_secret: Option<&str>, body: &[u8]) -> Option<String> {
+    hmac_secret.map(|secret| format!("sha256={}", sign(secret, body)))
+}
+
+/// How long a send must wait so it starts at least `min_interval` after the
+/// previous one, which may itself still be waiting for its slot
+fn send_delay(last_send: Option<Instant>, now: Instant, min_interval: Duration) -> Duration {
+    match last_send {
+        Some(last) => (last + min_interval).saturating_duration_since(now),
+        None => Duration::ZERO,
+    }
+}
+
+/// Computes the hex-encoded HMAC-SHA256 of `body` keyed with `secret`
+fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    #[test]
+    fn test_send_delay_spaces_out_webhook_calls() {
+        let now = Instant::now();
+        let min = Duration::from_millis(2000);
+        assert_eq!(send_delay(None, now, min), Duration::ZERO);
+        assert_eq!(send_delay(Some(now), now + Duration::from_millis(500), min), Duration::from_millis(1500));
+        assert_eq!(send_delay(Some(now), now + Duration::from_millis(2500), min), Duration::ZERO);
+        assert_eq!(send_delay(Some(now), now, Duration::ZERO), Duration::ZERO);
+        // A send still sleeping until its slot pushes the next one further out
+        assert_eq!(send_delay(Some(now + Duration::from_millis(1500)), now, min), Duration::from_millis(3500));
+    }
+
+    #[test]
+    fn test_alert_hash_covers_kind_and_message() {
+        let hash = alert_hash("local_node_down", "Local node down");
+        assert_eq!(hash, alert_hash("local_node_down", "Local node down"));
+        assert_ne!(hash, alert_hash("local_node_down", "Local node down again"));
+        assert_ne!(hash, alert_hash("peer_count", "Local node down"));
+    }
+
+    #[test]
+    fn test_test_alert_message() {
+        for kind in TEST_ALERT_KINDS {
+            let message = test_alert_message(kind, Utc::now()).unwrap();
+            assert!(message.starts_with("[TEST] "));
+            assert!(!message.contains('{'), "unfilled placeholder in {}", message);
+        }
+        assert!(test_alert_message("no_such_alert", Utc::now()).is_none());
+    }
+
+    #[test]
+    fn test_detect_backend_from_url() {
+        assert_eq!(NotificationBackend::detect("https://hooks.slack.com/services/T0/B0/X", None), NotificationBackend::Slack);
+        assert_eq!(NotificationBackend::detect("https://discord.com/api/webhooks/1/abc", None), NotificationBackend::Discord);
+        let template = r#"{"summary":"{message}"}"#.to_string();
+        assert_eq!(
+            NotificationBackend::detect("https://events.pagerduty.com/v2/enqueue", Some(template.clone())),
+            NotificationBackend::Generic { template },
+        );
+    }
+
+    fn test_notifier(webhook_url: String, proxy_url: Option<String>, backend: NotificationBackend) -> Notifier {
+        let target = WebhookTarget { client: reqwest::Client::new(), url: webhook_url, proxy_url, hmac_secret: None };
+        Notifier {
+            plugins: vec![webhook_plugin(&backend, target)],
+            timeout: Duration::from_secs(5),
+            cooldown: chrono::Duration::minutes(15),
+            dedupe: false,
+            connections: Semaphore::new(1),
+            min_interval: Duration::ZERO,
+            last_send: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn alert(message: &str) -> Alert {
+        Alert::new(AlertSeverity::Critical, "local_node_down", message)
+    }
+
+    /// Accepts one webhook request on a local port and returns its headers and body
+    pub(crate) async fn receive_webhook(listener: tokio::net::TcpListener) -> (String, String) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0u8; 4096];
+        // reqwest sends the headers and the (small) body before waiting for a response
+        loop {
+            let n = socket.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request).to_string();
+            if let Some((headers, body)) = text.split_once("\r\n\r\n") {
+                let length = headers.lines()
+                    .find_map(|line| line.to_ascii_lowercase().strip_prefix("content-length: ").map(|v| v.trim().parse::<usize>().unwrap()))
+                    .unwrap_or(0);
+                if body.len() >= length {
+                    socket.write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n").await.unwrap();
+                    return (headers.to_string(), body.to_string());
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_alert_uses_backend_format() {
+        let cases = [
+            (NotificationBackend::Discord, r#"{"content":"NODE \"a\" DOWN"}"#),
+            (NotificationBackend::Slack, r#"{"text":"NODE \"a\" DOWN"}"#),
+            (NotificationBackend::Generic { template: r#"{"msg":"{message}","source":"eth-alive"}"#.to_string() }, r#"{"msg":"NODE \"a\" DOWN","source":"eth-alive"}"#),
+        ];
+        for (backend, expected) in cases {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/hook", listener.local_addr().unwrap());
+            let server = tokio::spawn(receive_webhook(listener));
+
+            let notifier = test_notifier(url, None, backend);
+            notifier.send_alert(&alert(r#"NODE "a" DOWN"#)).await.unwrap();
+            assert_eq!(server.await.unwrap().1, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_alert_through_proxy() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let proxy = format!("http://{}/route", listener.local_addr().unwrap());
+        let server = tokio::spawn(receive_webhook(listener));
+
+        let webhook = "https://discord.com/api/webhooks/1/abc".to_string();
+        test_notifier(webhook, Some(proxy), NotificationBackend::Discord).send_alert(&alert("NODE DOWN")).await.unwrap();
+        let (headers, body) = server.await.unwrap();
+        assert!(headers.starts_with("POST /route "));
+        assert!(headers.to_ascii_lowercase().contains("x-target-webhook: https://discord.com/api/webhooks/1/abc"));
+        assert_eq!(body, r#"{"content":"NODE DOWN"}"#);
+    }
+
+    #[tokio::test]
+    async fn test_send_alert_succeeds_if_any_plugin_delivers() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = tokio::spawn(receive_webhook(listener));
+
+        let mut notifier = test_notifier(url, None, NotificationBackend::Discord);
+        let unreachable = WebhookTarget { client: reqwest::Client::new(), url: "http://127.0.0.1:1/hook".to_string(), proxy_url: None, hmac_secret: None };
+        notifier.plugins.insert(0, webhook_plugin(&NotificationBackend::Slack, unreachable));
+        notifier.send_alert(&alert("NODE DOWN")).await.unwrap();
+        assert_eq!(server.await.unwrap().1, r#"{"content":"NODE DOWN"}"#);
+
+        notifier.plugins.truncate(1);
+        assert!(notifier.send_alert(&alert("NODE DOWN")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_send_interval_is_per_plugin() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = tokio::spawn(receive_webhook(listener));
+        let mut notifier = test_notifier(url, None, NotificationBackend::Discord);
+        notifier.min_interval = Duration::from_secs(60);
+        notifier.send_alert(&alert("NODE DOWN")).await.unwrap();
+        server.await.unwrap();
+
+        // Another channel has its own rate limit, so it isn't held back by the Discord send
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = tokio::spawn(receive_webhook(listener));
+        let target = WebhookTarget { client: reqwest::Client::new(), url, proxy_url: None, hmac_secret: None };
+        notifier.plugins = vec![webhook_plugin(&NotificationBackend::Slack, target)];
+        tokio::time::timeout(Duration::from_secs(5), notifier.send_alert(&alert("NODE DOWN"))).await
+            .expect("second plugin waited for the first one's interval").unwrap();
+        assert_eq!(server.await.unwrap().1, r#"{"text":"NODE DOWN"}"#);
+        assert_eq!(notifier.last_send.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_crash_alert_reaches_every_plugin() {
+        let mut plugins: Vec<BoxedPlugin> = Vec::new();
+        let mut servers = Vec::new();
+        for backend in [NotificationBackend::Slack, NotificationBackend::Generic { template: r#"{"kind":"{kind}"}"#.to_string() }] {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/hook", listener.local_addr().unwrap());
+            servers.push(tokio::spawn(receive_webhook(listener)));
+            plugins.push(webhook_plugin(&backend, WebhookTarget { client: reqwest::Client::new(), url, proxy_url: None, hmac_secret: None }));
+        }
+
+        // As from the panic hook: a plain thread with no runtime of its own
+        std::thread::spawn(move || send_alert_blocking(&plugins, Duration::from_secs(5), "eth-alive CRASHED")).join().unwrap();
+        assert_eq!(servers.remove(0).await.unwrap().1, r#"{"text":"eth-alive CRASHED"}"#);
+        assert_eq!(servers.remove(0).await.unwrap().1, r#"{"kind":"crashed"}"#);
+    }
+
+    #[test]
+    fn test_sign_matches_rfc4231_vector() {
+        // RFC 4231, test case 2
+        let signature = sign("Jefe", b"what do ya want for nothing?");
+        assert_eq!(signature, "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+    }
+}
@@ -0,0 +1,68 @@
+
+// --- PID File ---
+//
+// PID_FILE holds the daemon's process ID for systemd and init scripts. A file
+// left behind by a crash is taken over; one whose process is still alive
+// means another instance may be watching the same node.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// Writes this process's PID to `path`
+    pub fn create(path: &str) -> io::Result<PidFile> {
+        fs::write(path, format!("{}\n", std::process::id()))?;
+        Ok(PidFile { path: PathBuf::from(path) })
+    }
+
+    /// Deletes the file on graceful shutdown
+    pub fn remove(self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            warn!("[WARN] Failed to remove PID file '{}': {}", self.path.display(), e);
+        }
+    }
+}
+
+/// PID of another live instance recorded in `path`, if any
+pub fn running_instance(path: &str) -> Option<u32> {
+    running_pid(&fs::read_to_string(path).ok()?)
+}
+
+/// The PID in a PID file's contents, if that process is still alive (and isn't us)
+fn running_pid(contents: &str) -> Option<u32> {
+    let pid: u32 = contents.trim().parse().ok()?;
+    let alive = Path::new(&format!("/proc/{}/status", pid)).exists();
+    (alive && pid != std::process::id()).then_some(pid)
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_running_pid() {
+        // PID 1 always exists; our own PID is never "another instance"
+        assert_eq!(running_pid("1\n"), Some(1));
+        assert_eq!(running_pid(&std::process::id().to_string()), None);
+        assert_eq!(running_pid("not a pid"), None);
+        assert_eq!(running_pid(&u32::MAX.to_string()), None);
+    }
+
+    #[test]
+    fn test_create_and_remove() {
+        let path = std::env::temp_dir().join(format!("eth-alive-{}.pid", std::process::id()));
+        let path = path.to_str().unwrap();
+        let pid_file = PidFile::create(path).unwrap();
+        assert_eq!(running_instance(path), None);
+        assert_eq!(fs::read_to_string(path).unwrap().trim(), std::process::id().to_string());
+        pid_file.remove();
+        assert!(!Path::new(path).exists());
+    }
+}
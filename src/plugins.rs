@@ -0,0 +1,330 @@
+
+// --- Notification Plugins ---
+//
+// Each alert channel is a `NotificationPlugin`: the Notifier decides whether
+// and when an alert goes out (cooldown, dedupe, rate limits), and hands it to
+// every configured plugin, which only formats and delivers it.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::transport::smtp::client::{Tls, TlsParameters};
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::Serialize;
+
+use crate::error::AppError;
+use crate::notify::{signature_header, Alert, NotificationBackend, SIGNATURE_HEADER, TARGET_WEBHOOK_HEADER};
+
+/// Telegram Bot API; the token is part of the path
+const TELEGRAM_API_URL: &str = "https://api.telegram.org";
+
+/// An alert channel
+#[async_trait]
+pub trait NotificationPlugin {
+    /// Shown in logs and by `test-alert`, e.g. "Discord webhook"
+    fn name(&self) -> &'static str;
+
+    async fn send(&self, alert: &Alert) -> Result<(), AppError>;
+}
+
+pub type BoxedPlugin = Box<dyn NotificationPlugin + Send + Sync>;
+
+/// Represents the JSON payload sent to Discord
+#[derive(Serialize)]
+struct DiscordBody<'a> {
+    content: &'a str,
+}
+
+/// Represents the JSON payload sent to a Slack incoming webhook
+#[derive(Serialize)]
+struct SlackBody<'a> {
+    text: &'a str,
+}
+
+/// Represents the JSON payload of Telegram's sendMessage
+#[derive(Serialize)]
+struct TelegramBody<'a> {
+    chat_id: &'a str,
+    text: &'a str,
+}
+
+pub fn discord_body(message: &str) -> Result<Vec<u8>, serde_json::Error> {
+    serde_json::to_vec(&DiscordBody { content: message })
+}
+
+pub fn slack_body(message: &str) -> Result<Vec<u8>, serde_json::Error> {
+    serde_json::to_vec(&SlackBody { text: message })
+}
+
+/// `template` with `{message}` replaced by the JSON-escaped alert, and
+/// `{severity}` and `{kind}` by e.g. "critical" and "local_node_down"
+pub fn template_body(template: &str, alert: &Alert) -> Result<Vec<u8>, serde_json::Error> {
+    let quoted = serde_json::to_string(&alert.message)?;
+    let escaped = &quoted[1..quoted.len() - 1];
+    Ok(template
+        .replace("{severity}", alert.severity.label())
+        .replace("{kind}", &alert.kind)
+        .replace("{message}", escaped)
+        .into_bytes())
+}
+
+/// Where a webhook plugin posts, with the WEBHOOK_PROXY_URL and
+/// WEBHOOK_HMAC_SECRET settings shared by all webhook formats
+pub struct WebhookTarget {
+    pub client: reqwest::Client,
+    pub url: String,
+    /// Sends go here instead, with the webhook in the X-Target-Webhook header
+    pub proxy_url: Option<String>,
+    pub hmac_secret: Option<String>,
+}
+
+impl WebhookTarget {
+    async fn post(&self, body: Vec<u8>) -> Result<(), AppError> {
+        let mut request = match &self.proxy_url {
+            Some(proxy) => self.client.post(proxy).header(TARGET_WEBHOOK_HEADER, &self.url),
+            None => self.client.post(&self.url),
+        };
+        request = request.header(reqwest::header::CONTENT_TYPE, "application/json");
+        if let Some(signature) = signature_header(self.hmac_secret.as_deref(), &body) {
+            request = request.header(SIGNATURE_HEADER, signature);
+        }
+        request.body(body).send().await
+            .and_then(|response| response.error_for_status())
+            .map_err(AppError::WebhookError)?;
+        Ok(())
+    }
+}
+
+fn serialize_error(e: serde_json::Error) -> AppError {
+    AppError::ParseError(e.to_string())
+}
+
+pub struct DiscordPlugin {
+    pub target: WebhookTarget,
+}
+
+#[async_trait]
+impl NotificationPlugin for DiscordPlugin {
+    fn name(&self) -> &'static str {
+        "Discord webhook"
+    }
+
+    async fn send(&self, alert: &Alert) -> Result<(), AppError> {
+        self.target.post(discord_body(&alert.message).map_err(serialize_error)?).await
+    }
+}
+
+pub struct SlackPlugin {
+    pub target: WebhookTarget,
+}
+
+#[async_trait]
+impl NotificationPlugin for SlackPlugin {
+    fn name(&self) -> &'static str {
+        "Slack webhook"
+    }
+
+    async fn send(&self, alert: &Alert) -> Result<(), AppError> {
+        self.target.post(slack_body(&alert.message).map_err(serialize_error)?).await
+    }
+}
+
+/// Any other webhook, with the body from WEBHOOK_TEMPLATE
+pub struct TemplatePlugin {
+    pub target: WebhookTarget,
+    pub template: String,
+}
+
+#[async_trait]
+impl NotificationPlugin for TemplatePlugin {
+    fn name(&self) -> &'static str {
+        "Webhook"
+    }
+
+    async fn send(&self, alert: &Alert) -> Result<(), AppError> {
+        self.target.post(template_body(&self.template, alert).map_err(serialize_error)?).await
+    }
+}
+
+/// The plugin for a webhook in the format detected from its URL
+pub fn webhook_plugin(backend: &NotificationBackend, target: WebhookTarget) -> BoxedPlugin {
+    match backend {
+        NotificationBackend::Discord => Box::new(DiscordPlugin { target }),
+        NotificationBackend::Slack => Box::new(SlackPlugin { target }),
+        NotificationBackend::Generic { template } => Box::new(TemplatePlugin { target, template: template.clone() }),
+    }
+}
+
+/// Messages a chat through a Telegram bot (TELEGRAM_BOT_TOKEN, TELEGRAM_CHAT_ID)
+pub struct TelegramPlugin {
+    client: reqwest::Client,
+    api_url: String,
+    bot_token: String,
+    chat_id: String,
+}
+
+impl TelegramPlugin {
+    pub fn new(client: reqwest::Client, bot_token: String, chat_id: String) -> Self {
+        TelegramPlugin { client, api_url: TELEGRAM_API_URL.to_string(), bot_token, chat_id }
+    }
+}
+
+#[async_trait]
+impl NotificationPlugin for TelegramPlugin {
+    fn name(&self) -> &'static str {
+        "Telegram"
+    }
+
+    async fn send(&self, alert: &Alert) -> Result<(), AppError> {
+        let url = format!("{}/bot{}/sendMessage", self.api_url, self.bot_token);
+        self.client.post(url)
+            .json(&TelegramBody { chat_id: &self.chat_id, text: &alert.message })
+            .send().await
+            .and_then(|response| response.error_for_status())
+            // The URL carries the bot token, so it's kept out of logged errors
+            .map_err(|e| AppError::WebhookError(e.without_url()))?;
+        Ok(())
+    }
+}
+
+/// How the SMTP connection is secured (SMTP_TLS)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SmtpTls {
+    /// TLS from the first byte (SMTPS)
+    Tls,
+    /// Plaintext upgraded with STARTTLS, which the server must offer
+    StartTls,
+    /// STARTTLS when the server offers it, plaintext otherwise
+    StartTlsOptional,
+    None,
+}
+
+impl SmtpTls {
+    /// Port the mode is conventionally served on, used when SMTP_PORT isn't set
+    pub fn default_port(self) -> u16 {
+        match self {
+            SmtpTls::Tls => 465,
+            SmtpTls::StartTls | SmtpTls::StartTlsOptional => 587,
+            SmtpTls::None => 25,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SmtpTls::Tls => "tls",
+            SmtpTls::StartTls => "starttls",
+            SmtpTls::StartTlsOptional => "starttls-optional",
+            SmtpTls::None => "none",
+        }
+    }
+}
+
+impl std::str::FromStr for SmtpTls {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tls" => Ok(SmtpTls::Tls),
+            "starttls" => Ok(SmtpTls::StartTls),
+            "starttls-optional" => Ok(SmtpTls::StartTlsOptional),
+            "none" => Ok(SmtpTls::None),
+            other => Err(format!("unknown SMTP TLS mode '{}'", other)),
+        }
+    }
+}
+
+/// SMTP_* settings of the email channel
+#[derive(Clone, Debug)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub tls: SmtpTls,
+    /// SMTP_USERNAME and SMTP_PASSWORD, when the server needs a login
+    pub credentials: Option<(String, String)>,
+    pub from: Mailbox,
+    pub to: Vec<Mailbox>,
+}
+
+/// Emails every alert to SMTP_TO
+pub struct EmailPlugin {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+}
+
+impl EmailPlugin {
+    pub fn new(config: &SmtpConfig, timeout: Duration) -> Result<Self, lettre::transport::smtp::Error> {
+        let tls = match config.tls {
+            SmtpTls::Tls => Tls::Wrapper(TlsParameters::new(config.host.clone())?),
+            SmtpTls::StartTls => Tls::Required(TlsParameters::new(config.host.clone())?),
+            SmtpTls::StartTlsOptional => Tls::Opportunistic(TlsParameters::new(config.host.clone())?),
+            SmtpTls::None => Tls::None,
+        };
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host)
+            .port(config.port)
+            .tls(tls)
+            .timeout(Some(timeout));
+        if let Some((username, password)) = &config.credentials {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        Ok(EmailPlugin { transport: builder.build(), from: config.from.clone(), to: config.to.clone() })
+    }
+}
+
+#[async_trait]
+impl NotificationPlugin for EmailPlugin {
+    fn name(&self) -> &'static str {
+        "Email"
+    }
+
+    async fn send(&self, alert: &Alert) -> Result<(), AppError> {
+        // The first line of the alert, e.g. "🚨[WARN] NODE LAGGING!"
+        let subject = alert.message.split(['!', '\n']).next().unwrap_or(&alert.message);
+        let mut message = Message::builder().from(self.from.clone()).subject(format!("eth-alive: {}", subject.trim()));
+        for to in &self.to {
+            message = message.to(to.clone());
+        }
+        let message = message.body(alert.message.clone()).map_err(|e| AppError::EmailError(e.to_string()))?;
+        self.transport.send(message).await.map_err(|e| AppError::EmailError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify::tests::receive_webhook;
+    use crate::notify::AlertSeverity;
+
+    #[tokio::test]
+    async fn test_telegram_plugin_sends_message() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let api_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(receive_webhook(listener));
+
+        let plugin = TelegramPlugin { client: reqwest::Client::new(), api_url, bot_token: "123:abc".to_string(), chat_id: "-100200".to_string() };
+        plugin.send(&Alert::new(AlertSeverity::Critical, "local_node_down", "NODE DOWN")).await.unwrap();
+        let (headers, body) = server.await.unwrap();
+        assert!(headers.starts_with("POST /bot123:abc/sendMessage "));
+        assert_eq!(body, r#"{"chat_id":"-100200","text":"NODE DOWN"}"#);
+    }
+
+    #[test]
+    fn test_smtp_tls_modes() {
+        assert_eq!("starttls-optional".parse::<SmtpTls>(), Ok(SmtpTls::StartTlsOptional));
+        assert_eq!("tls".parse::<SmtpTls>().unwrap().default_port(), 465);
+        assert_eq!("none".parse::<SmtpTls>().unwrap().default_port(), 25);
+        assert!("ssl".parse::<SmtpTls>().is_err());
+    }
+
+    #[test]
+    fn test_template_body_fills_placeholders() {
+        let alert = Alert::new(AlertSeverity::Warn, "node_lagging", "NODE \"a\"\nLAGGING");
+        let body = template_body(r#"{"msg":"{message}","severity":"{severity}","kind":"{kind}"}"#, &alert).unwrap();
+        assert_eq!(String::from_utf8(body).unwrap(), r#"{"msg":"NODE \"a\"\nLAGGING","severity":"warn","kind":"node_lagging"}"#);
+    }
+}
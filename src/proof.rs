@@ -0,0 +1,263 @@
+
+// --- Account Proofs ---
+//
+// Verifies an EIP-1186 'eth_getProof' account proof against a block's
+// stateRoot: the Merkle Patricia trie path for keccak256(address) is walked
+// from the root, checking every node against the hash its parent committed
+// to, and the account found at the end must be the one the node reported.
+
+use serde_json::Value;
+use sha3::{Digest, Keccak256};
+
+use crate::rlp::{rlp_bytes, rlp_items, rlp_list, rlp_string};
+use crate::rpc::parse_hex_bytes;
+
+/// How a trie node refers to a child
+enum NodeRef {
+    Hash(Vec<u8>),
+    /// Nodes shorter than 32 bytes are embedded in their parent
+    Inline(Vec<u8>),
+    Empty,
+}
+
+fn node_ref(item: &[u8]) -> Result<NodeRef, String> {
+    match rlp_string(item) {
+        Some([]) => Ok(NodeRef::Empty),
+        Some(hash) if hash.len() == 32 => Ok(NodeRef::Hash(hash.to_vec())),
+        Some(other) => Err(format!("invalid child reference of {} bytes", other.len())),
+        None => Ok(NodeRef::Inline(item.to_vec())),
+    }
+}
+
+fn nibbles(bytes: &[u8]) -> Vec<u8> {
+    bytes.iter().flat_map(|b| [b >> 4, b & 0x0f]).collect()
+}
+
+/// Decodes a leaf or extension path: (is a leaf, nibbles)
+fn hex_prefix(encoded: &[u8]) -> Result<(bool, Vec<u8>), String> {
+    let (&first, rest) = encoded.split_first().ok_or("empty node path")?;
+    let flag = first >> 4;
+    if flag > 3 {
+        return Err(format!("invalid node path flag {}", flag));
+    }
+    let mut path = if flag & 1 == 1 { vec![first & 0x0f] } else { Vec::new() };
+    path.extend(nibbles(rest));
+    Ok((flag & 2 == 2, path))
+}
+
+/// Follows `key` from `root` through the proof nodes. Returns the value stored
+/// under the key, or None if the proof shows the key isn't in the trie.
+fn verify_proof(root: &[u8], key: &[u8], proof: &[Vec<u8>]) -> Result<Option<Vec<u8>>, String> {
+    let path = nibbles(&Keccak256::digest(key));
+    let mut pos = 0;
+    let mut nodes = proof.iter().enumerate();
+    let mut next = NodeRef::Hash(root.to_vec());
+
+    loop {
+        let node = match next {
+            NodeRef::Empty => return Ok(None),
+            NodeRef::Inline(node) => node,
+            NodeRef::Hash(hash) => {
+                let (i, node) = nodes.next().ok_or("proof ends before reaching the account")?;
+                if Keccak256::digest(node).as_slice() != hash.as_slice() {
+                    return Err(format!("proof node {} does not match the hash committed to by its parent", i));
+                }
+                node.clone()
+            }
+        };
+        let items = rlp_items(&node).ok_or("proof node is not a valid trie node")?;
+        next = match items.len() {
+            // Branch: one child per nibble, plus a value for keys ending here
+            17 => {
+                let Some(&nibble) = path.get(pos) else {
+                    return Err("key ends at a branch node".to_string());
+                };
+                pos += 1;
+                node_ref(items[nibble as usize])?
+            }
+            // Leaf or extension: a shared run of nibbles
+            2 => {
+                let encoded = rlp_string(items[0]).ok_or("invalid node path")?;
+                let (is_leaf, node_path) = hex_prefix(encoded)?;
+                let rest = &path[pos..];
+                if is_leaf {
+                    if rest != node_path.as_slice() {
+                        return Ok(None);
+                    }
+                    if nodes.next().is_some() {
+                        return Err("proof continues past the leaf".to_string());
+                    }
+                    return rlp_string(items[1]).map(|value| Some(value.to_vec())).ok_or("invalid leaf value".to_string());
+                }
+                if !rest.starts_with(&node_path) {
+                    return Ok(None);
+                }
+                pos += node_path.len();
+                node_ref(items[1])?
+            }
+            n => return Err(format!("trie node has {} items", n)),
+        };
+    }
+}
+
+fn hex_field(object: &Value, field: &str) -> Result<Vec<u8>, String> {
+    let hex = object.get(field).and_then(Value::as_str).ok_or_else(|| format!("'{}' missing", field))?;
+    parse_hex_bytes(hex).map_err(|e| format!("'{}': {}", field, e))
+}
+
+/// RLP integers are minimal big-endian
+fn trim_leading_zeros(bytes: &[u8]) -> &[u8] {
+    &bytes[bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len())..]
+}
+
+/// Checks an 'eth_getProof' response against `state_root`
+pub fn verify_account_proof(state_root: &[u8], response: &Value) -> Result<(), String> {
+    let address = hex_field(response, "address")?;
+    let proof = response.get("accountProof").and_then(Value::as_array).ok_or("'accountProof' missing")?
+        .iter()
+        .map(|node| node.as_str().ok_or("proof node not a string".to_string())
+            .and_then(|hex| parse_hex_bytes(hex).map_err(|e| e.to_string())))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let nonce = hex_field(response, "nonce")?;
+    let balance = hex_field(response, "balance")?;
+    match verify_proof(state_root, &address, &proof)? {
+        Some(account) => {
+            let reported = rlp_list(&[
+                rlp_bytes(trim_leading_zeros(&nonce)),
+                rlp_bytes(trim_leading_zeros(&balance)),
+                rlp_bytes(&hex_field(response, "storageHash")?),
+                rlp_bytes(&hex_field(response, "codeHash")?),
+            ]);
+            if account != reported {
+                return Err("the account in the trie differs from the reported nonce, balance, storageHash or codeHash".to_string());
+            }
+        }
+        // An address not in the state must be reported as an empty account
+        None if !trim_leading_zeros(&nonce).is_empty() || !trim_leading_zeros(&balance).is_empty() => {
+            return Err("the proof shows the account does not exist, but a nonce or balance was reported".to_string());
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        format!("0x{}", bytes.iter().map(|b| format!("{:02x}", b)).collect::<String>())
+    }
+
+    /// Hex-prefix encoding of a leaf path
+    fn leaf_path(nibbles: &[u8]) -> Vec<u8> {
+        let (mut out, rest) = if nibbles.len() % 2 == 1 { (vec![0x30 | nibbles[0]], &nibbles[1..]) } else { (vec![0x20], nibbles) };
+        out.extend(rest.chunks(2).map(|pair| (pair[0] << 4) | pair[1]));
+        out
+    }
+
+    /// A state trie of one branch over two account leaves
+    struct TestTrie {
+        root: Vec<u8>,
+        branch: Vec<u8>,
+        leaves: Vec<(Vec<u8>, Vec<u8>)>,
+    }
+
+    const CODE_HASH: &str = "0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470";
+    const STORAGE_HASH: &str = "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421";
+
+    fn account(nonce: u64, balance: u64) -> Vec<u8> {
+        rlp_list(&[
+            rlp_bytes(trim_leading_zeros(&nonce.to_be_bytes())),
+            rlp_bytes(trim_leading_zeros(&balance.to_be_bytes())),
+            rlp_bytes(&parse_hex_bytes(STORAGE_HASH).unwrap()),
+            rlp_bytes(&parse_hex_bytes(CODE_HASH).unwrap()),
+        ])
+    }
+
+    fn first_nibble(address: &[u8]) -> u8 {
+        Keccak256::digest(address)[0] >> 4
+    }
+
+    /// Two addresses in different branch slots and a third in an empty slot
+    fn addresses() -> [Vec<u8>; 3] {
+        let mut found: Vec<Vec<u8>> = Vec::new();
+        for i in 1u8.. {
+            let candidate = [vec![0u8; 19], vec![i]].concat();
+            if found.iter().all(|other| first_nibble(other) != first_nibble(&candidate)) {
+                found.push(candidate);
+            }
+            if found.len() == 3 {
+                break;
+            }
+        }
+        found.try_into().unwrap()
+    }
+
+    fn test_trie(accounts: &[Vec<u8>]) -> TestTrie {
+        let mut children = vec![rlp_bytes(&[]); 17];
+        let mut leaves = Vec::new();
+        for (i, address) in accounts.iter().enumerate() {
+            let path = nibbles(&Keccak256::digest(address));
+            let leaf = rlp_list(&[rlp_bytes(&leaf_path(&path[1..])), rlp_bytes(&account(i as u64 + 1, 1_000 * (i as u64 + 1)))]);
+            children[path[0] as usize] = rlp_bytes(&Keccak256::digest(&leaf));
+            leaves.push((address.clone(), leaf));
+        }
+        let branch = rlp_list(&children);
+        TestTrie { root: Keccak256::digest(&branch).to_vec(), branch, leaves }
+    }
+
+    fn response(address: &[u8], nonce: u64, balance: u64, proof: &[&[u8]]) -> Value {
+        serde_json::json!({
+            "address": hex(address),
+            "accountProof": proof.iter().map(|node| hex(node)).collect::<Vec<_>>(),
+            "nonce": format!("0x{:x}", nonce),
+            "balance": format!("0x{:x}", balance),
+            "storageHash": STORAGE_HASH,
+            "codeHash": CODE_HASH,
+            "storageProof": []
+        })
+    }
+
+    #[test]
+    fn test_valid_account_proof() {
+        let [a, b, _] = addresses();
+        let trie = test_trie(&[a.clone(), b]);
+        let leaf = &trie.leaves[0].1;
+        assert_eq!(verify_account_proof(&trie.root, &response(&a, 1, 1_000, &[&trie.branch, leaf])), Ok(()));
+    }
+
+    #[test]
+    fn test_tampered_account_proof() {
+        let [a, b, _] = addresses();
+        let trie = test_trie(&[a.clone(), b]);
+        let leaf = &trie.leaves[0].1;
+        // Balance differs from the one committed to by the trie
+        assert!(verify_account_proof(&trie.root, &response(&a, 1, 2_000, &[&trie.branch, leaf])).unwrap_err().contains("differs"));
+        // Proof doesn't lead to this state root
+        assert!(verify_account_proof(&[0u8; 32], &response(&a, 1, 1_000, &[&trie.branch, leaf])).unwrap_err().contains("does not match"));
+        // Leaf of another account
+        let other = &trie.leaves[1].1;
+        assert!(verify_account_proof(&trie.root, &response(&a, 1, 1_000, &[&trie.branch, other])).is_err());
+        assert!(verify_account_proof(&trie.root, &response(&a, 1, 1_000, &[&trie.branch])).unwrap_err().contains("ends before"));
+    }
+
+    #[test]
+    fn test_absent_account_proof() {
+        let [a, b, absent] = addresses();
+        let trie = test_trie(&[a, b]);
+        assert_eq!(verify_account_proof(&trie.root, &response(&absent, 0, 0, &[&trie.branch])), Ok(()));
+        assert!(verify_account_proof(&trie.root, &response(&absent, 0, 5, &[&trie.branch])).unwrap_err().contains("does not exist"));
+    }
+
+    #[test]
+    fn test_hex_prefix() {
+        assert_eq!(hex_prefix(&[0x20, 0x0f, 0x1c]), Ok((true, vec![0x0, 0xf, 0x1, 0xc])));
+        assert_eq!(hex_prefix(&[0x3f, 0x1c]), Ok((true, vec![0xf, 0x1, 0xc])));
+        assert_eq!(hex_prefix(&[0x11, 0x23]), Ok((false, vec![0x1, 0x2, 0x3])));
+        assert!(hex_prefix(&[0x40]).is_err());
+    }
+}
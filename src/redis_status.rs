@@ -0,0 +1,89 @@
+
+// --- Redis Status ---
+//
+// The latest status snapshot, written to `eth-alive:{NODE_LABEL}:status` in
+// REDIS_URL for other services in the same cluster. The key expires after two
+// poll intervals, so a missing key means the monitor itself stopped.
+
+use std::time::Duration;
+
+use redis::aio::MultiplexedConnection;
+use tokio::sync::mpsc;
+
+use crate::redact_url;
+use crate::snapshot::Snapshot;
+
+/// Redis operations give up after this long, so a hung server only delays its own writes
+const REDIS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Hands snapshots to the writer task without ever waiting on Redis
+pub struct RedisStatus {
+    sender: mpsc::Sender<Snapshot>,
+}
+
+impl RedisStatus {
+    /// Validates the URL and spawns the writer task; the connection is made lazily
+    pub fn spawn(url: &str, node_label: &str, poll_interval_secs: u64) -> redis::RedisResult<Self> {
+        let client = redis::Client::open(url)?;
+        // Capacity 1: while a write is in flight one newer snapshot waits, later ones are dropped
+        let (sender, receiver) = mpsc::channel(1);
+        tokio::spawn(write_loop(client, redact_url(url), status_key(node_label), 2 * poll_interval_secs, receiver));
+        Ok(RedisStatus { sender })
+    }
+
+    pub fn publish(&self, snapshot: Snapshot) {
+        if self.sender.try_send(snapshot).is_err() {
+            debug!("[DEBUG] Redis write still in progress, skipping this status update");
+        }
+    }
+}
+
+fn status_key(node_label: &str) -> String {
+    format!("eth-alive:{}:status", node_label)
+}
+
+async fn write_loop(client: redis::Client, url: String, key: String, ttl_secs: u64, mut receiver: mpsc::Receiver<Snapshot>) {
+    let mut connection: Option<MultiplexedConnection> = None;
+    while let Some(snapshot) = receiver.recv().await {
+        let json = match serde_json::to_string(&snapshot) {
+            Ok(json) => json,
+            Err(e) => {
+                error!("[ERROR] Failed to serialize Redis status: {}", e);
+                continue;
+            }
+        };
+        // A failed write drops the connection; the next status reconnects
+        let result = tokio::time::timeout(REDIS_TIMEOUT, async {
+            if connection.is_none() {
+                connection = Some(client.get_multiplexed_async_connection().await?);
+            }
+            let conn = connection.as_mut().expect("connected above");
+            redis::cmd("SET").arg(&key).arg(&json).arg("EX").arg(ttl_secs).query_async::<()>(conn).await
+        }).await;
+        let error = match result {
+            Ok(Ok(())) => continue,
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => format!("timed out after {}s", REDIS_TIMEOUT.as_secs()),
+        };
+        error!("[ERROR] Failed to write status to Redis {}: {}", url, error);
+        connection = None;
+    }
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_key() {
+        assert_eq!(status_key("validator-1"), "eth-alive:validator-1:status");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_url_is_rejected() {
+        assert!(RedisStatus::spawn("not a url", "eth-alive", 60).is_err());
+        assert!(RedisStatus::spawn("redis://127.0.0.1:6379", "eth-alive", 60).is_ok());
+    }
+}
@@ -0,0 +1,473 @@
+
+// --- Remote References ---
+//
+// One or more trusted remote RPCs provide the reference block number. Each
+// poll cycle the remotes are put in an order (REMOTE_RPC_STRATEGY) and the
+// reference is the first one in that order that answers, or with the
+// consensus and max strategies, the median or highest of all of them.
+// A remote that keeps failing has its circuit opened and is left out until a
+// probe every CIRCUIT_PROBE_INTERVAL_SECS succeeds again.
+
+use std::time::{Duration, Instant};
+
+use futures::future::join_all;
+use rand::seq::SliceRandom;
+
+use crate::checks::CheckOutcome;
+use crate::redact_url;
+use crate::rpc::{fetch_block_number, RpcResult};
+
+/// Weight of the newest sample in the per-remote latency average
+const LATENCY_EMA_ALPHA: f64 = 0.3;
+
+/// Latency charged to a remote that failed, so lowest-latency moves away from it
+const FAILURE_PENALTY_MS: f64 = 10_000.0;
+
+/// How the remote tried first is chosen each poll cycle (REMOTE_RPC_STRATEGY)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RemoteStrategy {
+    /// Always in configured order
+    FirstSuccess,
+    /// Start one remote further every cycle
+    RoundRobin,
+    /// Fastest average latency first
+    LowestLatency,
+    /// Random order
+    Random,
+    /// Query all remotes and use the median, so one lagging provider can't cause false alerts
+    Consensus,
+    /// Query all remotes and use the highest, e.g. the backends behind one load balancer
+    Max,
+}
+
+impl std::str::FromStr for RemoteStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first-success" => Ok(RemoteStrategy::FirstSuccess),
+            "round-robin" => Ok(RemoteStrategy::RoundRobin),
+            "lowest-latency" => Ok(RemoteStrategy::LowestLatency),
+            "random" => Ok(RemoteStrategy::Random),
+            "consensus" => Ok(RemoteStrategy::Consensus),
+            "max" => Ok(RemoteStrategy::Max),
+            other => Err(format!("unknown remote strategy '{}'", other)),
+        }
+    }
+}
+
+/// Failure tracking of one remote
+#[derive(Clone, Debug, Default)]
+struct CircuitBreaker {
+    consecutive_failures: u32,
+    /// When the circuit opened, or when its last probe failed
+    opened_at: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    /// Closed, or open for long enough that the remote is due a probe
+    fn allows(&self, now: Instant, probe_interval: Duration) -> bool {
+        self.opened_at.is_none_or(|opened| now.duration_since(opened) >= probe_interval)
+    }
+
+    /// Returns true if this failure opened the circuit
+    fn record_failure(&mut self, now: Instant, threshold: u32) -> bool {
+        self.consecutive_failures += 1;
+        if self.opened_at.is_some() {
+            // Failed probe: wait another interval
+            self.opened_at = Some(now);
+            return false;
+        }
+        if self.consecutive_failures >= threshold {
+            self.opened_at = Some(now);
+            return true;
+        }
+        false
+    }
+
+    /// Returns true if this success closed an open circuit
+    fn record_success(&mut self) -> bool {
+        self.consecutive_failures = 0;
+        self.opened_at.take().is_some()
+    }
+}
+
+/// CIRCUIT_FAILURE_THRESHOLD and CIRCUIT_PROBE_INTERVAL_SECS
+#[derive(Clone, Copy, Debug)]
+struct CircuitSettings {
+    failure_threshold: u32,
+    probe_interval: Duration,
+}
+
+/// The configured remotes plus the state the strategies need across cycles
+pub struct RemotePool {
+    urls: Vec<String>,
+    strategy: RemoteStrategy,
+    cycle: usize,
+    latency_ema_ms: Vec<Option<f64>>,
+    breakers: Vec<CircuitBreaker>,
+    circuit: Option<CircuitSettings>,
+}
+
+impl RemotePool {
+    pub fn new(urls: Vec<String>, strategy: RemoteStrategy) -> Self {
+        let latency_ema_ms = vec![None; urls.len()];
+        let breakers = vec![CircuitBreaker::default(); urls.len()];
+        RemotePool { urls, strategy, cycle: 0, latency_ema_ms, breakers, circuit: None }
+    }
+
+    /// Gives every remote a circuit breaker opening after `failure_threshold`
+    /// failures in a row (0 leaves them off)
+    pub fn with_circuit_breakers(mut self, failure_threshold: u32, probe_interval: Duration) -> Self {
+        if failure_threshold > 0 {
+            self.circuit = Some(CircuitSettings { failure_threshold, probe_interval });
+        }
+        self
+    }
+
+    /// Which remotes may be queried now. With every circuit open, all of them
+    /// are, since a reference from a flaky remote beats none at all
+    fn available(&self, now: Instant) -> Vec<bool> {
+        let available: Vec<bool> = match self.circuit {
+            Some(circuit) if self.urls.len() > 1 => self.breakers.iter().map(|breaker| breaker.allows(now, circuit.probe_interval)).collect(),
+            _ => vec![true; self.urls.len()],
+        };
+        if available.contains(&true) { available } else { vec![true; self.urls.len()] }
+    }
+
+    pub fn urls(&self) -> &[String] {
+        &self.urls
+    }
+
+    /// Tries the remotes one at a time in this cycle's order and returns the first block number obtained
+    pub async fn fetch(&mut self, client: &reqwest::Client) -> RpcResult<u64> {
+        if matches!(self.strategy, RemoteStrategy::Consensus | RemoteStrategy::Max) {
+            let results = self.fetch_all(client).await;
+            return self.select(results);
+        }
+        let mut last_err = None;
+        let available = self.available(Instant::now());
+        for index in self.next_order().into_iter().filter(|index| available[*index]) {
+            let url = self.urls[index].clone();
+            let started = Instant::now();
+            let result = fetch_block_number(client, &url).await;
+            self.record(index, started, result.is_ok());
+            match result {
+                Ok(block) => {
+                    crate::metrics::record_remote_selected(&url);
+                    return Ok(block);
+                }
+                Err(e) => {
+                    if self.urls.len() > 1 {
+                        warn!("[WARN] Remote {} failed, trying next: {}", redact_url(&url), e);
+                    }
+                    last_err = Some(e);
+                }
+            }
+        }
+        Err(last_err.unwrap_or_else(|| "no remote RPC configured".into()))
+    }
+
+    /// Queries every remote concurrently, preserving the configured order.
+    /// Remotes with an open circuit aren't queried and count as failed
+    pub async fn fetch_all(&mut self, client: &reqwest::Client) -> Vec<RpcResult<u64>> {
+        let available = self.available(Instant::now());
+        let timed = join_all(self.urls.iter().zip(available).map(|(url, available)| async move {
+            let started = Instant::now();
+            let result = match available {
+                true => Some(fetch_block_number(client, url).await),
+                false => None,
+            };
+            (started, result)
+        })).await;
+
+        timed.into_iter().enumerate().map(|(index, (started, result))| match result {
+            Some(result) => {
+                self.record(index, started, result.is_ok());
+                result
+            }
+            None => Err("circuit open".into()),
+        }).collect()
+    }
+
+    /// Picks the reference from a full set of results (see `fetch_all`) using
+    /// this cycle's order. The consensus median isn't one remote's answer, so
+    /// only the other strategies count a selection
+    pub fn select(&mut self, results: Vec<RpcResult<u64>>) -> RpcResult<u64> {
+        if self.strategy == RemoteStrategy::Consensus {
+            return consensus_block_number(&results).ok_or_else(|| {
+                let answered = results.iter().filter(|result| result.is_ok()).count();
+                format!("only {} of {} remotes responded, no consensus", answered, results.len()).into()
+            });
+        }
+        if self.strategy == RemoteStrategy::Max {
+            let highest = results.iter().enumerate()
+                .filter_map(|(index, result)| result.as_ref().ok().map(|block| (index, *block)))
+                .max_by_key(|(_, block)| *block);
+            return match highest {
+                Some((index, block)) => {
+                    crate::metrics::record_remote_selected(&self.urls[index]);
+                    Ok(block)
+                }
+                None => first_success(results),
+            };
+        }
+        let order = self.next_order();
+        if let Some(index) = order.iter().copied().find(|index| results.get(*index).is_some_and(Result::is_ok)) {
+            crate::metrics::record_remote_selected(&self.urls[index]);
+        }
+        let mut results: Vec<Option<RpcResult<u64>>> = results.into_iter().map(Some).collect();
+        let ordered = order.into_iter()
+            .filter_map(|index| results.get_mut(index).and_then(Option::take))
+            .collect();
+        first_success(ordered)
+    }
+
+    /// Order in which the remotes are tried this cycle; advances the round-robin cursor
+    fn next_order(&mut self) -> Vec<usize> {
+        let count = self.urls.len();
+        let mut order: Vec<usize> = (0..count).collect();
+        match self.strategy {
+            RemoteStrategy::FirstSuccess | RemoteStrategy::Consensus | RemoteStrategy::Max => {}
+            RemoteStrategy::RoundRobin => order.rotate_left(self.cycle % count.max(1)),
+            // Remotes without a sample yet sort first so each one gets measured
+            RemoteStrategy::LowestLatency => order.sort_by(|a, b| {
+                let latency = |i: usize| self.latency_ema_ms[i].unwrap_or(0.0);
+                latency(*a).total_cmp(&latency(*b))
+            }),
+            RemoteStrategy::Random => order.shuffle(&mut rand::thread_rng()),
+        }
+        self.cycle += 1;
+        order
+    }
+
+    /// Folds one request into the remote's latency average and circuit breaker
+    fn record(&mut self, index: usize, started: Instant, ok: bool) {
+        let sample = if ok { started.elapsed().as_secs_f64() * 1000.0 } else { FAILURE_PENALTY_MS };
+        let ema = &mut self.latency_ema_ms[index];
+        *ema = Some(match *ema {
+            Some(previous) => LATENCY_EMA_ALPHA * sample + (1.0 - LATENCY_EMA_ALPHA) * previous,
+            None => sample,
+        });
+
+        let Some(circuit) = self.circuit else {
+            return;
+        };
+        let breaker = &mut self.breakers[index];
+        if ok {
+            if breaker.record_success() {
+                info!("[INFO] Remote {} circuit closed, using it again", redact_url(&self.urls[index]));
+            }
+        } else if breaker.record_failure(Instant::now(), circuit.failure_threshold) && self.urls.len() > 1 {
+            info!("[INFO] Remote {} circuit open, using other remotes", redact_url(&self.urls[index]));
+        }
+    }
+}
+
+/// Picks the first successful result, or the last error if none succeeded
+pub fn first_success(results: Vec<RpcResult<u64>>) -> RpcResult<u64> {
+    let mut last_err = None;
+    for result in results {
+        match result {
+            Ok(block) => return Ok(block),
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| "no remote RPC configured".into()))
+}
+
+/// Median block number of the remotes that answered (the lower middle value for
+/// an even count, so a single remote running ahead can't raise the reference).
+/// None when fewer than half of the remotes answered.
+pub fn consensus_block_number(results: &[RpcResult<u64>]) -> Option<u64> {
+    let mut answered: Vec<u64> = results.iter().filter_map(|result| result.as_ref().ok().copied()).collect();
+    if answered.is_empty() || answered.len() * 2 < results.len() {
+        return None;
+    }
+    answered.sort_unstable();
+    Some(answered[(answered.len() - 1) / 2])
+}
+
+/// Compares the remotes that answered against each other.
+/// Alerts when the highest and lowest differ by more than `threshold` blocks.
+pub fn agreement_outcome(urls: &[String], results: &[RpcResult<u64>], threshold: u64) -> Option<CheckOutcome> {
+    let answered: Vec<(&String, u64)> = urls.iter()
+        .zip(results)
+        .filter_map(|(url, result)| result.as_ref().ok().map(|block| (url, *block)))
+        .collect();
+
+    // Nothing to compare with fewer than two answers
+    if answered.len() < 2 {
+        return None;
+    }
+
+    let (high_url, high) = answered.iter().max_by_key(|(_, block)| *block).copied()?;
+    let (low_url, low) = answered.iter().min_by_key(|(_, block)| *block).copied()?;
+    let diff = high - low;
+    let median = consensus_block_number(results).unwrap_or(low);
+
+    Some(if diff > threshold {
+        CheckOutcome::Alert(tr!(
+            "remote_disagreement",
+            high_remote = redact_url(high_url), high = high,
+            low_remote = redact_url(low_url), low = low,
+            diff = diff,
+        ))
+    } else {
+        CheckOutcome::Ok(format!("[OK] Remotes agree | Max: {} | Median: {} | Min: {} | Spread: {} blocks", high, median, low, diff))
+    })
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+
+    fn urls(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("http://remote{}.example", i)).collect()
+    }
+
+    #[test]
+    fn test_remotes_within_threshold_agree() {
+        let results = vec![Ok(100), Ok(102), Ok(101)];
+        let outcome = agreement_outcome(&urls(3), &results, 3);
+        assert!(matches!(outcome, Some(CheckOutcome::Ok(line)) if line.contains("Max: 102 | Median: 101 | Min: 100 | Spread: 2")));
+    }
+
+    #[test]
+    fn test_remotes_beyond_threshold_disagree() {
+        let results = vec![Ok(100), Ok(110)];
+        let outcome = agreement_outcome(&urls(2), &results, 3);
+        assert!(matches!(outcome, Some(CheckOutcome::Alert(msg)) if msg.contains("Difference: 10 blocks")));
+    }
+
+    #[test]
+    fn test_failed_remotes_are_ignored() {
+        let results = vec![Ok(100), Err("down".into()), Ok(101)];
+        assert!(matches!(agreement_outcome(&urls(3), &results, 3), Some(CheckOutcome::Ok(_))));
+
+        let results = vec![Ok(100), Err("down".into())];
+        assert!(agreement_outcome(&urls(2), &results, 3).is_none());
+    }
+
+    #[test]
+    fn test_round_robin_rotates_start() {
+        let mut pool = RemotePool::new(urls(3), RemoteStrategy::RoundRobin);
+        assert_eq!(pool.next_order(), vec![0, 1, 2]);
+        assert_eq!(pool.next_order(), vec![1, 2, 0]);
+        assert_eq!(pool.next_order(), vec![2, 0, 1]);
+        assert_eq!(pool.next_order(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_lowest_latency_prefers_fastest() {
+        let mut pool = RemotePool::new(urls(3), RemoteStrategy::LowestLatency);
+        pool.latency_ema_ms = vec![Some(250.0), Some(40.0), Some(FAILURE_PENALTY_MS)];
+        assert_eq!(pool.next_order(), vec![1, 0, 2]);
+
+        // An unmeasured remote is tried first
+        pool.latency_ema_ms[0] = None;
+        assert_eq!(pool.next_order()[0], 0);
+    }
+
+    #[test]
+    fn test_select_follows_strategy_order() {
+        let mut pool = RemotePool::new(urls(3), RemoteStrategy::RoundRobin);
+        assert_eq!(pool.select(vec![Ok(10), Ok(11), Ok(12)]).unwrap(), 10);
+        assert_eq!(pool.select(vec![Ok(10), Err("down".into()), Ok(12)]).unwrap(), 12);
+    }
+
+    #[test]
+    fn test_consensus_odd_count_uses_median() {
+        // The lagging provider doesn't drag the reference down
+        assert_eq!(consensus_block_number(&[Ok(100), Ok(80), Ok(101)]), Some(100));
+        assert_eq!(consensus_block_number(&[Ok(100), Ok(101), Ok(102), Ok(50), Ok(103)]), Some(101));
+    }
+
+    #[test]
+    fn test_consensus_even_count_uses_lower_middle() {
+        assert_eq!(consensus_block_number(&[Ok(103), Ok(100), Ok(101), Ok(102)]), Some(101));
+        // Exactly half answering is enough
+        assert_eq!(consensus_block_number(&[Ok(100), Err("down".into())]), Some(100));
+    }
+
+    #[test]
+    fn test_consensus_needs_half_of_remotes() {
+        assert_eq!(consensus_block_number(&[Ok(100), Err("down".into()), Err("down".into())]), None);
+        assert_eq!(consensus_block_number(&[Err("down".into()), Err("down".into())]), None);
+        assert_eq!(consensus_block_number(&[]), None);
+    }
+
+    #[test]
+    fn test_consensus_single_remote() {
+        assert_eq!(consensus_block_number(&[Ok(42)]), Some(42));
+        assert_eq!(consensus_block_number(&[Err("down".into())]), None);
+    }
+
+    #[test]
+    fn test_select_consensus_reports_missing_quorum() {
+        let mut pool = RemotePool::new(urls(3), RemoteStrategy::Consensus);
+        assert_eq!(pool.select(vec![Ok(100), Ok(70), Ok(101)]).unwrap(), 100);
+        let err = pool.select(vec![Ok(100), Err("down".into()), Err("down".into())]).unwrap_err();
+        assert!(matches!(err, AppError::ParseError(message) if message == "only 1 of 3 remotes responded, no consensus"));
+    }
+
+    #[test]
+    fn test_select_max_uses_highest() {
+        let mut pool = RemotePool::new(urls(3), RemoteStrategy::Max);
+        assert_eq!(pool.select(vec![Ok(100), Ok(95), Ok(101)]).unwrap(), 101);
+        assert_eq!(pool.select(vec![Err("down".into()), Ok(95), Err("down".into())]).unwrap(), 95);
+        assert!(pool.select(vec![Err("down".into()), Err("down".into())]).is_err());
+    }
+
+    #[test]
+    fn test_parse_remote_strategy() {
+        assert_eq!("round-robin".parse::<RemoteStrategy>().unwrap(), RemoteStrategy::RoundRobin);
+        assert_eq!("max".parse::<RemoteStrategy>().unwrap(), RemoteStrategy::Max);
+        assert!("fastest".parse::<RemoteStrategy>().is_err());
+    }
+
+    #[test]
+    fn test_circuit_breaker_opens_and_probes() {
+        let probe_interval = Duration::from_secs(60);
+        let start = Instant::now();
+        let mut breaker = CircuitBreaker::default();
+        assert!(!breaker.record_failure(start, 3));
+        assert!(!breaker.record_failure(start, 3));
+        assert!(breaker.allows(start, probe_interval));
+        assert!(breaker.record_failure(start, 3));
+        assert!(!breaker.allows(start + Duration::from_secs(30), probe_interval));
+        assert!(breaker.allows(start + probe_interval, probe_interval));
+
+        // A failed probe waits another interval, a successful one closes the circuit
+        assert!(!breaker.record_failure(start + probe_interval, 3));
+        assert!(!breaker.allows(start + Duration::from_secs(90), probe_interval));
+        assert!(breaker.record_success());
+        assert!(breaker.allows(start, probe_interval));
+    }
+
+    #[test]
+    fn test_open_circuits_leave_out_remotes() {
+        let mut pool = RemotePool::new(urls(3), RemoteStrategy::FirstSuccess).with_circuit_breakers(2, Duration::from_secs(60));
+        let started = Instant::now();
+        pool.record(1, started, false);
+        pool.record(1, started, false);
+        assert_eq!(pool.available(Instant::now()), vec![true, false, true]);
+
+        // Never all of them
+        for index in [0, 2] {
+            pool.record(index, started, false);
+            pool.record(index, started, false);
+        }
+        assert_eq!(pool.available(Instant::now()), vec![true, true, true]);
+    }
+
+    #[test]
+    fn test_first_success_skips_errors() {
+        let results = vec![Err("down".into()), Ok(7), Ok(9)];
+        assert_eq!(first_success(results).unwrap(), 7);
+        assert!(first_success(vec![Err("down".into())]).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+
+// --- Interactive RPC Console (--repl) ---
+//
+// Sends ad-hoc JSON-RPC calls through the same clients the watchdog uses,
+// so bind addresses and other connection settings apply unchanged.
+
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::redact_url;
+use crate::rpc::rpc_call;
+
+const HELP: &str = "\
+Usage: [@local|@remote] <method> [params as JSON array]
+  eth_blockNumber
+  @remote eth_getBlockByNumber [\"latest\", false]
+Type 'help' to show this message, 'exit' or Ctrl-D to quit.";
+
+/// Which configured connection a command is sent to
+#[derive(Debug, PartialEq)]
+enum Target {
+    Local,
+    Remote,
+}
+
+/// A parsed console line
+#[derive(Debug, PartialEq)]
+struct Command {
+    target: Target,
+    method: String,
+    params: Value,
+}
+
+fn parse_command(line: &str) -> Result<Command, String> {
+    let mut rest = line.trim();
+
+    let target = match rest.split_once(char::is_whitespace) {
+        Some(("@local", tail)) => { rest = tail.trim_start(); Target::Local }
+        Some(("@remote", tail)) => { rest = tail.trim_start(); Target::Remote }
+        Some((word, _)) if word.starts_with('@') => return Err(format!("unknown target '{}'", word)),
+        _ if rest.starts_with('@') => return Err("missing method name".to_string()),
+        _ => Target::Local,
+    };
+
+    let (method, raw_params) = match rest.split_once(char::is_whitespace) {
+        Some((method, params)) => (method, params.trim()),
+        None => (rest, ""),
+    };
+    if method.is_empty() {
+        return Err("missing method name".to_string());
+    }
+
+    let params = if raw_params.is_empty() {
+        Value::Array(Vec::new())
+    } else {
+        let params: Value = serde_json::from_str(raw_params).map_err(|e| format!("invalid params JSON: {}", e))?;
+        if !params.is_array() {
+            return Err("params must be a JSON array".to_string());
+        }
+        params
+    };
+
+    Ok(Command { target, method: method.to_string(), params })
+}
+
+/// Runs the console until the user exits
+pub async fn run(config: &Config, local_client: &reqwest::Client, remote_client: &reqwest::Client) {
+    let mut editor = DefaultEditor::new().expect("Failed to initialise the line editor");
+    let remote_rpc = &config.remote_rpcs[0];
+
+    println!("eth-alive RPC console");
+    println!("  @local  -> {}", redact_url(&config.local_rpc));
+    println!("  @remote -> {}", redact_url(remote_rpc));
+    println!("{}", HELP);
+
+    loop {
+        let line = match tokio::task::block_in_place(|| editor.readline("eth-alive> ")) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                break;
+            }
+        };
+
+        let line = line.trim();
+        match line {
+            "" => continue,
+            "exit" | "quit" => break,
+            "help" => {
+                println!("{}", HELP);
+                continue;
+            }
+            _ => {}
+        }
+        editor.add_history_entry(line).ok();
+
+        let command = match parse_command(line) {
+            Ok(command) => command,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                continue;
+            }
+        };
+
+        let (client, url) = match command.target {
+            Target::Local => (local_client, config.local_rpc.as_str()),
+            Target::Remote => (remote_client, remote_rpc.as_str()),
+        };
+        match rpc_call(client, url, &command.method, command.params).await {
+            Ok(result) => println!("{}", serde_json::to_string_pretty(&result).unwrap_or_default()),
+            Err(e) => eprintln!("Error: {}", e),
+        }
+    }
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_method_only() {
+        let command = parse_command("eth_blockNumber").unwrap();
+        assert_eq!(command.target, Target::Local);
+        assert_eq!(command.method, "eth_blockNumber");
+        assert_eq!(command.params, serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_parse_target_and_params() {
+        let command = parse_command(r#"@remote eth_getBlockByNumber ["latest", false]"#).unwrap();
+        assert_eq!(command.target, Target::Remote);
+        assert_eq!(command.method, "eth_getBlockByNumber");
+        assert_eq!(command.params, serde_json::json!(["latest", false]));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_command("@nowhere eth_chainId").is_err());
+        assert!(parse_command("@local").is_err());
+        assert!(parse_command("eth_call {\"to\": \"0x0\"}").is_err());
+        assert!(parse_command("eth_call [oops").is_err());
+    }
+}
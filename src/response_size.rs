@@ -0,0 +1,64 @@
+
+// --- Response Size Tracking ---
+//
+// An 'eth_blockNumber' response is a ~50 byte JSON object. When the bodies
+// grow (e.g. error pages or stack traces), the node is in trouble even if
+// some calls still succeed.
+
+use std::collections::VecDeque;
+
+/// Number of recent responses the percentile is computed over
+const WINDOW: usize = 100;
+
+/// Rolling window of recent response body sizes
+pub struct ResponseSizes {
+    samples: VecDeque<usize>,
+}
+
+impl ResponseSizes {
+    pub fn new() -> Self {
+        ResponseSizes { samples: VecDeque::with_capacity(WINDOW) }
+    }
+
+    pub fn record(&mut self, size: usize) {
+        if self.samples.len() == WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(size);
+    }
+
+    /// 99th percentile (nearest rank) of the window, or None before the first sample
+    pub fn p99(&self) -> Option<usize> {
+        let mut sorted: Vec<usize> = self.samples.iter().copied().collect();
+        sorted.sort_unstable();
+        let rank = (sorted.len() * 99).div_ceil(100);
+        sorted.get(rank.checked_sub(1)?).copied()
+    }
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_p99_over_window() {
+        let mut sizes = ResponseSizes::new();
+        assert_eq!(sizes.p99(), None);
+
+        for _ in 0..98 {
+            sizes.record(50);
+        }
+        sizes.record(4000);
+        sizes.record(9000);
+        // 100 samples: the 99th smallest is the first outlier
+        assert_eq!(sizes.p99(), Some(4000));
+
+        // Old samples fall out of the window
+        for _ in 0..100 {
+            sizes.record(60);
+        }
+        assert_eq!(sizes.p99(), Some(60));
+    }
+}
@@ -0,0 +1,117 @@
+
+// --- RLP ---
+//
+// Recursive Length Prefix encoding, just enough to build discv4 packets and
+// to rehash block headers, and decoding for the trie nodes of Merkle proofs.
+
+pub fn rlp_bytes(bytes: &[u8]) -> Vec<u8> {
+    if bytes.len() == 1 && bytes[0] < 0x80 {
+        return bytes.to_vec();
+    }
+    let mut out = rlp_length(bytes.len(), 0x80);
+    out.extend_from_slice(bytes);
+    out
+}
+
+/// Big-endian with leading zeros stripped (0 is the empty string)
+pub fn rlp_uint(value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    rlp_bytes(&bytes[start..])
+}
+
+pub fn rlp_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload = items.concat();
+    let mut out = rlp_length(payload.len(), 0xc0);
+    out.extend_from_slice(&payload);
+    out
+}
+
+fn rlp_length(len: usize, offset: u8) -> Vec<u8> {
+    if len <= 55 {
+        return vec![offset + len as u8];
+    }
+    let bytes = (len as u64).to_be_bytes();
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    let mut out = vec![offset + 55 + (bytes.len() - start) as u8];
+    out.extend_from_slice(&bytes[start..]);
+    out
+}
+
+
+/// Splits the first item off `data`: (is a list, payload, rest)
+fn rlp_split(data: &[u8]) -> Option<(bool, &[u8], &[u8])> {
+    let (&prefix, rest) = data.split_first()?;
+    let (is_list, offset, len) = match prefix {
+        0x00..=0x7f => return Some((false, &data[..1], rest)),
+        0x80..=0xb7 => (false, 0, (prefix - 0x80) as usize),
+        0xb8..=0xbf => (false, (prefix - 0xb7) as usize, 0),
+        0xc0..=0xf7 => (true, 0, (prefix - 0xc0) as usize),
+        0xf8..=0xff => (true, (prefix - 0xf7) as usize, 0),
+    };
+    // Long form: the length itself follows as `offset` big-endian bytes
+    let len = if offset > 0 {
+        rest.get(..offset)?.iter().try_fold(0usize, |acc, b| acc.checked_mul(256)?.checked_add(*b as usize))?
+    } else {
+        len
+    };
+    let rest = &rest[offset..];
+    (rest.len() >= len).then(|| (is_list, &rest[..len], &rest[len..]))
+}
+
+/// The items of an RLP list, each still in its encoded form
+pub fn rlp_items(data: &[u8]) -> Option<Vec<&[u8]>> {
+    let (true, mut payload, []) = rlp_split(data)? else {
+        return None;
+    };
+    let mut items = Vec::new();
+    while !payload.is_empty() {
+        let (_, _, rest) = rlp_split(payload)?;
+        items.push(&payload[..payload.len() - rest.len()]);
+        payload = rest;
+    }
+    Some(items)
+}
+
+/// The contents of an encoded RLP string (None for a list)
+pub fn rlp_string(item: &[u8]) -> Option<&[u8]> {
+    match rlp_split(item)? {
+        (false, payload, []) => Some(payload),
+        _ => None,
+    }
+}
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rlp_encoding() {
+        // Examples from the RLP specification
+        assert_eq!(rlp_bytes(b"dog"), vec![0x83, b'd', b'o', b'g']);
+        assert_eq!(rlp_list(&[rlp_bytes(b"cat"), rlp_bytes(b"dog")]), vec![0xc8, 0x83, b'c', b'a', b't', 0x83, b'd', b'o', b'g']);
+        assert_eq!(rlp_uint(0), vec![0x80]);
+        assert_eq!(rlp_uint(15), vec![0x0f]);
+        assert_eq!(rlp_uint(1024), vec![0x82, 0x04, 0x00]);
+        let long = vec![b'a'; 56];
+        assert_eq!(&rlp_bytes(&long)[..2], &[0xb8, 56]);
+    }
+
+    #[test]
+    fn test_rlp_decoding() {
+        let list = rlp_list(&[rlp_bytes(b"cat"), rlp_uint(15), rlp_list(&[]), rlp_bytes(&[b'a'; 56])]);
+        let items = rlp_items(&list).unwrap();
+        assert_eq!(items.len(), 4);
+        assert_eq!(rlp_string(items[0]), Some(&b"cat"[..]));
+        assert_eq!(rlp_string(items[1]), Some(&[0x0f][..]));
+        assert_eq!(rlp_string(items[2]), None);
+        assert_eq!(rlp_items(items[2]), Some(Vec::new()));
+        assert_eq!(rlp_string(items[3]).map(<[u8]>::len), Some(56));
+
+        // Truncated input, trailing bytes, and a string where a list is expected
+        assert_eq!(rlp_items(&list[..list.len() - 1]), None);
+        assert_eq!(rlp_items(&[list.as_slice(), &[0x01]].concat()), None);
+        assert_eq!(rlp_items(&rlp_bytes(b"dog")), None);
+    }
+}
@@ -0,0 +1,101 @@
+
+// --- Method Routing ---
+//
+// Secondary checks can send each JSON-RPC method to a different endpoint
+// (e.g. `eth_getLogs` to an archive node, cheap calls to a fast provider).
+// Routes and endpoints come from the TOML file named by METHOD_ROUTING:
+//
+//     [[endpoints]]
+//     alias = "archive_node"
+//     url = "http://archive:8545"
+//
+//     [routes]
+//     "eth_getLogs" = "archive_node"
+//     "debug_*" = "archive_node"
+//
+// Methods without a route go to LOCAL_RPC_URL.
+
+use std::collections::HashMap;
+use serde::Deserialize;
+
+/// A named RPC endpoint that routes can point at
+#[derive(Debug, Deserialize)]
+pub struct Endpoint {
+    pub alias: String,
+    pub url: String,
+}
+
+/// Method pattern -> endpoint alias table
+#[derive(Debug, Deserialize)]
+pub struct MethodRouting {
+    #[serde(default)]
+    pub endpoints: Vec<Endpoint>,
+    /// Exact method names, or prefixes ending in '*'
+    #[serde(default)]
+    pub routes: HashMap<String, String>,
+}
+
+/// Parses a routing file, checking that every route names a defined endpoint
+pub fn parse_routing(raw: &str) -> Result<MethodRouting, String> {
+    let routing: MethodRouting = toml::from_str(raw).map_err(|e| e.to_string())?;
+    for (pattern, alias) in &routing.routes {
+        if !routing.endpoints.iter().any(|endpoint| &endpoint.alias == alias) {
+            return Err(format!("route '{}' points at unknown endpoint '{}'", pattern, alias));
+        }
+    }
+    Ok(routing)
+}
+
+/// Returns the URL a method should be sent to. An exact route wins over
+/// wildcards, and a longer wildcard prefix wins over a shorter one.
+pub fn route_request<'a>(method: &str, routing: &'a MethodRouting, default: &'a str) -> &'a str {
+    let specificity = |pattern: &str| match pattern.strip_suffix('*') {
+        Some(prefix) if method.starts_with(prefix) => Some(prefix.len()),
+        Some(_) => None,
+        None if pattern == method => Some(usize::MAX),
+        None => None,
+    };
+
+    routing.routes.iter()
+        .filter_map(|(pattern, alias)| specificity(pattern).map(|rank| (rank, alias)))
+        .max_by_key(|(rank, _)| *rank)
+        .and_then(|(_, alias)| routing.endpoints.iter().find(|endpoint| &endpoint.alias == alias))
+        .map_or(default, |endpoint| endpoint.url.as_str())
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROUTING: &str = r#"
+        [[endpoints]]
+        alias = "archive_node"
+        url = "http://archive:8545"
+
+        [[endpoints]]
+        alias = "fast_node"
+        url = "http://fast:8545"
+
+        [routes]
+        "eth_getLogs" = "archive_node"
+        "eth_*" = "fast_node"
+        "debug_*" = "archive_node"
+    "#;
+
+    #[test]
+    fn test_route_request() {
+        let routing = parse_routing(ROUTING).unwrap();
+        assert_eq!(route_request("eth_getLogs", &routing, "http://local"), "http://archive:8545");
+        assert_eq!(route_request("eth_blockNumber", &routing, "http://local"), "http://fast:8545");
+        assert_eq!(route_request("debug_traceBlockByNumber", &routing, "http://local"), "http://archive:8545");
+        assert_eq!(route_request("net_peerCount", &routing, "http://local"), "http://local");
+    }
+
+    #[test]
+    fn test_unknown_endpoint_rejected() {
+        let raw = "[routes]\n\"eth_getLogs\" = \"missing\"\n";
+        assert!(parse_routing(raw).unwrap_err().contains("unknown endpoint"));
+    }
+}
@@ -0,0 +1,465 @@
+
+// --- JSON-RPC Helpers ---
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde_json::Value;
+
+use crate::error::AppError;
+
+/// Result type shared by every JSON-RPC helper
+pub type RpcResult<T> = Result<T, AppError>;
+
+/// JSON-RPC error code for a method the server doesn't implement
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Whether the node rejected the call because it doesn't support the method
+/// (e.g. a Geth-only namespace on Nethermind)
+pub fn is_method_not_found(e: &AppError) -> bool {
+    match e {
+        AppError::RpcError { code, message } => *code == METHOD_NOT_FOUND || message.to_ascii_lowercase().contains("method not found"),
+        _ => false,
+    }
+}
+
+static FORCE_HTTP10: AtomicBool = AtomicBool::new(false);
+
+/// Sends every JSON-RPC request as HTTP/1.0 (FORCE_HTTP10), for minimal
+/// servers on embedded hardware that don't speak HTTP/1.1
+pub fn force_http10() {
+    FORCE_HTTP10.store(true, Ordering::Relaxed);
+}
+
+/// Performs a JSON-RPC call and returns the raw 'result' field
+pub async fn rpc_call(client: &reqwest::Client, url: &str, method: &str, params: Value) -> RpcResult<Value> {
+    rpc_call_sized(client, url, method, params).await.1
+}
+
+/// Like `rpc_call`, but also returns the size of the response body (if one
+/// was received), including error responses
+pub async fn rpc_call_sized(client: &reqwest::Client, url: &str, method: &str, params: Value) -> (Option<usize>, RpcResult<Value>) {
+    let payload = serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": method,
+        "params": params,
+        "id": 1
+    });
+
+    let (size, body) = post(client, url, &payload).await;
+    (size, body.and_then(|body| parse_response(&body)))
+}
+
+/// Performs several JSON-RPC calls in one batch request. The results are in
+/// the order of `calls`; each one fails on its own.
+pub async fn rpc_batch(client: &reqwest::Client, url: &str, calls: &[(&str, Value)]) -> RpcResult<Vec<RpcResult<Value>>> {
+    let payload: Vec<Value> = calls.iter().enumerate().map(|(id, (method, params))| serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": method,
+        "params": params,
+        "id": id
+    })).collect();
+    let body = post(client, url, &Value::Array(payload)).await.1?;
+    parse_batch_response(&body, calls.len())
+}
+
+/// POSTs a JSON-RPC payload and returns the body (and its size, if one was
+/// received, including for a failed HTTP status)
+async fn post(client: &reqwest::Client, url: &str, payload: &Value) -> (Option<usize>, RpcResult<Vec<u8>>) {
+    // Send Request
+    let mut request = client.post(url).json(payload);
+    if FORCE_HTTP10.load(Ordering::Relaxed) {
+        request = request.version(reqwest::Version::HTTP_10);
+    }
+    let resp = match request.send().await {
+        Ok(resp) => resp,
+        Err(e) => return (None, Err(e.into())),
+    };
+    crate::headers::observe(url, resp.headers());
+
+    // Read the body even for a failed HTTP status, so its size is known
+    let status_error = resp.error_for_status_ref().err();
+    let body = match resp.bytes().await {
+        Ok(body) => body,
+        Err(e) => return (None, Err(e.into())),
+    };
+    let size = Some(body.len());
+    match status_error {
+        Some(e) => (size, Err(e.into())),
+        None => (size, Ok(body.to_vec())),
+    }
+}
+
+/// Splits a batch response into per-call results; servers may answer in any order
+fn parse_batch_response(body: &[u8], count: usize) -> RpcResult<Vec<RpcResult<Value>>> {
+    let responses: Vec<Value> = match serde_json::from_slice(body)? {
+        Value::Array(responses) => responses,
+        // A single error object means the whole batch was rejected
+        other => return Err(response_result(other).err().unwrap_or_else(|| "Invalid response: batch result not an array".into())),
+    };
+    let mut results: Vec<Option<RpcResult<Value>>> = (0..count).map(|_| None).collect();
+    for response in responses {
+        let slot = response.get("id").and_then(Value::as_u64).and_then(|id| results.get_mut(id as usize));
+        if let Some(slot) = slot {
+            *slot = Some(response_result(response));
+        }
+    }
+    Ok(results.into_iter()
+        .map(|result| result.unwrap_or_else(|| Err("Invalid response: missing from batch".into())))
+        .collect())
+}
+
+/// Extracts the 'result' field of a JSON-RPC response body
+fn parse_response(body: &[u8]) -> RpcResult<Value> {
+    // Parse as Generic JSON Value
+    response_result(serde_json::from_slice(body)?)
+}
+
+/// Extracts the 'result' field of one JSON-RPC response object
+fn response_result(mut body: Value) -> RpcResult<Value> {
+
+    // Anything but "2.0" means a severely misconfigured client (or whatever is in front of it)
+    let version = unexpected_jsonrpc_version(&body);
+    if let Some(version) = &version {
+        warn!("[WARN] UNEXPECTED_JSONRPC_VERSION: {}", version);
+    }
+    crate::metrics::record_jsonrpc_version(version.is_none());
+
+    // Check for RPC error
+    if let Some(err) = body.get("error") {
+        let err_msg = err.get("message").and_then(|m| m.as_str()).unwrap_or("Unknown RPC error");
+        let code = err.get("code").and_then(Value::as_i64).unwrap_or(0);
+        return Err(AppError::RpcError { code, message: err_msg.to_string() });
+    }
+
+    // Extract result
+    body.get_mut("result")
+        .map(Value::take)
+        .ok_or_else(|| "Invalid response: 'result' field missing".into())
+}
+
+/// The response's 'jsonrpc' field if it isn't "2.0" (a missing field reads "missing")
+fn unexpected_jsonrpc_version(body: &Value) -> Option<String> {
+    match body.get("jsonrpc") {
+        Some(Value::String(version)) if version == "2.0" => None,
+        Some(Value::String(version)) => Some(version.clone()),
+        Some(other) => Some(other.to_string()),
+        None => Some("missing".to_string()),
+    }
+}
+
+/// Performs a JSON-RPC call whose result is a hex quantity (e.g. 'eth_blockNumber')
+pub async fn fetch_quantity(client: &reqwest::Client, url: &str, method: &str, params: Value) -> RpcResult<u64> {
+    quantity(rpc_call(client, url, method, params).await?)
+}
+
+fn quantity(result: Value) -> RpcResult<u64> {
+    let result_str = result.as_str()
+        .ok_or("Invalid response: 'result' field not a string")?;
+
+    Ok(parse_hex_to_u64(result_str)?)
+}
+
+/// Performs 'eth_blockNumber' JSON-RPC call to the specified URL
+pub async fn fetch_block_number(client: &reqwest::Client, url: &str) -> RpcResult<u64> {
+    fetch_quantity(client, url, "eth_blockNumber", serde_json::json!([])).await
+}
+
+/// Performs 'eth_blockNumber' and also returns the response body size
+pub async fn fetch_block_number_sized(client: &reqwest::Client, url: &str) -> (Option<usize>, RpcResult<u64>) {
+    let (size, result) = rpc_call_sized(client, url, "eth_blockNumber", serde_json::json!([])).await;
+    (size, result.and_then(quantity))
+}
+
+/// Performs 'eth_getBlockByNumber' (header and tx hashes only) for a block tag or hex number
+pub async fn fetch_block(client: &reqwest::Client, url: &str, block: &str) -> RpcResult<Value> {
+    let block = rpc_call(client, url, "eth_getBlockByNumber", serde_json::json!([block, false])).await?;
+    if block.is_null() {
+        return Err("Invalid response: block not found".into());
+    }
+    Ok(block)
+}
+
+/// Number of the latest finalized block ('finalized' tag, PoS chains)
+pub async fn fetch_finalized_block(client: &reqwest::Client, url: &str) -> RpcResult<u64> {
+    let block = fetch_block(client, url, "finalized").await?;
+    quantity_field(&block, "number")
+}
+
+/// Performs 'eth_feeHistory' over the last `blocks` blocks and returns the
+/// median (50th percentile) priority fee reward of each block, oldest first
+pub async fn fetch_median_rewards(client: &reqwest::Client, url: &str, blocks: u64) -> RpcResult<Vec<u64>> {
+    let history = rpc_call(client, url, "eth_feeHistory", serde_json::json!([format!("0x{:x}", blocks), "latest", [50]])).await?;
+    median_rewards(&history).ok_or_else(|| "Invalid response: 'reward' missing from fee history".into())
+}
+
+fn median_rewards(history: &Value) -> Option<Vec<u64>> {
+    history.get("reward")?.as_array()?.iter()
+        .map(|percentiles| percentiles.get(0)?.as_str().and_then(|hex| parse_hex_to_u64(hex).ok()))
+        .collect()
+}
+
+/// Blob gas consumed by one EIP-4844 blob
+const GAS_PER_BLOB: u64 = 1 << 17;
+
+/// Number of EIP-4844 blobs in the latest block, or None on pre-4844 chains.
+/// Derived from the header's 'blobGasUsed', which every blob transaction's
+/// 'blobVersionedHashes' contribute to, so no full transaction bodies are needed.
+pub async fn fetch_blob_count(client: &reqwest::Client, url: &str) -> RpcResult<Option<u64>> {
+    let block = fetch_block(client, url, "latest").await?;
+    blob_count(&block)
+}
+
+fn blob_count(block: &Value) -> RpcResult<Option<u64>> {
+    if block.get("blobGasUsed").is_none() {
+        return Ok(None);
+    }
+    Ok(Some(quantity_field(block, "blobGasUsed")? / GAS_PER_BLOB))
+}
+
+/// Performs 'eth_getUncleCountByBlockNumber' for a block number
+pub async fn fetch_uncle_count(client: &reqwest::Client, url: &str, block: u64) -> RpcResult<u64> {
+    fetch_quantity(client, url, "eth_getUncleCountByBlockNumber", serde_json::json!([format!("0x{:x}", block)])).await
+}
+
+/// Performs 'eth_getUncleByBlockNumberAndIndex'
+pub async fn fetch_uncle(client: &reqwest::Client, url: &str, block: u64, index: u64) -> RpcResult<Value> {
+    let params = serde_json::json!([format!("0x{:x}", block), format!("0x{:x}", index)]);
+    let uncle = rpc_call(client, url, "eth_getUncleByBlockNumberAndIndex", params).await?;
+    if uncle.is_null() {
+        return Err(format!("Invalid response: uncle {} of block {} not found", index, block).into());
+    }
+    Ok(uncle)
+}
+
+/// Performs 'debug_traceBlockByNumber' with the call tracer (no logs); one entry per transaction
+pub async fn fetch_block_traces(client: &reqwest::Client, url: &str, block: &str) -> RpcResult<Vec<Value>> {
+    let config = serde_json::json!({ "tracer": "callTracer", "tracerConfig": { "withLog": false } });
+    match rpc_call(client, url, "debug_traceBlockByNumber", serde_json::json!([block, config])).await? {
+        Value::Array(traces) => Ok(traces),
+        other => Err(format!("Invalid response: unexpected trace result {}", other).into()),
+    }
+}
+
+/// Reads a hex quantity field (e.g. 'gasLimit') from a block or receipt object
+pub fn quantity_field(object: &Value, field: &str) -> RpcResult<u64> {
+    let hex = object.get(field).and_then(Value::as_str)
+        .ok_or_else(|| format!("Invalid response: '{}' missing", field))?;
+    Ok(parse_hex_to_u64(hex)?)
+}
+
+/// Performs 'eth_protocolVersion'. Clients return either a hex string or a number
+pub async fn fetch_protocol_version(client: &reqwest::Client, url: &str) -> RpcResult<String> {
+    match rpc_call(client, url, "eth_protocolVersion", serde_json::json!([])).await? {
+        Value::String(version) => Ok(version),
+        Value::Number(version) => Ok(version.to_string()),
+        other => Err(format!("Invalid response: unexpected protocol version {}", other).into()),
+    }
+}
+
+/// What 'eth_syncing' reports: `false` once synced, block progress while syncing
+#[derive(Debug, PartialEq)]
+pub enum SyncStatus {
+    Synced,
+    Syncing { current_block: u64, highest_block: u64, blocks_remaining: u64 },
+}
+
+impl SyncStatus {
+    pub fn progress_pct(&self) -> f64 {
+        match *self {
+            SyncStatus::Synced => 100.0,
+            SyncStatus::Syncing { highest_block: 0, .. } => 0.0,
+            SyncStatus::Syncing { current_block, highest_block, .. } => current_block as f64 / highest_block as f64 * 100.0,
+        }
+    }
+}
+
+/// Performs 'eth_syncing'
+pub async fn fetch_sync_status(client: &reqwest::Client, url: &str) -> RpcResult<SyncStatus> {
+    sync_status(&rpc_call(client, url, "eth_syncing", serde_json::json!([])).await?)
+}
+
+fn sync_status(result: &Value) -> RpcResult<SyncStatus> {
+    if result == &Value::Bool(false) {
+        return Ok(SyncStatus::Synced);
+    }
+    let current_block = quantity_field(result, "currentBlock")?;
+    let highest_block = quantity_field(result, "highestBlock")?;
+    Ok(SyncStatus::Syncing { current_block, highest_block, blocks_remaining: highest_block.saturating_sub(current_block) })
+}
+
+/// Performs 'debug_metrics' (Geth) and reads the number of live goroutines
+pub async fn fetch_goroutine_count(client: &reqwest::Client, url: &str) -> RpcResult<u64> {
+    let metrics = rpc_call(client, url, "debug_metrics", serde_json::json!([true])).await?;
+    goroutine_count(&metrics).ok_or_else(|| "Invalid response: 'system.goroutines' missing from debug_metrics".into())
+}
+
+/// The gauge is `system/goroutines` in older Geth releases and
+/// `system/cpu/goroutines` in newer ones, either as a number or as `{"Value": n}`
+fn goroutine_count(metrics: &Value) -> Option<u64> {
+    ["/system/goroutines", "/system/cpu/goroutines"].iter()
+        .filter_map(|path| metrics.pointer(path))
+        .find_map(|gauge| gauge.as_u64().or_else(|| gauge.get("Value")?.as_u64()))
+}
+
+/// Inbound vs outbound peer connections
+#[derive(Debug, PartialEq)]
+pub struct PeerDetails {
+    pub inbound: u64,
+    pub outbound: u64,
+}
+
+/// Performs 'admin_peers' (Geth admin namespace) and returns the peer list
+pub async fn fetch_peers(client: &reqwest::Client, url: &str) -> RpcResult<Vec<Value>> {
+    match rpc_call(client, url, "admin_peers", serde_json::json!([])).await? {
+        Value::Array(peers) => Ok(peers),
+        _ => Err("Invalid response: 'result' field not a peer list".into()),
+    }
+}
+
+/// Fetches the peer list and counts peers by connection direction
+pub async fn fetch_peer_details(client: &reqwest::Client, url: &str) -> RpcResult<PeerDetails> {
+    Ok(count_peer_directions(&fetch_peers(client, url).await?))
+}
+
+fn count_peer_directions(peers: &[Value]) -> PeerDetails {
+    let inbound = peers.iter()
+        .filter(|peer| peer.pointer("/network/inbound").and_then(Value::as_bool) == Some(true))
+        .count() as u64;
+    PeerDetails { inbound, outbound: peers.len() as u64 - inbound }
+}
+
+/// Converts a hex string (with or without '0x' prefix) to u64
+pub fn parse_hex_to_u64(hex: &str) -> Result<u64, std::num::ParseIntError> {
+    let clean_hex = hex.trim_start_matches("0x");
+    u64::from_str_radix(clean_hex, 16)
+}
+
+/// Converts a hex string (with or without '0x' prefix) to bytes; an odd
+/// number of digits is read as if it had a leading zero
+pub fn parse_hex_bytes(hex: &str) -> Result<Vec<u8>, std::num::ParseIntError> {
+    let clean_hex = hex.trim_start_matches("0x");
+    let padded = if clean_hex.len() % 2 == 1 { format!("0{}", clean_hex) } else { clean_hex.to_string() };
+    // By bytes, so a multi-byte character fails to parse instead of splitting mid-char
+    padded.as_bytes().chunks(2).map(|pair| u8::from_str_radix(&String::from_utf8_lossy(pair), 16)).collect()
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unexpected_jsonrpc_version() {
+        assert_eq!(unexpected_jsonrpc_version(&serde_json::json!({ "jsonrpc": "2.0", "id": 1, "result": "0x1" })), None);
+        assert_eq!(unexpected_jsonrpc_version(&serde_json::json!({ "jsonrpc": "1.0", "result": "0x1" })), Some("1.0".to_string()));
+        assert_eq!(unexpected_jsonrpc_version(&serde_json::json!({ "jsonrpc": 2, "result": "0x1" })), Some("2".to_string()));
+        assert_eq!(unexpected_jsonrpc_version(&serde_json::json!({ "result": "0x1" })), Some("missing".to_string()));
+    }
+
+    #[test]
+    fn test_goroutine_count() {
+        assert_eq!(goroutine_count(&serde_json::json!({"system": {"goroutines": 812, "memory": {}}})), Some(812));
+        assert_eq!(goroutine_count(&serde_json::json!({"system": {"cpu": {"goroutines": {"Value": 6120}}}})), Some(6120));
+        assert_eq!(goroutine_count(&serde_json::json!({"chain": {}})), None);
+    }
+
+    #[test]
+    fn test_blob_count_from_blob_gas() {
+        let block = serde_json::json!({ "number": "0x10", "blobGasUsed": "0x60000" });
+        assert_eq!(blob_count(&block).unwrap(), Some(3));
+        // Pre-4844 headers have no blob gas fields
+        assert_eq!(blob_count(&serde_json::json!({ "number": "0x10" })).unwrap(), None);
+    }
+
+    #[test]
+    fn test_median_rewards_from_fee_history() {
+        let history = serde_json::json!({ "oldestBlock": "0x10", "reward": [["0x3b9aca00"], ["0x77359400"]], "baseFeePerGas": [] });
+        assert_eq!(median_rewards(&history), Some(vec![1_000_000_000, 2_000_000_000]));
+        assert_eq!(median_rewards(&serde_json::json!({ "oldestBlock": "0x10" })), None);
+    }
+
+    #[test]
+    fn test_batch_response_matched_by_id() {
+        let body = br#"[
+            {"jsonrpc": "2.0", "id": 1, "error": {"code": -32000, "message": "not found"}},
+            {"jsonrpc": "2.0", "id": 0, "result": "0x1"}
+        ]"#;
+        let results = parse_batch_response(body, 3).unwrap();
+        assert_eq!(results[0].as_ref().unwrap(), &Value::String("0x1".to_string()));
+        assert!(results[1].as_ref().unwrap_err().to_string().contains("not found"));
+        assert!(results[2].as_ref().unwrap_err().to_string().contains("missing from batch"));
+
+        // Servers without batch support answer with one error object
+        let rejected = br#"{"jsonrpc": "2.0", "id": null, "error": {"code": -32600, "message": "batch not supported"}}"#;
+        assert!(parse_batch_response(rejected, 1).unwrap_err().to_string().contains("batch not supported"));
+    }
+
+    #[test]
+    fn test_sync_status() {
+        assert_eq!(sync_status(&Value::Bool(false)).unwrap(), SyncStatus::Synced);
+        let syncing = serde_json::json!({ "startingBlock": "0x0", "currentBlock": "0x1f4", "highestBlock": "0x3e8" });
+        let status = sync_status(&syncing).unwrap();
+        assert_eq!(status, SyncStatus::Syncing { current_block: 500, highest_block: 1000, blocks_remaining: 500 });
+        assert_eq!(status.progress_pct(), 50.0);
+        assert!(sync_status(&serde_json::json!({ "startingBlock": "0x0" })).is_err());
+    }
+
+    #[test]
+    fn test_count_peer_directions() {
+        let peers = vec![
+            serde_json::json!({ "id": "a", "network": { "inbound": true } }),
+            serde_json::json!({ "id": "b", "network": { "inbound": false } }),
+            serde_json::json!({ "id": "c", "network": { "inbound": true } }),
+        ];
+        assert_eq!(count_peer_directions(&peers), PeerDetails { inbound: 2, outbound: 1 });
+    }
+
+    #[test]
+    fn test_hex_parsing_with_prefix() {
+        // 0x10a = 266
+        let input = "0x10a";
+        let result = parse_hex_to_u64(input);
+        assert_eq!(result.unwrap(), 266);
+    }
+
+    #[test]
+    fn test_hex_parsing_without_prefix() {
+        // 10a = 266
+        let input = "10a";
+        let result = parse_hex_to_u64(input);
+        assert_eq!(result.unwrap(), 266);
+    }
+
+    #[test]
+    fn test_hex_parsing_uppercase() {
+        // 0x10A = 266
+        let input = "0x10A";
+        let result = parse_hex_to_u64(input);
+        assert_eq!(result.unwrap(), 266);
+    }
+
+    #[test]
+    fn test_hex_parsing_zero() {
+        let input = "0x0";
+        let result = parse_hex_to_u64(input);
+        assert_eq!(result.unwrap(), 0);
+    }
+
+    #[test]
+    fn test_hex_bytes() {
+        assert_eq!(parse_hex_bytes("0x0102ff").unwrap(), vec![0x01, 0x02, 0xff]);
+        assert_eq!(parse_hex_bytes("0x102").unwrap(), vec![0x01, 0x02]);
+        assert!(parse_hex_bytes("0x").unwrap().is_empty());
+        assert!(parse_hex_bytes("0xzz").is_err());
+        assert!(parse_hex_bytes("0xé1").is_err());
+        assert!(parse_hex_bytes("0x1é").is_err());
+    }
+
+    #[test]
+    fn test_invalid_hex() {
+        let input = "0xZZZ"; // Not a hex number
+        let result = parse_hex_to_u64(input);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,72 @@
+
+// --- Self Monitoring ---
+//
+// SELF_MONITOR=true periodically logs eth-alive's own memory and CPU usage,
+// and alerts when memory grows past SELF_MAX_MEMORY_MB (a leak in the
+// monitor itself would otherwise go unnoticed until the OOM killer).
+
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+use crate::checks::CheckOutcome;
+
+/// Self monitoring runs every N poll cycles
+pub const SELF_MONITOR_INTERVAL: u64 = 10;
+
+/// Resource usage of this process
+#[derive(Debug, PartialEq)]
+pub struct Usage {
+    pub memory_rss_bytes: u64,
+    pub cpu_seconds: f64,
+}
+
+pub struct SelfMonitor {
+    system: System,
+    pid: Pid,
+}
+
+impl SelfMonitor {
+    pub fn new() -> Result<Self, &'static str> {
+        Ok(SelfMonitor { system: System::new(), pid: sysinfo::get_current_pid()? })
+    }
+
+    /// Refreshes and returns the current usage
+    pub fn sample(&mut self) -> Option<Usage> {
+        let refresh = ProcessRefreshKind::nothing().with_memory().with_cpu();
+        self.system.refresh_processes_specifics(ProcessesToUpdate::Some(&[self.pid]), false, refresh);
+        let process = self.system.process(self.pid)?;
+        Some(Usage {
+            memory_rss_bytes: process.memory(),
+            cpu_seconds: process.accumulated_cpu_time() as f64 / 1000.0,
+        })
+    }
+}
+
+pub fn self_usage_outcome(usage: &Usage, max_memory_mb: u64) -> CheckOutcome {
+    let memory_mb = usage.memory_rss_bytes as f64 / (1024.0 * 1024.0);
+    if memory_mb > max_memory_mb as f64 {
+        CheckOutcome::Alert(tr!("self_memory_high", memory = format!("{:.1}", memory_mb), max = max_memory_mb))
+    } else {
+        CheckOutcome::Ok(format!("[INFO] Self usage | Memory: {:.1} MB RSS | CPU time: {:.2} s", memory_mb, usage.cpu_seconds))
+    }
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_memory_threshold() {
+        let usage = Usage { memory_rss_bytes: 300 * 1024 * 1024, cpu_seconds: 12.5 };
+        assert!(matches!(self_usage_outcome(&usage, 256), CheckOutcome::Alert(_)));
+        let usage = Usage { memory_rss_bytes: 20 * 1024 * 1024, cpu_seconds: 12.5 };
+        assert!(matches!(self_usage_outcome(&usage, 256), CheckOutcome::Ok(_)));
+    }
+
+    #[test]
+    fn test_sample_own_process() {
+        let usage = SelfMonitor::new().unwrap().sample().unwrap();
+        assert!(usage.memory_rss_bytes > 0);
+    }
+}
@@ -0,0 +1,67 @@
+
+// --- Status Snapshot File ---
+//
+// Latest poll result as JSON (SNAPSHOT_FILE) for shell scripts and cron jobs
+// that can read a file but don't speak HTTP.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::state::write_atomic;
+
+#[derive(Debug, Serialize)]
+pub struct Snapshot {
+    pub local_block: u64,
+    pub remote_block: u64,
+    pub lag: u64,
+    pub timestamp: DateTime<Utc>,
+    pub healthy: bool,
+}
+
+impl Snapshot {
+    /// Healthy means neither the lag nor the fork (local ahead) alert would fire
+    pub fn new(local: u64, remote: u64, lag_threshold: u64, ahead_threshold: u64) -> Self {
+        let lag = remote.saturating_sub(local);
+        let lead = local.saturating_sub(remote);
+        Snapshot {
+            local_block: local,
+            remote_block: remote,
+            lag,
+            timestamp: Utc::now(),
+            healthy: lag < lag_threshold && lead <= ahead_threshold,
+        }
+    }
+
+    /// Atomically replaces the snapshot file, logging on failure
+    pub fn write(&self, path: &str) {
+        let result = serde_json::to_vec(self)
+            .map_err(std::io::Error::other)
+            .and_then(|json| write_atomic(path, &json));
+        if let Err(e) = result {
+            error!("[ERROR] Failed to write snapshot file '{}': {}", path, e);
+        }
+    }
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_health() {
+        assert!(Snapshot::new(100, 101, 3, 10).healthy);
+        assert!(!Snapshot::new(100, 103, 3, 10).healthy);
+        assert!(!Snapshot::new(120, 100, 3, 10).healthy);
+        assert_eq!(Snapshot::new(105, 100, 3, 10).lag, 0);
+    }
+
+    #[test]
+    fn test_snapshot_json_fields() {
+        let json = serde_json::to_value(Snapshot::new(100, 102, 3, 10)).unwrap();
+        for field in ["local_block", "remote_block", "lag", "timestamp", "healthy"] {
+            assert!(json.get(field).is_some(), "missing field {}", field);
+        }
+    }
+}
@@ -0,0 +1,69 @@
+
+// --- Block Stall Detection ---
+//
+// A frozen node can look synced when it stopped at a recent block and the
+// remote is slow too. The tracker remembers when the local head last changed,
+// independently of the lag comparison.
+
+use chrono::{DateTime, Duration, Utc};
+
+pub struct StallTracker {
+    threshold: Duration,
+    /// Last local block seen and when it was first observed at that height
+    last: Option<(u64, DateTime<Utc>)>,
+}
+
+impl StallTracker {
+    pub fn new(threshold_secs: u64) -> Self {
+        StallTracker { threshold: Duration::seconds(threshold_secs as i64), last: None }
+    }
+
+    /// Records the local head. Returns how long it has been stuck once that
+    /// reaches the threshold
+    pub fn observe(&mut self, block: u64, now: DateTime<Utc>) -> Option<Duration> {
+        match self.last {
+            Some((last_block, since)) if last_block == block => {
+                let stalled = now - since;
+                (stalled >= self.threshold).then_some(stalled)
+            }
+            _ => {
+                self.last = Some((block, now));
+                None
+            }
+        }
+    }
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeated_block_stalls_after_threshold() {
+        let mut tracker = StallTracker::new(300);
+        let start = Utc::now();
+        let at = |secs| start + Duration::seconds(secs);
+
+        assert_eq!(tracker.observe(100, at(0)), None);
+        assert_eq!(tracker.observe(100, at(120)), None);
+        assert_eq!(tracker.observe(100, at(299)), None);
+        assert_eq!(tracker.observe(100, at(300)), Some(Duration::seconds(300)));
+        assert_eq!(tracker.observe(100, at(420)), Some(Duration::seconds(420)));
+    }
+
+    #[test]
+    fn test_advancing_block_resets_stall() {
+        let mut tracker = StallTracker::new(300);
+        let start = Utc::now();
+        let at = |secs| start + Duration::seconds(secs);
+
+        assert_eq!(tracker.observe(100, at(0)), None);
+        assert_eq!(tracker.observe(101, at(200)), None);
+        assert_eq!(tracker.observe(101, at(400)), None);
+        assert_eq!(tracker.observe(101, at(500)), Some(Duration::seconds(300)));
+        // A reorg to a lower block also counts as movement
+        assert_eq!(tracker.observe(99, at(510)), None);
+    }
+}
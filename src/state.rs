@@ -0,0 +1,114 @@
+
+// --- Persistent State ---
+//
+// Small JSON document that survives restarts (STATE_FILE). Every field is
+// optional so older files keep loading as new fields are added.
+
+use std::io;
+use std::path::Path;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct State {
+    /// Last 'eth_protocolVersion' reported by the local node
+    pub protocol_version: Option<String>,
+    /// 'admin_nodeInfo' enode and ENR of the local node, with CHECK_ENR_STABILITY
+    pub enode: Option<String>,
+    pub enr: Option<String>,
+    /// The lag, fork or outage in progress, so a restart can pick it up
+    pub incident: Option<Incident>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Incident {
+    /// Alert kind of the latest judgement, e.g. "node_lagging"
+    pub kind: String,
+    pub since: DateTime<Utc>,
+}
+
+impl State {
+    /// Loads the state file, returning an empty state if it doesn't exist yet
+    pub fn load(path: &str) -> io::Result<State> {
+        match std::fs::read_to_string(path) {
+            Ok(raw) => serde_json::from_str(&raw).map_err(io::Error::other),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(State::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Records the judgement of a poll: `kind` while unhealthy, None once
+    /// recovered. Returns true if the incident changed and needs saving; an
+    /// incident changing kind (e.g. lagging, then down) keeps its start
+    pub fn track_incident(&mut self, kind: Option<&str>, now: DateTime<Utc>) -> bool {
+        let incident = kind.map(|kind| Incident {
+            kind: kind.to_string(),
+            since: self.incident.as_ref().map_or(now, |incident| incident.since),
+        });
+        if incident == self.incident {
+            return false;
+        }
+        self.incident = incident;
+        true
+    }
+
+    /// Writes the state atomically (write to a temp file, then rename)
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        write_atomic(path, json.as_bytes())
+    }
+}
+
+/// Writes `contents` to a sibling temp file and renames it over `path`,
+/// so readers never observe a half-written file
+pub fn write_atomic(path: &str, contents: &[u8]) -> io::Result<()> {
+    let tmp = format!("{}.tmp", path);
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, Path::new(path))
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_round_trip() {
+        let path = std::env::temp_dir().join(format!("eth-alive-state-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let state = State { protocol_version: Some("0x44".to_string()), ..State::default() };
+        state.save(path).unwrap();
+        let loaded = State::load(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded.protocol_version.as_deref(), Some("0x44"));
+    }
+
+    #[test]
+    fn test_track_incident() {
+        let start = Utc::now();
+        let mut state = State::default();
+        assert!(!state.track_incident(None, start));
+        assert!(state.track_incident(Some("node_lagging"), start));
+        assert!(!state.track_incident(Some("node_lagging"), start + chrono::Duration::minutes(1)));
+        assert!(state.track_incident(Some("local_node_down"), start + chrono::Duration::minutes(2)));
+        assert_eq!(state.incident, Some(Incident { kind: "local_node_down".to_string(), since: start }));
+        assert!(state.track_incident(None, start + chrono::Duration::minutes(3)));
+        assert_eq!(state.incident, None);
+    }
+
+    #[test]
+    fn test_missing_state_file_is_empty() {
+        let state = State::load("/nonexistent/eth-alive-state.json").unwrap();
+        assert!(state.protocol_version.is_none());
+    }
+
+    #[test]
+    fn test_unknown_fields_are_ignored() {
+        let state: State = serde_json::from_str(r#"{"protocol_version": "0x41", "future_field": 1}"#).unwrap();
+        assert_eq!(state.protocol_version.as_deref(), Some("0x41"));
+    }
+}
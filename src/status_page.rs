@@ -0,0 +1,102 @@
+
+// --- Status Page Suppression ---
+//
+// Network-wide events (e.g. delayed finalization on mainnet) set off the same
+// alerts on every node. With STATUS_PAGE_URL pointing at a Statuspage-style
+// JSON API, a known ongoing incident suppresses alerts until it's over. The
+// page is polled every STATUS_REFRESH_MINUTES.
+
+use std::sync::RwLock;
+use std::time::Duration;
+
+use serde_json::Value;
+
+/// Description of the ongoing incident, None while the page reports none
+static INCIDENT: RwLock<Option<String>> = RwLock::new(None);
+
+/// The known chain incident alerts are currently suppressed for
+pub fn known_incident() -> Option<String> {
+    INCIDENT.read().unwrap().clone()
+}
+
+/// Fetches the status once, then keeps refreshing it in the background.
+/// A failed fetch keeps the previous status
+pub async fn init(url: String, refresh_minutes: u64) {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .expect("Failed to build status page HTTP client");
+
+    refresh(&client, &url).await;
+    tokio::spawn(async move {
+        let period = Duration::from_secs(refresh_minutes * 60);
+        let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        loop {
+            interval.tick().await;
+            refresh(&client, &url).await;
+        }
+    });
+}
+
+async fn refresh(client: &reqwest::Client, url: &str) {
+    let document: Value = match fetch(client, url).await {
+        Ok(document) => document,
+        Err(e) => {
+            error!("[ERROR] Failed to fetch status page {}: {}", crate::redact_url(url), e);
+            return;
+        }
+    };
+    let incident = ongoing_incident(&document);
+    let mut current = INCIDENT.write().unwrap();
+    match (&*current, &incident) {
+        (None, Some(description)) => info!("[INFO] Status page reports a known chain incident, suppressing alerts: {}", description),
+        (Some(_), None) => info!("[INFO] Status page incident is over, alerts resume"),
+        _ => {}
+    }
+    *current = incident;
+}
+
+async fn fetch(client: &reqwest::Client, url: &str) -> Result<Value, reqwest::Error> {
+    client.get(url).send().await?.error_for_status()?.json().await
+}
+
+/// The first unresolved incident (summary.json, incidents/unresolved.json), or
+/// failing that the overall status when its indicator isn't "none" (status.json)
+fn ongoing_incident(document: &Value) -> Option<String> {
+    let unresolved = document.get("incidents").and_then(Value::as_array).into_iter().flatten()
+        .filter(|incident| !matches!(incident.get("status").and_then(Value::as_str), Some("resolved" | "postmortem" | "completed")))
+        .find_map(|incident| incident.get("name").and_then(Value::as_str));
+    if let Some(name) = unresolved {
+        return Some(name.to_string());
+    }
+    let status = document.get("status")?;
+    match status.get("indicator").and_then(Value::as_str) {
+        None | Some("none") => None,
+        Some(indicator) => Some(status.get("description").and_then(Value::as_str).unwrap_or(indicator).to_string()),
+    }
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ongoing_incident() {
+        let operational = serde_json::json!({ "status": { "indicator": "none", "description": "All Systems Operational" } });
+        assert_eq!(ongoing_incident(&operational), None);
+
+        let degraded = serde_json::json!({ "status": { "indicator": "major", "description": "Delayed finalization" } });
+        assert_eq!(ongoing_incident(&degraded).as_deref(), Some("Delayed finalization"));
+
+        let summary = serde_json::json!({
+            "status": { "indicator": "minor", "description": "Minor Service Outage" },
+            "incidents": [
+                { "name": "Missed slots last week", "status": "resolved" },
+                { "name": "Mainnet finality delayed", "status": "investigating" }
+            ]
+        });
+        assert_eq!(ongoing_incident(&summary).as_deref(), Some("Mainnet finality delayed"));
+    }
+}
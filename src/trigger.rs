@@ -0,0 +1,42 @@
+
+// --- Manual Poll Trigger ---
+//
+// Listens on a Unix domain socket; any client that writes to it wakes the
+// poll loop for an immediate, out-of-schedule poll cycle.
+
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+use tokio::net::UnixListener;
+use tokio::sync::Notify;
+
+/// Binds the trigger socket and spawns the listener task.
+/// Returns the handle the poll loop waits on.
+pub fn spawn_listener(path: &str) -> std::io::Result<Arc<Notify>> {
+    // A socket left behind by a previous run would make bind() fail
+    if std::fs::metadata(path).is_ok() {
+        std::fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    let notify = Arc::new(Notify::new());
+
+    let wake = notify.clone();
+    tokio::spawn(async move {
+        loop {
+            let mut stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    error!("[ERROR] Trigger socket accept failed: {}", e);
+                    continue;
+                }
+            };
+
+            let mut buf = [0u8; 64];
+            if matches!(stream.read(&mut buf).await, Ok(n) if n > 0) {
+                info!("[INFO] Manual poll triggered via socket");
+                wake.notify_one();
+            }
+        }
+    });
+
+    Ok(notify)
+}
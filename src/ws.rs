@@ -0,0 +1,201 @@
+
+// --- WebSocket Subscriptions ---
+//
+// Long-lived 'eth_subscribe' streams over the node's WebSocket endpoint.
+// Each stream reconnects with exponential backoff when the socket drops.
+
+use std::time::Duration;
+use futures::{SinkExt, StreamExt};
+use serde_json::Value;
+use tokio::sync::watch;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::error::AppError;
+use crate::redact_url;
+use crate::rpc::{parse_hex_to_u64, RpcResult};
+
+const MAX_BACKOFF_SECS: u64 = 60;
+
+/// How the local head is learned (TRANSPORT_MODE): polling 'eth_blockNumber'
+/// over HTTP, or a 'newHeads' subscription on LOCAL_WS_URL
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransportMode {
+    Poll,
+    Ws,
+}
+
+impl std::str::FromStr for TransportMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "poll" => Ok(TransportMode::Poll),
+            "ws" => Ok(TransportMode::Ws),
+            other => Err(format!("unknown transport mode '{}'", other)),
+        }
+    }
+}
+
+/// Delay before reconnect attempt `attempt` (0-based): 1s, 2s, 4s, ... capped at 60s
+pub fn backoff(attempt: u32) -> Duration {
+    Duration::from_secs(2u64.saturating_pow(attempt).min(MAX_BACKOFF_SECS))
+}
+
+/// Opens a subscription and hands every notification payload to `on_event`.
+/// Returns when the socket closes or errors.
+async fn stream_subscription<F: FnMut(&Value)>(url: &str, params: &Value, on_event: &mut F) -> RpcResult<()> {
+    let (mut socket, _) = tokio_tungstenite::connect_async(url).await?;
+
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "eth_subscribe",
+        "params": params,
+        "id": 1
+    });
+    socket.send(Message::Text(request.to_string())).await?;
+
+    while let Some(message) = socket.next().await {
+        let text = match message? {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let body: Value = match serde_json::from_str(&text) {
+            Ok(body) => body,
+            Err(_) => continue,
+        };
+
+        if let Some(err) = body.get("error") {
+            let code = err.get("code").and_then(Value::as_i64).unwrap_or(0);
+            let err_msg = err.get("message").and_then(|m| m.as_str()).unwrap_or("Unknown RPC error");
+            return Err(AppError::RpcError { code, message: err_msg.to_string() });
+        }
+        if let Some(result) = body.pointer("/params/result") {
+            on_event(result);
+        }
+    }
+
+    Err(AppError::WsError("closed".to_string()))
+}
+
+/// Keeps a subscription alive forever, reconnecting with exponential backoff.
+/// `on_disconnect` runs each time the stream is lost.
+pub async fn subscribe_forever<F: FnMut(&Value), D: FnMut()>(url: &str, params: Value, mut on_event: F, mut on_disconnect: D) {
+    let mut attempt = 0;
+    loop {
+        let started = std::time::Instant::now();
+        if let Err(e) = stream_subscription(url, &params, &mut on_event).await {
+            on_disconnect();
+            // A connection that stayed up for a while resets the backoff
+            if started.elapsed() > Duration::from_secs(MAX_BACKOFF_SECS) {
+                attempt = 0;
+            }
+            let delay = backoff(attempt);
+            warn!("[WARN] WebSocket {} disconnected: {} | Reconnecting in {}s", redact_url(url), e, delay.as_secs());
+            tokio::time::sleep(delay).await;
+            attempt = attempt.saturating_add(1);
+        }
+    }
+}
+
+// --- Log Subscriptions ---
+
+/// Builds the 'eth_subscribe' params for a logs filter.
+/// Topics are alternatives for topic0 (any of them matches).
+pub fn logs_params(addresses: &[String], topics: &[String]) -> Value {
+    serde_json::json!(["logs", log_filter(addresses, topics)])
+}
+
+/// Log filter object matching any of `addresses` and, if given, any of the topic0 values
+pub fn log_filter(addresses: &[String], topics: &[String]) -> Value {
+    let mut filter = serde_json::json!({ "address": addresses });
+    if !topics.is_empty() {
+        filter["topics"] = serde_json::json!([topics]);
+    }
+    filter
+}
+
+/// Extracts (address, topic0) from a log notification
+pub fn parse_log(log: &Value) -> Option<(String, String)> {
+    let address = log.get("address")?.as_str()?.to_string();
+    let topic0 = log.pointer("/topics/0").and_then(|t| t.as_str()).unwrap_or("(anonymous)").to_string();
+    Some((address, topic0))
+}
+
+/// Spawns a task that logs every matching contract event
+pub fn spawn_log_watcher(url: String, addresses: Vec<String>, topics: Vec<String>) {
+    tokio::spawn(async move {
+        let params = logs_params(&addresses, &topics);
+        subscribe_forever(&url, params, |log| {
+            if let Some((address, topic0)) = parse_log(log) {
+                info!("[EVENT] Contract {} emitted event {}", address, topic0);
+            }
+        }, || {}).await;
+    });
+}
+
+// --- Head Subscription ---
+
+/// Block number of a 'newHeads' notification
+pub fn parse_head_number(header: &Value) -> Option<u64> {
+    parse_hex_to_u64(header.get("number")?.as_str()?).ok()
+}
+
+/// Spawns a 'newHeads' subscription. The receiver holds the latest head, and
+/// None while the socket is down (or before the first head arrived).
+pub fn spawn_head_watcher(url: String) -> watch::Receiver<Option<u64>> {
+    let (sender, receiver) = watch::channel(None);
+    tokio::spawn(async move {
+        subscribe_forever(&url, serde_json::json!(["newHeads"]), |header| {
+            if let Some(number) = parse_head_number(header) {
+                sender.send_replace(Some(number));
+            }
+        }, || {
+            // Only the first failed attempt is news to the receiver
+            sender.send_if_modified(|head| head.take().is_some());
+        }).await;
+    });
+    receiver
+}
+
+
+// --- TESTS ---
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        assert_eq!(backoff(0), Duration::from_secs(1));
+        assert_eq!(backoff(3), Duration::from_secs(8));
+        assert_eq!(backoff(10), Duration::from_secs(MAX_BACKOFF_SECS));
+        assert_eq!(backoff(u32::MAX), Duration::from_secs(MAX_BACKOFF_SECS));
+    }
+
+    #[test]
+    fn test_logs_params() {
+        let params = logs_params(&["0xabc".to_string()], &["0xt1".to_string(), "0xt2".to_string()]);
+        assert_eq!(params, serde_json::json!(["logs", { "address": ["0xabc"], "topics": [["0xt1", "0xt2"]] }]));
+
+        let params = logs_params(&["0xabc".to_string()], &[]);
+        assert_eq!(params, serde_json::json!(["logs", { "address": ["0xabc"] }]));
+    }
+
+    #[test]
+    fn test_parse_head_number() {
+        let header = serde_json::json!({ "number": "0x1b4", "hash": "0xabc", "parentHash": "0xdef" });
+        assert_eq!(parse_head_number(&header), Some(436));
+        assert_eq!(parse_head_number(&serde_json::json!({ "hash": "0xabc" })), None);
+        assert_eq!("ws".parse::<TransportMode>(), Ok(TransportMode::Ws));
+        assert!("grpc".parse::<TransportMode>().is_err());
+    }
+
+    #[test]
+    fn test_parse_log() {
+        let log = serde_json::json!({ "address": "0xabc", "topics": ["0xddf2", "0x01"], "data": "0x" });
+        assert_eq!(parse_log(&log), Some(("0xabc".to_string(), "0xddf2".to_string())));
+
+        let anonymous = serde_json::json!({ "address": "0xabc", "topics": [] });
+        assert_eq!(parse_log(&anonymous).unwrap().1, "(anonymous)");
+    }
+}